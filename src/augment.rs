@@ -0,0 +1,254 @@
+//! Random transformations of the examples of every sampled batch, so that learning on few examples overfits less.
+//!
+//! The geometric ones work on the images of the dataset as its shape gives them, after their preprocessing: the
+//! transformed images keep their width, height and channels, and what comes from outside of them is their background.
+
+use std::cmp;
+use std::fmt;
+use std::str::FromStr;
+use rand::Rng;
+use rand::distributions::{Gamma, IndependentSample, Normal};
+use preprocess::Image;
+use error::{Error, Result};
+
+/// How strongly the examples are transformed, each transformation being off at 0, the default.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Augmentation {
+    /// Largest shift of the images, as a fraction of their width and height.
+    pub translate: f64,
+    /// Largest rotation of the images, in degrees.
+    pub rotate: f64,
+    /// Largest relative change of the size of the images.
+    pub scale: f64,
+    /// Largest horizontal shear of the images, in pixels per row.
+    pub shear: f64,
+    /// Largest displacement of the elastic distortion, in pixels before smoothing.
+    pub elastic_alpha: f64,
+    /// Standard deviation in pixels of the gaussian the random displacements of the elastic distortion are smoothed
+    /// with.
+    pub elastic_sigma: f64,
+    /// Standard deviation of the gaussian noise added to every value.
+    pub noise: f64,
+    /// Probability that a random rectangle of up to half the width and height of an image is set to 0.
+    pub erase: f64,
+    /// Parameter of the beta distribution of the weights with which every example and its target are mixed with
+    /// another one of the batch.
+    pub mixup: f64,
+}
+
+/// Smoothing of the elastic distortion if only its strength is given.
+const DEFAULT_ELASTIC_SIGMA: f64 = 4.0;
+
+/// A uniformly random number in `[-max, max)`, 0 if `max` is 0.
+fn uniform<R: Rng>(rng: &mut R, max: f64) -> f64 {
+    if max > 0.0 { rng.gen_range(-max, max) } else { 0.0 }
+}
+
+/// Blurs the `w`x`h` `field` with a gaussian of standard deviation `sigma`, one direction at a time.
+fn blur(field: &[f64], w: usize, h: usize, sigma: f64) -> Vec<f64> {
+    let radius = (3.0 * sigma).ceil() as isize;
+    let kernel: Vec<f64> = (-radius..radius + 1).map(|d| (-(d * d) as f64 / (2.0 * sigma * sigma)).exp()).collect();
+    let sum: f64 = kernel.iter().sum();
+    let pass = |field: &[f64], step: (isize, isize)| -> Vec<f64> {
+        (0..w * h).map(|i| {
+            let (x, y) = ((i % w) as isize, (i / w) as isize);
+            kernel.iter().enumerate().map(|(k, weight)| {
+                let d = k as isize - radius;
+                // the edges are repeated
+                let sx = cmp::min(cmp::max(x + d * step.0, 0), w as isize - 1) as usize;
+                let sy = cmp::min(cmp::max(y + d * step.1, 0), h as isize - 1) as usize;
+                weight * field[sy * w + sx]
+            }).sum::<f64>() / sum
+        }).collect()
+    };
+    pass(&pass(field, (1, 0)), (0, 1))
+}
+
+impl Augmentation {
+    /// Whether no example is changed.
+    pub fn is_none(&self) -> bool {
+        *self == Augmentation::default()
+    }
+
+    fn is_geometric(&self) -> bool {
+        self.translate != 0.0 || self.rotate != 0.0 || self.scale != 0.0 || self.shear != 0.0 ||
+            self.elastic_alpha != 0.0 || self.erase != 0.0
+    }
+
+    /// Fails if the augmentation needs images but the inputs of the shape `shape` are not ones.
+    pub fn check(&self, shape: &[usize]) -> Result<()> {
+        if self.is_geometric() && shape.len() != 2 && shape.len() != 3 {
+            return Err(Error::argument("augmentation", format!("the inputs of shape {:?} are not images that can be \
+                                                                 moved, distorted or erased", shape)));
+        }
+        Ok(())
+    }
+
+    /// Transforms every input of a batch of inputs of the shape `shape`, and mixes them up with their `targets`.
+    pub fn apply<R: Rng>(&self, shape: &[usize], inputs: &mut [Vec<f64>], targets: &mut [Vec<f64>], rng: &mut R) {
+        if self.is_geometric() {
+            let (height, width) = (shape[0] as u32, shape[1] as u32);
+            let channels = if shape.len() == 3 { shape[2] } else { 1 };
+            for input in inputs.iter_mut() {
+                let image = Image { width: width, height: height, channels: channels, pixels: input.clone() };
+                *input = self.transform(image, rng).pixels;
+            }
+        }
+
+        if self.noise > 0.0 {
+            let normal = Normal::new(0.0, self.noise);
+            for x in inputs.iter_mut().flat_map(|input| input.iter_mut()) {
+                *x += normal.ind_sample(rng);
+            }
+        }
+
+        if self.mixup > 0.0 && inputs.len() > 1 {
+            let gamma = Gamma::new(self.mixup, 1.0);
+            let (original_inputs, original_targets) = (inputs.to_vec(), targets.to_vec());
+            for i in 0..inputs.len() {
+                let j = rng.gen_range(0, inputs.len());
+                // a beta distributed weight
+                let (a, b) = (gamma.ind_sample(rng), gamma.ind_sample(rng));
+                let lambda = if a + b > 0.0 { a / (a + b) } else { 1.0 };
+                for (x, &other) in inputs[i].iter_mut().zip(&original_inputs[j]) {
+                    *x = lambda * *x + (1.0 - lambda) * other;
+                }
+                for (t, &other) in targets[i].iter_mut().zip(&original_targets[j]) {
+                    *t = lambda * *t + (1.0 - lambda) * other;
+                }
+            }
+        }
+    }
+
+    /// Moves, distorts and erases `image` at random.
+    fn transform<R: Rng>(&self, mut image: Image, rng: &mut R) -> Image {
+        let (w, h) = (image.width as f64, image.height as f64);
+        let (cx, cy) = ((w - 1.0) / 2.0, (h - 1.0) / 2.0);
+
+        if self.translate != 0.0 || self.rotate != 0.0 || self.scale != 0.0 || self.shear != 0.0 {
+            let angle = uniform(rng, self.rotate).to_radians();
+            let scale = 1.0 + uniform(rng, self.scale);
+            let shear = uniform(rng, self.shear);
+            let (tx, ty) = (uniform(rng, self.translate) * w, uniform(rng, self.translate) * h);
+            // the shear, then the scaling, then the rotation around the center: m = scale * rotation * shear
+            let (sin, cos) = angle.sin_cos();
+            let m = [[scale * cos, scale * (cos * shear - sin)], [scale * sin, scale * (sin * shear + cos)]];
+            let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+            image = image.warp(image.width, image.height, |x, y| {
+                let (u, v) = (x - cx - tx, y - cy - ty);
+                ((m[1][1] * u - m[0][1] * v) / det + cx, (m[0][0] * v - m[1][0] * u) / det + cy))
+            });
+        }
+
+        if self.elastic_alpha != 0.0 {
+            let (width, height) = (image.width as usize, image.height as usize);
+            let mut field = || -> Vec<f64> {
+                let random: Vec<f64> = (0..width * height).map(|_| uniform(rng, 1.0)).collect();
+                blur(&random, width, height, self.elastic_sigma).into_iter().map(|d| d * self.elastic_alpha).collect()
+            };
+            let (dx, dy) = (field(), field());
+            image = image.warp(image.width, image.height, |x, y| {
+                let i = y as usize * width + x as usize;
+                (x + dx[i], y + dy[i])
+            });
+        }
+
+        if self.erase > 0.0 && rng.gen::<f64>() < self.erase {
+            let (width, height) = (image.width as usize, image.height as usize);
+            let erased_w = rng.gen_range(1, cmp::max(width / 2, 1) + 1);
+            let erased_h = rng.gen_range(1, cmp::max(height / 2, 1) + 1);
+            let left = rng.gen_range(0, width - erased_w + 1);
+            let top = rng.gen_range(0, height - erased_h + 1);
+            for y in top..top + erased_h {
+                for x in left..left + erased_w {
+                    for c in 0..image.channels {
+                        image.pixels[(y * width + x) * image.channels + c] = 0.0;
+                    }
+                }
+            }
+        }
+        image
+    }
+}
+
+/// Parses comma-separated `NAME=VALUE` settings: `translate`, `rotate`, `scale`, `shear`, `elastic=ALPHA[:SIGMA]`,
+/// `noise`, `erase` and `mixup`, like `rotate=10,elastic=8:4,mixup=0.2`. `none` is no augmentation.
+impl FromStr for Augmentation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Augmentation> {
+        let mut augmentation = Augmentation::default();
+        for setting in s.split(',').map(|s| s.trim()).filter(|s| !s.is_empty() && *s != "none") {
+            let i = setting.find('=')
+                .ok_or_else(|| Error::argument("augmentation", format!("{:?} is not NAME=VALUE", setting)))?;
+            let (name, value) = (&setting[..i], &setting[i + 1..]);
+            let parse = |value: &str| -> Result<f64> {
+                match value.parse::<f64>() {
+                    Ok(x) if x >= 0.0 => Ok(x),
+                    _ => Err(Error::argument("augmentation",
+                                             format!("{} {:?} is not a non-negative number", name, value))),
+                }
+            };
+            match name {
+                "translate" => augmentation.translate = parse(value)?,
+                "rotate" => augmentation.rotate = parse(value)?,
+                "scale" => augmentation.scale = parse(value)?,
+                "shear" => augmentation.shear = parse(value)?,
+                "elastic" => {
+                    let mut parts = value.splitn(2, ':');
+                    augmentation.elastic_alpha = parse(parts.next().unwrap())?;
+                    augmentation.elastic_sigma = match parts.next() {
+                        Some(sigma) => parse(sigma)?,
+                        None => DEFAULT_ELASTIC_SIGMA,
+                    };
+                    if augmentation.elastic_sigma == 0.0 {
+                        return Err(Error::argument("augmentation", "the elastic distortion needs a smoothing above 0"));
+                    }
+                }
+                "noise" => augmentation.noise = parse(value)?,
+                "erase" => augmentation.erase = parse(value)?.min(1.0),
+                "mixup" => augmentation.mixup = parse(value)?,
+                _ => return Err(Error::argument("augmentation", format!("unknown setting {:?}", name))),
+            }
+        }
+        Ok(augmentation)
+    }
+}
+
+impl fmt::Display for Augmentation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_none() {
+            return write!(f, "none");
+        }
+        let settings = [("translate", self.translate), ("rotate", self.rotate), ("scale", self.scale),
+                        ("shear", self.shear), ("noise", self.noise), ("erase", self.erase), ("mixup", self.mixup)];
+        let mut written: Vec<String> = settings.iter().filter(|s| s.1 != 0.0).map(|s| format!("{}={}", s.0, s.1))
+            .collect();
+        if self.elastic_alpha != 0.0 {
+            written.push(format!("elastic={}:{}", self.elastic_alpha, self.elastic_sigma));
+        }
+        write!(f, "{}", written.join(","))
+    }
+}
+
+#[test]
+fn test_augmentation() {
+    use rand::{SeedableRng, StdRng};
+    let augmentation: Augmentation = "rotate=10, elastic=2, erase=1, mixup=0.2".parse().unwrap();
+    assert_eq!((augmentation.rotate, augmentation.elastic_sigma), (10.0, DEFAULT_ELASTIC_SIGMA));
+    assert_eq!(augmentation.to_string().parse::<Augmentation>().unwrap(), augmentation);
+    assert!("rotate=-1".parse::<Augmentation>().is_err());
+    assert!(augmentation.check(&[4]).is_err());
+
+    // the same seed transforms the same way, and the shape is kept
+    let run = || {
+        let mut inputs = vec![vec![0.5; 12], vec![1.0; 12]];
+        let mut targets = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        augmentation.apply(&[3, 4], &mut inputs, &mut targets, &mut StdRng::from_seed(&[7][..]));
+        (inputs, targets)
+    };
+    let (inputs, targets) = run();
+    assert_eq!(run(), (inputs.clone(), targets.clone()));
+    assert!(inputs.iter().all(|input| input.len() == 12));
+    assert!(targets.iter().all(|t| (t[0] + t[1] - 1.0).abs() < 1e-12));
+}
@@ -1,95 +1,122 @@
+//! Sparse autoencoders.
+
 use multilayer_perceptron::{MultilayerPerceptron, SparsityParams};
 use activation_func::*;
 use rand;
-use img::get_img_and_label;
-use std::fs;
+#[cfg(test)]
 use img;
-use clap;
-use mnist;
 use rayon::prelude::*;
+use trainer::EpochStats;
+use dataset::{self, Dataset};
+use util;
+use error::{Error, Result};
+
+/// Settings of an autoencoder learning run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AutoencoderConfig {
+    pub learning_rate: f64,
+    pub hidden_neurons: usize,
+    /// Desired average activation of the hidden neurons.
+    pub sparsity: f64,
+    /// Weight of the sparsity penalty.
+    pub penalty_factor: f64,
+    /// Fraction of the dataset that is used during each epoch.
+    pub sample_ratio: f64,
+    pub epochs: u64,
+    /// Seed of the initial weights and the samples, random if not set.
+    pub seed: Option<usize>,
+}
 
-pub fn run(matches: &clap::ArgMatches<'static>) -> Result<(), &'static str> {
-    let epoch_count: u64 = matches.value_of("epoch-count").and_then(|x| x.parse().ok()).unwrap_or(5000);
-    let is_mnist = matches.is_present("mnist");
-    let sample_ratio: f64 = matches.value_of("sample").and_then(|x| x.parse().ok()).unwrap_or(0.01);
-
-    let (w, h, images_own) = if is_mnist {
-        let images_own: Vec<_> = mnist::MnistDigits::default_training_set().unwrap();
-        (28, 28, images_own)
-    } else {
-        let paths: Vec<_> = fs::read_dir("res/Sieci Neuronowe").unwrap().map(|p| p.unwrap().path()).collect();
-        let images_own: Vec<_> = paths.iter().map(|p| get_img_and_label(p)).collect();
-        (7, 10, images_own)
-    };
+impl Default for AutoencoderConfig {
+    fn default() -> Self {
+        AutoencoderConfig {
+            learning_rate: 0.3,
+            hidden_neurons: 25,
+            sparsity: 0.05,
+            penalty_factor: 0.8,
+            sample_ratio: 0.01,
+            epochs: 5000,
+            seed: None,
+        }
+    }
+}
 
-    let images: Vec<_> = images_own.iter().map(|&(ref x, _)| (&x[..], &x[..])).collect();
-    let mut autoencoder = MultilayerPerceptron::new(0.3, images[0].0.len(), &[
-        (matches.value_of("hidden-neurons").and_then(|x| x.parse().ok()).unwrap_or(25), Sigmoid(1.0).into()),
-        (images[0].0.len(), Sigmoid(10.0).into())
-    ]);
+/// Creates an untrained autoencoder for inputs of length `inputs`.
+pub fn new_net(config: &AutoencoderConfig, inputs: usize) -> MultilayerPerceptron {
+    let mut autoencoder = MultilayerPerceptron::with_rng(config.learning_rate, inputs, &[
+        (config.hidden_neurons, Sigmoid(1.0).into()),
+        (inputs, Sigmoid(10.0).into())
+    ], &mut util::rng(config.seed, 0));
 
     autoencoder.sparsity_params = Some(SparsityParams {
-        sparsity: matches.value_of("sparsity").and_then(|x| x.parse().ok()).unwrap_or(0.05),
-        penalty_factor: matches.value_of("penalty-factor").and_then(|x| x.parse().ok()).unwrap_or(0.8),
+        sparsity: config.sparsity,
+        penalty_factor: config.penalty_factor,
     });
 
-    let sample_size = (images.len() as f64 * sample_ratio) as usize;
-
-    use pbr::ProgressBar;
-    let mut pbr = ProgressBar::new(epoch_count);
+    autoencoder
+}
 
-    for i in 0..epoch_count {
-        let sample: Vec<(&[f64], &[f64])> = rand::sample(&mut rand::thread_rng(), images.iter().cloned(), sample_size);
-        autoencoder.learn_batch(&sample);
-        pbr.inc();
+/// Learns `net` to reproduce the inputs of `data`, calling `on_epoch` after every epoch and stopping at its first
+/// error. The labels are ignored.
+pub fn train<D, F>(config: &AutoencoderConfig, net: &mut MultilayerPerceptron, data: &D, mut on_epoch: F) -> Result<()>
+    where D: Dataset + ?Sized, F: FnMut(&EpochStats, &MultilayerPerceptron) -> Result<()>
+{
+    let sample_size = (data.len() as f64 * config.sample_ratio) as usize;
+    if sample_size == 0 {
+        return Err(Error::argument("sample ratio", format!("{} of {} images is an empty sample",
+                                                           config.sample_ratio, data.len())));
     }
 
-    let outs: Vec<_> = images.iter().map(|i| autoencoder.feed_forward(&i.0[..]).0.at).collect();
-    for (ii, i) in outs.into_iter().enumerate() {
-        if is_mnist && ii % 1000 != 0 {
-            continue;
-        }
-        use std::path::Path;
-        let name = format!("out{}.png", ii);
-        let p = Path::new("autoencoded").join(&name);
-        img::save(&i, w, h, p);
+    let mut rng = util::rng(config.seed, 1);
+    for i in 0..config.epochs {
+        let indices = rand::sample(&mut rng, 0..data.len(), sample_size);
+        let images = dataset::load_batch(data, &indices)?;
+        let sample: Vec<(&[f64], &[f64])> = images.iter().map(|&(ref x, _)| (&x[..], &x[..])).collect();
+        let batch = net.learn_batch(&sample);
+        on_epoch(&EpochStats { epoch: i, learning_rate: net.learning_rate, batch: batch }, net)?;
     }
 
-    extract_features(&autoencoder, w, h);
-
-    let error = calc_err(&autoencoder, &images);
-    pbr.finish_println(&format!("error: {0:>5.2}  ", error));
-
     Ok(())
 }
 
-fn extract_features(net: &MultilayerPerceptron, w: u32, h: u32) {
-    use na::{Column, Iterable, Row};
+/// Normalized outgoing weights of the hidden neuron `i`, which can be saved as an image of the feature it detects.
+pub fn feature(net: &MultilayerPerceptron, i: usize) -> Vec<f64> {
+    use na::{Iterable, Row};
     let ref hidden_layer = net.layers[1];
+    let row = hidden_layer.weights.row(i);
+    let sum: f64 = row.iter().map(|&x| x * x).sum();
+    let l: f64 = sum.sqrt();
+    (0..hidden_layer.weights.ncols()).map(|j|
+        1.0 - (hidden_layer.weights[(i, j)] / l)
+    ).collect()
+}
 
-    for i in 0..hidden_layer.weights.nrows() {
-        let row = hidden_layer.weights.row(i);
-        let sum: f64 = row.iter().map(|&x| x * x).sum();
-        let l: f64 = sum.sqrt();
-        let xs: Vec<_> = (0..hidden_layer.weights.ncols()).map(|j|
-            1.0 - (hidden_layer.weights[(i, j)] / l)
-        ).collect();
-        let name = format!("autoencoded/feature{}.png", i);
-        img::save(&xs, w, h, &name);
-    }
+/// Features of all the hidden neurons, see `feature`.
+pub fn features(net: &MultilayerPerceptron) -> Vec<Vec<f64>> {
+    (0..net.layers[1].weights.nrows()).map(|i| feature(net, i)).collect()
 }
 
-fn calc_err(network: &MultilayerPerceptron, data: &[(&[f64], &[f64])]) -> f64 {
+/// Number of images read into memory at once by `reconstruction_error`.
+const CHUNK_SIZE: usize = 1024;
+
+/// Average distance between the inputs of `data` and their reconstructions by `network`.
+pub fn reconstruction_error<D: Dataset + ?Sized>(network: &MultilayerPerceptron, data: &D) -> Result<f64> {
     use na::{DVector, norm};
     use std::iter::FromIterator;
-    let sum: f64 = data.par_iter().map(|&(ref img, ref label)| {
-        let out = network.feed_forward(img).0;
-        let label = DVector::from_iter(label.iter().cloned());
-        norm(&(label - out))
-    }).sum();
-    sum / (data.len() as f64)
+    let indices: Vec<usize> = (0..data.len()).collect();
+    let mut sum = 0.0;
+    for chunk in indices.chunks(CHUNK_SIZE) {
+        let images = dataset::load_batch(data, chunk)?;
+        sum += images.par_iter().map(|&(ref img, _)| {
+            let out = network.feed_forward(img).0;
+            let img = DVector::from_iter(img.iter().cloned());
+            norm(&(img - out))
+        }).sum();
+    }
+    Ok(sum / (data.len() as f64))
 }
 
+#[cfg(test)]
 macro_rules! trace {
     ($e:expr) => { {println!(concat!(stringify!($e), " = {:?}"), $e)} };
 }
@@ -97,8 +124,8 @@ macro_rules! trace {
 #[test]
 fn autoencoder_raport() {
     //// LOAD IMAGES ////
-    let paths: Vec<_> = fs::read_dir("res/Sieci Neuronowe").unwrap().map(|p| p.unwrap().path()).collect();
-    let images_own: Vec<_> = paths.iter().map(|p| get_img_and_label(p)).collect();
+    let (dir, _) = dataset::ImageDir::open("res/Sieci Neuronowe").unwrap();
+    let images_own: Vec<_> = dataset::stream(&dir).collect::<Result<_>>().unwrap();
     let images: Vec<_> = images_own.iter().map(|&(ref x, _)| (&x[..], &x[..])).collect();
 
     let autoencoder = MultilayerPerceptron::new(0.3, images[0].0.len(), &[
@@ -122,7 +149,7 @@ fn autoencoder_raport() {
         println!("next:");
         trace!((i, sparsity, penalty_factor));
 
-        use std::fs::{self, DirBuilder};
+        use std::fs::DirBuilder;
 
         let path = format!("raport/{}", i);
         DirBuilder::new()
@@ -145,25 +172,13 @@ fn autoencoder_raport() {
             autoencoder.learn_batch(&sample);
 
             if j % 100 == 0 {
-                let error = calc_err(&autoencoder, &images);
+                let error = reconstruction_error(&autoencoder, &images_own).unwrap();
                 println!("{:.2}", error);
 
-                dump_feature(&autoencoder, feature_idx, 7, 10, &format!("{}/feat_epoch_{:05}.png", &path, j));
+                img::save(&feature(&autoencoder, feature_idx), 7, 10, &format!("{}/feat_epoch_{:05}.png", &path, j)).unwrap();
                 let image = autoencoder.feed_forward(&images[img_idx].0[..]).0.at;
-                img::save(&image, 7, 10, &format!("{}/img_epoch_{:05}.png", &path, j))
+                img::save(&image, 7, 10, &format!("{}/img_epoch_{:05}.png", &path, j)).unwrap()
             }
         }
     }
 }
-
-fn dump_feature(net: &MultilayerPerceptron, i: usize, w: u32, h: u32, name: &str) {
-    use na::{Column, Iterable, Row};
-    let ref hidden_layer = net.layers[1];
-    let row = hidden_layer.weights.row(i);
-    let sum: f64 = row.iter().map(|&x| x * x).sum();
-    let l: f64 = sum.sqrt();
-    let xs: Vec<_> = (0..hidden_layer.weights.ncols()).map(|j|
-        1.0 - (hidden_layer.weights[(i, j)] / l)
-    ).collect();
-    img::save(&xs, w, h, &name);
-}
\ No newline at end of file
@@ -0,0 +1,85 @@
+//! Learning from datasets whose labels are unevenly represented: weights of the examples by their label, and a sampler
+//! drawing every label equally often.
+
+use std::collections::{BTreeMap, HashMap};
+use rand::Rng;
+use dataset::Dataset;
+
+/// How much the examples of every label count when learning.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ClassWeighting {
+    /// Every example counts the same.
+    Unweighted,
+    /// The examples of a label count inversely to their number.
+    InverseFrequency,
+    /// The examples of a label count inversely to their effective number `(1 - beta^n) / (1 - beta)`, which grows
+    /// slower than their number `n` as the new examples overlap with the ones already there. `beta` is in [0, 1),
+    /// 0 being unweighted and values close to 1 close to the inverse frequency.
+    EffectiveNumber(f64),
+}
+
+impl ClassWeighting {
+    /// The weight of every label of `data`, scaled so that the mean weight of the examples is 1. `None` if the
+    /// examples are unweighted.
+    pub fn weights<D: Dataset + ?Sized>(&self, data: &D) -> Option<HashMap<String, f64>> {
+        if let ClassWeighting::Unweighted = *self {
+            return None;
+        }
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for i in 0..data.len() {
+            *counts.entry(data.label(i)).or_insert(0) += 1;
+        }
+
+        let weight = |n: usize| match *self {
+            ClassWeighting::Unweighted => 1.0,
+            ClassWeighting::InverseFrequency => 1.0 / n as f64,
+            ClassWeighting::EffectiveNumber(beta) => (1.0 - beta) / (1.0 - beta.powi(n as i32)),
+        };
+        let total: f64 = counts.values().map(|&n| n as f64 * weight(n)).sum();
+        let scale = data.len() as f64 / total;
+        Some(counts.into_iter().map(|(label, n)| (label.to_string(), scale * weight(n))).collect())
+    }
+}
+
+/// Draws the examples of a dataset so that every label is drawn equally often, whatever its number of examples.
+pub struct BalancedSampler {
+    /// Indices of the examples of every label.
+    by_label: Vec<Vec<usize>>,
+}
+
+impl BalancedSampler {
+    pub fn new<D: Dataset + ?Sized>(data: &D) -> BalancedSampler {
+        let mut by_label: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for i in 0..data.len() {
+            by_label.entry(data.label(i)).or_insert_with(Vec::new).push(i);
+        }
+        BalancedSampler { by_label: by_label.into_iter().map(|(_, indices)| indices).collect() }
+    }
+
+    /// Draws `amount` examples, each of a label picked at random and then one of its examples at random, so that the
+    /// examples of rare labels can be drawn several times.
+    pub fn sample<R: Rng>(&self, rng: &mut R, amount: usize) -> Vec<usize> {
+        (0..amount).map(|_| {
+            let indices = &self.by_label[rng.gen_range(0, self.by_label.len())];
+            indices[rng.gen_range(0, indices.len())]
+        }).collect()
+    }
+}
+
+#[test]
+fn test_class_weights() {
+    let examples: Vec<(Vec<f64>, String)> = vec![(vec![0.0], "a".into()), (vec![0.0], "a".into()),
+                                                 (vec![0.0], "a".into()), (vec![0.0], "b".into())];
+    assert_eq!(ClassWeighting::Unweighted.weights(&examples), None);
+    let inverse = ClassWeighting::InverseFrequency.weights(&examples).unwrap();
+    assert!((inverse["a"] - 2.0 / 3.0).abs() < 1e-12);
+    assert!((inverse["b"] - 2.0).abs() < 1e-12);
+    let effective = ClassWeighting::EffectiveNumber(0.5).weights(&examples).unwrap();
+    assert!(effective["a"] < effective["b"] && effective["b"] < inverse["b"]);
+    assert!((3.0 * effective["a"] + effective["b"] - 4.0).abs() < 1e-12);
+
+    use rand::{SeedableRng, StdRng};
+    let sample = BalancedSampler::new(&examples).sample(&mut StdRng::from_seed(&[1][..]), 1000);
+    let b = sample.iter().filter(|&&i| i == 3).count();
+    assert!(b > 400 && b < 600);
+}
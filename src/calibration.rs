@@ -0,0 +1,167 @@
+//! How well the scores of a classifier match its actual accuracy, and temperature scaling to improve that.
+
+use inference::{softmax, Classifier};
+use metrics::Outcome;
+use dataset::{self, Dataset};
+use error::{Error, Result};
+
+/// Outcomes whose confidence fell into `[lower, upper)`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReliabilityBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    /// Mean confidence of the outcomes in the bin.
+    pub confidence: f64,
+    /// Fraction of the outcomes in the bin that were correct.
+    pub accuracy: f64,
+}
+
+/// Data of a reliability diagram: `bins` equally wide confidence bins.
+pub fn reliability_diagram(outcomes: &[Outcome], bins: usize) -> Vec<ReliabilityBin> {
+    let mut counts = vec![0; bins];
+    let mut confidences = vec![0.0; bins];
+    let mut corrects = vec![0; bins];
+
+    for o in outcomes {
+        let bin = ::std::cmp::min((o.confidence() * bins as f64) as usize, bins - 1);
+        counts[bin] += 1;
+        confidences[bin] += o.confidence();
+        if o.is_correct() {
+            corrects[bin] += 1;
+        }
+    }
+
+    (0..bins).map(|i| ReliabilityBin {
+        lower: i as f64 / bins as f64,
+        upper: (i + 1) as f64 / bins as f64,
+        count: counts[i],
+        confidence: if counts[i] == 0 { 0.0 } else { confidences[i] / counts[i] as f64 },
+        accuracy: if counts[i] == 0 { 0.0 } else { corrects[i] as f64 / counts[i] as f64 },
+    }).collect()
+}
+
+/// Mean difference between confidence and accuracy of the bins, weighted by their sizes.
+pub fn expected_calibration_error(diagram: &[ReliabilityBin]) -> f64 {
+    let total: usize = diagram.iter().map(|b| b.count).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    diagram.iter()
+        .map(|b| b.count as f64 * (b.confidence - b.accuracy).abs())
+        .sum::<f64>() / total as f64
+}
+
+/// Mean squared difference between the scores and the one-hot encoded true labels.
+pub fn brier_score(outcomes: &[Outcome]) -> f64 {
+    if outcomes.is_empty() {
+        return 0.0;
+    }
+    outcomes.iter().map(|o| {
+        o.ranking.iter().map(|&(ref label, score)| {
+            let target = if *label == o.truth { 1.0 } else { 0.0 };
+            (score - target) * (score - target)
+        }).sum::<f64>()
+    }).sum::<f64>() / outcomes.len() as f64
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub expected_calibration_error: f64,
+    pub brier_score: f64,
+    pub reliability_diagram: Vec<ReliabilityBin>,
+}
+
+impl Calibration {
+    pub fn new(outcomes: &[Outcome], bins: usize) -> Calibration {
+        let diagram = reliability_diagram(outcomes, bins);
+        Calibration {
+            expected_calibration_error: expected_calibration_error(&diagram),
+            brier_score: brier_score(outcomes),
+            reliability_diagram: diagram,
+        }
+    }
+}
+
+/// Mean negative log-likelihood of the `truths` under softmax of `outputs` divided by `temperature`.
+fn nll(outputs: &[Vec<f64>], truths: &[usize], temperature: f64) -> f64 {
+    outputs.iter().zip(truths).map(|(out, &truth)| {
+        let scaled: Vec<f64> = out.iter().map(|x| x / temperature).collect();
+        -softmax(&scaled)[truth].max(1e-300).ln()
+    }).sum::<f64>() / outputs.len() as f64
+}
+
+/// Finds the temperature minimizing the negative log-likelihood of the true labels, using golden-section search
+/// over its logarithm.
+pub fn fit_temperature(outputs: &[Vec<f64>], truths: &[usize]) -> f64 {
+    let phi = (5f64.sqrt() - 1.0) / 2.0;
+    let f = |log_t: f64| nll(outputs, truths, log_t.exp());
+    let (mut a, mut b) = ((1e-3f64).ln(), (1e3f64).ln());
+    let mut c = b - phi * (b - a);
+    let mut d = a + phi * (b - a);
+    let (mut fc, mut fd) = (f(c), f(d));
+
+    for _ in 0..100 {
+        if fc < fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - phi * (b - a);
+            fc = f(c);
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + phi * (b - a);
+            fd = f(d);
+        }
+    }
+
+    ((a + b) / 2.0).exp()
+}
+
+/// Fits the temperature of `classifier` on the held-out examples of `data` and sets it. Returns the new temperature.
+pub fn calibrate<D: Dataset + ?Sized>(classifier: &mut Classifier, data: &D) -> Result<f64> {
+    let labels: Vec<String> = classifier.labels().into_iter().map(|l| l.to_string()).collect();
+    let mut outputs = Vec::with_capacity(data.len());
+    let mut truths = Vec::with_capacity(data.len());
+
+    for example in dataset::stream(data) {
+        let (input, label) = example?;
+        let truth = labels.iter().position(|l| *l == label)
+            .ok_or_else(|| Error::argument("calibration set", format!("the net has no output for label {:?}", label)))?;
+        outputs.push(classifier.outputs(&input)?);
+        truths.push(truth);
+    }
+    if outputs.is_empty() {
+        return Err(Error::EmptyDataset { source: "calibration set".into() });
+    }
+
+    let temperature = fit_temperature(&outputs, &truths);
+    classifier.set_temperature(temperature);
+    Ok(temperature)
+}
+
+#[test]
+fn test_fit_temperature() {
+    // always right and too unsure of it: the temperature should go down
+    let outputs = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+    let truths = vec![0, 1];
+    assert!(fit_temperature(&outputs, &truths) < 0.1);
+
+    // right only half of the time: the best scores are uniform
+    let outputs = vec![vec![1.0, 0.0], vec![1.0, 0.0]];
+    assert!(fit_temperature(&outputs, &truths) > 100.0);
+}
+
+#[test]
+fn test_expected_calibration_error() {
+    let outcome = |truth: &str, predicted: &str, confidence: f64| Outcome {
+        truth: truth.into(),
+        ranking: vec![(predicted.into(), confidence), ("x".into(), 1.0 - confidence)],
+    };
+    let outcomes = [outcome("a", "a", 0.95), outcome("a", "b", 0.95)];
+    let diagram = reliability_diagram(&outcomes, 10);
+    assert_eq!(diagram[9].count, 2);
+    assert!((expected_calibration_error(&diagram) - 0.45).abs() < 1e-12);
+}
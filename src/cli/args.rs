@@ -0,0 +1,458 @@
+use clap::{Arg, App, SubCommand, ArgMatches};
+use cli::validators::*;
+
+/// Options of the classifier learning runs, shared by the subcommands that learn.
+fn learn_settings() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("learn-sample")
+            .short("s")
+            .long("sample")
+            .help("Sets the percentage of the learn dataset that will be used during each epoch.")
+            .takes_value(true)
+            .default_value("0.2")
+            .validator(str_is_float),
+        Arg::with_name("sampling")
+            .long("sampling")
+            .help("Sets how the examples of every epoch are drawn: distinct ones uniformly, or the examples of every \
+                   label equally often, repeating the ones of rare labels.")
+            .takes_value(true)
+            .possible_values(&["uniform", "balanced"])
+            .default_value("uniform"),
+        Arg::with_name("class-weights")
+            .long("class-weights")
+            .help("Sets how much the examples of every label count: all the same, inversely to the number of \
+                   examples of their label, or inversely to its effective number.")
+            .takes_value(true)
+            .possible_values(&["none", "inverse-frequency", "effective-number"])
+            .default_value("none"),
+        Arg::with_name("effective-beta")
+            .long("effective-beta")
+            .help("Sets how fast the effective number of examples of a label grows with their number, from 0 \
+                   (not at all) to close to 1 (as fast).")
+            .takes_value(true)
+            .value_name("BETA")
+            .default_value("0.999")
+            .validator(str_is_float),
+        Arg::with_name("max-epochs")
+            .help("Sets the maximum number of epochs that the learning algorithm will use.")
+            .short("e")
+            .long("max-epochs")
+            .takes_value(true)
+            .default_value("50")
+            .validator(str_is_integer),
+        Arg::with_name("learning-rate")
+            .short("r")
+            .long("learning-rate")
+            .help("Sets the learning rate.")
+            .takes_value(true)
+            .default_value("0.1")
+            .validator(str_is_float),
+        Arg::with_name("no-parallel")
+            .long("no-parallel")
+            .help("Runs learning on single thread instead of all the available threads."),
+        Arg::with_name("seed")
+            .long("seed")
+            .help("Seeds the initial weights, the samples and their augmentation, so that the learning can be \
+                   repeated.")
+            .takes_value(true)
+            .validator(str_is_integer),
+        Arg::with_name("loss")
+            .long("loss")
+            .help("Sets what the learning minimizes: the squared error, or the Huber loss which weighs outliers less.")
+            .takes_value(true)
+            .possible_values(&["mse", "huber"])
+            .default_value("mse"),
+        Arg::with_name("huber-delta")
+            .long("huber-delta")
+            .help("Sets how far from its target an output is an outlier for the Huber loss.")
+            .takes_value(true)
+            .value_name("DELTA")
+            .default_value("1.0")
+            .validator(str_is_float),
+        Arg::with_name("preprocess")
+            .long("preprocess")
+            .help("Sets how the image files are made into the inputs of new nets, which keep it for predicting: \
+                   gray or rgb, then comma-separated steps among resize=WxH, invert, crop (to the bounding box of \
+                   the ink), center, deskew, minmax and zscore. Nets that are trained further keep their own.")
+            .takes_value(true)
+            .value_name("STEPS")
+            .default_value("gray,invert")
+            .validator(preprocessing_is_valid),
+        Arg::with_name("augment")
+            .long("augment")
+            .help("Randomly transforms the examples of every sample before learning them: comma-separated settings \
+                   among translate=FRACTION, rotate=DEGREES, scale=FRACTION, shear=AMOUNT, elastic=ALPHA[:SIGMA], \
+                   noise=STDDEV, erase=PROBABILITY and mixup=ALPHA, like rotate=10,elastic=8:4.")
+            .takes_value(true)
+            .value_name("SETTINGS")
+            .default_value("none")
+            .validator(augmentation_is_valid),
+    ]
+}
+
+/// Options of the subcommands that record their learning in run directories.
+fn run_settings() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("run-dir")
+            .long("run-dir")
+            .help("Sets the directory the manifest, the metrics of every epoch and the nets of the run are written to, \
+                   runs/KIND-TIMESTAMP by default.")
+            .takes_value(true)
+            .value_name("DIR"),
+        Arg::with_name("tensorboard")
+            .long("tensorboard")
+            .help("Also writes the metrics, weight histograms and images of the run to a TensorBoard event file \
+                   in the run directory."),
+    ]
+}
+
+pub fn get() -> ArgMatches<'static> {
+    App::new("Multilayer-perceptron-based Classifier")
+        .version("1.0")
+        .author("Mikołaj Robakowski <mikolaj.rob@gmail.com>")
+        .about("Classifies images using multilayer perceptron.\n\
+                Written for Neural Networks classes at Wrocław University of technology")
+        .subcommand(SubCommand::with_name("gui"))
+        .subcommand(SubCommand::with_name("autoencoder")
+            .arg(Arg::with_name("epoch-count")
+                .takes_value(true)
+                .short("e"))
+            .arg(Arg::with_name("mnist")
+                .takes_value(false)
+                .long("mnist"))
+            .arg(Arg::with_name("sample").takes_value(true).long("sample").short("a"))
+            .arg(Arg::with_name("penalty-factor")
+                .takes_value(true)
+                .short("p"))
+            .arg(Arg::with_name("hidden-neurons")
+                .takes_value(true)
+                .short("n"))
+            .arg(Arg::with_name("sparsity")
+                .takes_value(true)
+                .short("s"))
+            .arg(Arg::with_name("seed")
+                .long("seed")
+                .help("Seeds the initial weights and the samples, so that the learning can be repeated.")
+                .takes_value(true)
+                .validator(str_is_integer))
+            .args(&run_settings()))
+        .subcommand(SubCommand::with_name("learn")
+            .about("Learns the net")
+            .arg(Arg::with_name("out-net")
+                .help("File to write net to")
+                .short("o")
+                .long("out-net")
+                .takes_value(true)
+                .value_name("NET_OUTPUT_FILE"))
+            .arg(Arg::with_name("learn-dataset")
+                .help("Sets the input folder with the images to learn.\n\
+                   The filenames must be in format LABEL(_.*)?")
+                .index(1)
+                .takes_value(true)
+                .value_name("LEARN_DIR")
+                .required(true)
+                .validator(dataset_exists))
+            .arg(Arg::with_name("validation")
+                .help("Sets the folder with the images the accuracy is also checked on after every epoch. \
+                       The best net is chosen by this accuracy instead of the one on the learning dataset.")
+                .long("validation")
+                .takes_value(true)
+                .value_name("DIR")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("task")
+                .help("Sets what a new net learns: the labels of the examples, their targets with linear outputs, or \
+                       their label sets (see the separator= option of datasets) with sigmoid outputs and the \
+                       cross-entropy. Regressors are scored by their RMSE instead of the accuracy, and multi-label \
+                       classifiers by the fraction of exactly predicted label sets.")
+                .long("task")
+                .takes_value(true)
+                .possible_values(&["classification", "regression", "multi-label"])
+                .default_value("classification"))
+            .args(&learn_settings())
+            .args(&run_settings())
+            .arg(Arg::with_name("in-net")
+                .help("Net to use")
+                .short("i")
+                .long("in-net")
+                .takes_value(true)
+                .value_name("NET_INPUT_FILE")
+                .validator(file_exists)))
+        .subcommand(SubCommand::with_name("crossval")
+            .about("Estimates how well nets learnt with the given settings generalize, using k-fold cross-validation")
+            .arg(Arg::with_name("dataset")
+                .help("Sets the folder with the labelled images.\n\
+                   The filenames must be in format LABEL_AUTHOR_N, the images of an author are kept in one fold.")
+                .index(1)
+                .takes_value(true)
+                .required(true)
+                .value_name("DIR")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("folds")
+                .help("Sets the number of folds.")
+                .short("k")
+                .long("folds")
+                .takes_value(true)
+                .value_name("K")
+                .default_value("5")
+                .validator(str_is_integer))
+            .args(&learn_settings()))
+        .subcommand(SubCommand::with_name("split")
+            .about("Divides a dataset into train, validation and test portions, stratified by label")
+            .arg(Arg::with_name("dataset")
+                .help("Sets the dataset to divide.")
+                .index(1)
+                .takes_value(true)
+                .required(true)
+                .value_name("DATASET")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("out")
+                .help("Sets the directory the portions are written to.")
+                .short("o")
+                .long("out")
+                .takes_value(true)
+                .required(true)
+                .value_name("DIR"))
+            .arg(Arg::with_name("ratios")
+                .help("Sets the shares of the train, validation and test portions, a portion with 0 being skipped.")
+                .long("ratios")
+                .takes_value(true)
+                .value_name("TRAIN,VALIDATION,TEST")
+                .default_value("0.7,0.15,0.15"))
+            .arg(Arg::with_name("group")
+                .help("Keeps the examples whose file names share a field in the same portion: author for \
+                       LABEL_AUTHOR_N names, or the number of a _-separated field counted from 0.")
+                .long("group")
+                .takes_value(true)
+                .value_name("FIELD"))
+            .arg(Arg::with_name("seed")
+                .long("seed")
+                .help("Seeds which examples go to which portion, so that the split can be repeated.")
+                .takes_value(true)
+                .validator(str_is_integer))
+            .arg(Arg::with_name("format")
+                .help("Writes the portions as index files (train.csv, ...) that the dataset is opened with using \
+                       index=FILE, or copies their files to directories (train/LABEL/, ...).")
+                .short("f")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["index", "dirs"])
+                .default_value("index")))
+        .subcommand(SubCommand::with_name("search")
+            .about("Searches for the best learning settings, learning a net for each tried combination")
+            .arg(Arg::with_name("dataset")
+                .help("Sets the folder with the images to learn.\n\
+                   The filenames must be in format LABEL(_.*)?.")
+                .index(1)
+                .takes_value(true)
+                .required(true)
+                .value_name("DIR")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("validation")
+                .help("Sets the folder with the images the nets are scored on, \
+                       a fifth of the learning dataset is held out if not given.")
+                .long("validation")
+                .takes_value(true)
+                .value_name("DIR")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("model")
+                .help("Sets what is learnt: classifiers are scored by accuracy, \
+                       autoencoders by negated reconstruction error.")
+                .long("model")
+                .takes_value(true)
+                .possible_values(&["classifier", "autoencoder"])
+                .default_value("classifier"))
+            .arg(Arg::with_name("param")
+                .help("Declares a searched parameter and its values: a list (0.1,0.3), a range (0.1..0.5) \
+                       or a logarithmic range (0.001..1:log). The parameters are learning-rate, hidden-neurons \
+                       and sample-ratio, and sparsity and penalty-factor of autoencoders.")
+                .short("p")
+                .long("param")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(true)
+                .value_name("NAME=VALUES"))
+            .arg(Arg::with_name("strategy")
+                .help("Tries every combination of the listed values, or random ones.")
+                .long("strategy")
+                .takes_value(true)
+                .possible_values(&["grid", "random"])
+                .default_value("grid"))
+            .arg(Arg::with_name("trials")
+                .help("Sets the number of random trials.")
+                .short("n")
+                .long("trials")
+                .takes_value(true)
+                .default_value("20")
+                .validator(str_is_integer))
+            .arg(Arg::with_name("halving")
+                .help("Learns all the trials briefly and only the best 1/ETA of them ETA times longer, \
+                       until they reach the maximum number of epochs.")
+                .long("halving")
+                .takes_value(true)
+                .value_name("ETA")
+                .validator(str_is_integer))
+            .arg(Arg::with_name("leaderboard")
+                .help("Writes all the trials, the best first, to FILE as JSON if it ends with .json or CSV otherwise.")
+                .long("leaderboard")
+                .takes_value(true)
+                .value_name("FILE"))
+            .arg(Arg::with_name("out-net")
+                .help("File to write the best net to")
+                .short("o")
+                .long("out-net")
+                .takes_value(true)
+                .value_name("NET_OUTPUT_FILE"))
+            .args(&learn_settings()))
+        .subcommand(SubCommand::with_name("plot")
+            .about("Plots the loss and accuracy curves of runs, comparing them on the same charts")
+            .arg(Arg::with_name("runs")
+                .help("Run directories written by learn or autoencoder")
+                .index(1)
+                .multiple(true)
+                .required(true)
+                .value_name("RUN_DIR")
+                .validator(path_exists))
+            .arg(Arg::with_name("output")
+                .help("File to write the plot to, as SVG or PNG depending on its extension")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .required(true)
+                .value_name("FILE"))
+            .arg(Arg::with_name("smooth")
+                .help("Averages every point with the WINDOW - 1 ones before it.")
+                .long("smooth")
+                .takes_value(true)
+                .value_name("WINDOW")
+                .default_value("1")
+                .validator(str_is_integer)))
+        .subcommand(SubCommand::with_name("predict")
+            .about("Classifies unlabelled images, or prints the outputs of regressors or the labels of multi-label \
+                    classifiers for them")
+            .arg(Arg::with_name("inputs")
+                .help("Images to classify, or directories containing them")
+                .index(1)
+                .multiple(true)
+                .required(true)
+                .value_name("FILE_OR_DIR"))
+            .arg(Arg::with_name("in-net")
+                .help("Net to use")
+                .short("i")
+                .long("in-net")
+                .takes_value(true)
+                .required(true)
+                .value_name("NET_INPUT_FILE")
+                .validator(file_exists))
+            .arg(Arg::with_name("format")
+                .help("Sets the output format.")
+                .short("f")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["table", "csv", "jsonl"])
+                .default_value("table"))
+            .arg(Arg::with_name("top-k")
+                .help("Sets the number of the best labels printed for every file.")
+                .short("k")
+                .long("top-k")
+                .takes_value(true)
+                .default_value("1")
+                .validator(str_is_integer)))
+        .subcommand(SubCommand::with_name("calibrate")
+            .about("Fits the temperature of the net's scores on held-out examples, so that they become probabilities, \
+                    or the thresholds of the labels of multi-label classifiers")
+            .arg(Arg::with_name("held-out-dataset")
+                .help("Sets the folder with the held-out images.\n\
+                   The filenames must be in format LABEL(_.*)?.")
+                .index(1)
+                .takes_value(true)
+                .required(true)
+                .value_name("DIR")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("in-net")
+                .help("Net to calibrate")
+                .short("i")
+                .long("in-net")
+                .takes_value(true)
+                .required(true)
+                .value_name("NET_INPUT_FILE")
+                .validator(file_exists))
+            .arg(Arg::with_name("out-net")
+                .help("File to write the calibrated net to, the input net is overwritten if not given")
+                .short("o")
+                .long("out-net")
+                .takes_value(true)
+                .value_name("NET_OUTPUT_FILE"))
+            .arg(Arg::with_name("target-coverage")
+                .help("Also tunes a reject rule that answers \"unknown\" for all but this fraction of the held-out \
+                       examples, the ones the net is the most sure of.")
+                .long("target-coverage")
+                .takes_value(true)
+                .value_name("FRACTION")
+                .validator(str_is_float))
+            .arg(Arg::with_name("reject-by")
+                .help("Sets what the tuned reject rule thresholds: the best score or its margin over the second best.")
+                .long("reject-by")
+                .takes_value(true)
+                .possible_values(&["confidence", "margin"])
+                .default_value("confidence")))
+        .subcommand(SubCommand::with_name("check")
+            .about("Checks the net, reporting the MAE, RMSE and R² of regressors, and the Hamming loss, subset \
+                    accuracy and per-label F1 of multi-label classifiers")
+            .arg(Arg::with_name("check-dataset")
+                .help("Sets the folder with the images to check against.\n\
+                   The filenames must be in format LABEL(_.*)?.")
+                .index(1)
+                .takes_value(true)
+                .required(true)
+                .value_name("DIR")
+                .validator(dataset_exists))
+            .arg(Arg::with_name("in-net")
+                .help("Net to use")
+                .short("i")
+                .long("in-net")
+                .takes_value(true)
+                .required(true)
+                .value_name("NET_INPUT_FILE")
+                .validator(file_exists))
+            .arg(Arg::with_name("report")
+                .help("Sets the format of the report.")
+                .long("report")
+                .takes_value(true)
+                .possible_values(&["text", "json", "csv"])
+                .default_value("text"))
+            .arg(Arg::with_name("top-k")
+                .help("Also reports how often the true label is among the K best ones.")
+                .short("k")
+                .long("top-k")
+                .takes_value(true)
+                .value_name("K")
+                .default_value("3")
+                .validator(str_is_integer))
+            .arg(Arg::with_name("dump-errors")
+                .help("Writes every misclassified example, named after its true and predicted labels, \
+                       to DIR along with an index.csv.")
+                .long("dump-errors")
+                .takes_value(true)
+                .value_name("DIR"))
+            .arg(Arg::with_name("html")
+                .help("Writes a self-contained HTML report with the metrics and thumbnails of the examples.")
+                .long("html")
+                .takes_value(true)
+                .value_name("REPORT_FILE"))
+            .arg(Arg::with_name("reject-confidence")
+                .help("Rejects the examples whose best score is below THRESHOLD instead of the net's reject rule.")
+                .long("reject-confidence")
+                .takes_value(true)
+                .value_name("THRESHOLD")
+                .conflicts_with("reject-margin")
+                .validator(str_is_float))
+            .arg(Arg::with_name("reject-margin")
+                .help("Rejects the examples whose best score exceeds the second best by less than THRESHOLD \
+                       instead of the net's reject rule.")
+                .long("reject-margin")
+                .takes_value(true)
+                .value_name("THRESHOLD")
+                .validator(str_is_float)))
+        .get_matches()
+}
\ No newline at end of file
@@ -0,0 +1,97 @@
+use mulperc::autoencoder::{self, AutoencoderConfig};
+use mulperc::{dataset, img, MultilayerPerceptron, NetFile};
+use mulperc::run::{EpochRecord, Manifest};
+use mulperc::preprocess::Pipeline;
+use mulperc::tensorboard::EventWriter;
+use mulperc::error::Result;
+use clap;
+use rand;
+use std::cmp::max;
+use cli::classifier::{create_event_writer, create_run_dir, is_snapshot, log_events, open_dataset};
+
+/// Number of images whose reconstructions are written to TensorBoard.
+const RECONSTRUCTED_IMAGES: usize = 8;
+
+/// Writes `originals` above their reconstructions by `net`, and the features of `net`, as images to TensorBoard.
+fn log_images(events: &mut EventWriter, epoch: u64, net: &MultilayerPerceptron, originals: &[Vec<f64>], w: u32, h: u32)
+              -> Result<()> {
+    let mut pairs = originals.to_vec();
+    pairs.extend(originals.iter().map(|x| net.feed_forward(x).0.at));
+    let (pixels, tiled_w, tiled_h) = img::tile(&pairs, w, h, originals.len());
+    events.image("autoencoder/reconstructions", epoch, &pixels, tiled_w, tiled_h)?;
+
+    let features = autoencoder::features(net);
+    let columns = (features.len() as f64).sqrt().ceil() as usize;
+    let (pixels, tiled_w, tiled_h) = img::tile(&features, w, h, columns);
+    events.image("autoencoder/features", epoch, &pixels, tiled_w, tiled_h)
+}
+
+pub fn run(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let defaults = AutoencoderConfig::default();
+    let config = AutoencoderConfig {
+        epochs: matches.value_of("epoch-count").and_then(|x| x.parse().ok()).unwrap_or(defaults.epochs),
+        sample_ratio: matches.value_of("sample").and_then(|x| x.parse().ok()).unwrap_or(defaults.sample_ratio),
+        hidden_neurons: matches.value_of("hidden-neurons").and_then(|x| x.parse().ok()).unwrap_or(defaults.hidden_neurons),
+        sparsity: matches.value_of("sparsity").and_then(|x| x.parse().ok()).unwrap_or(defaults.sparsity),
+        penalty_factor: matches.value_of("penalty-factor").and_then(|x| x.parse().ok()).unwrap_or(defaults.penalty_factor),
+        seed: Some(matches.value_of("seed").and_then(|x| x.parse().ok()).unwrap_or_else(rand::random)),
+        ..defaults
+    };
+    let is_mnist = matches.is_present("mnist");
+
+    let source = if is_mnist { "mnist" } else { "res/Sieci Neuronowe" };
+    let images = open_dataset(source, false, &Pipeline::default())?;
+    let (w, h) = dataset::image_dims(&*images);
+
+    let mut net = autoencoder::new_net(&config, images.input_len());
+
+    let mut manifest = Manifest::new("autoencoder", &config, config.seed.unwrap());
+    manifest.add_dataset("learning", source, images.len());
+    let mut run = create_run_dir(matches, manifest)?;
+    let mut events = create_event_writer(matches, &run)?;
+    let step = max(images.len() / RECONSTRUCTED_IMAGES, 1);
+    let originals = (0..images.len()).filter(|&i| i % step == 0).take(RECONSTRUCTED_IMAGES)
+        .map(|i| images.input(i)).collect::<Result<Vec<_>>>()?;
+
+    use pbr::ProgressBar;
+    let mut pbr = ProgressBar::new(config.epochs);
+    autoencoder::train(&config, &mut net, &*images, |stats, net| {
+        pbr.inc();
+        let record = EpochRecord::new(stats);
+        run.log(&record)?;
+        if run.improves(-record.loss) {
+            run.save_best(-record.loss, &NetFile::autoencoder(net.clone()))?;
+        }
+        if let Some(events) = events.as_mut() {
+            let snapshot = is_snapshot(record.epoch, config.epochs);
+            log_events(events, &record, net, snapshot)?;
+            if snapshot {
+                log_images(events, record.epoch, net, &originals, w, h)?;
+            }
+        }
+        Ok(())
+    })?;
+    if let Some(mut events) = events {
+        events.flush()?;
+    }
+    run.finish(&NetFile::autoencoder(net.clone()))?;
+
+    for ii in 0..images.len() {
+        if is_mnist && ii % 1000 != 0 {
+            continue;
+        }
+        use std::path::Path;
+        let name = format!("out{}.png", ii);
+        let p = Path::new("autoencoded").join(&name);
+        img::save(&net.feed_forward(&images.input(ii)?).0.at, w, h, p)?;
+    }
+
+    for (i, feature) in autoencoder::features(&net).iter().enumerate() {
+        img::save(feature, w, h, &format!("autoencoded/feature{}.png", i))?;
+    }
+
+    let error = autoencoder::reconstruction_error(&net, &*images)?;
+    pbr.finish_println(&format!("error: {0:>5.2}  ", error));
+
+    Ok(())
+}
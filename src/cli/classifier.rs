@@ -0,0 +1,398 @@
+use clap;
+use rand;
+use std::cmp::max;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use mulperc::{calibration, dataset, metrics, multilabel, regression, rejection, run, Classifier, MultiLabelClassifier,
+              MultilayerPerceptron, NetFile, Regressor, Trainer, TrainConfig};
+use mulperc::calibration::Calibration;
+use mulperc::dataset::{Dataset, Subset};
+use mulperc::metrics::Evaluation;
+use mulperc::multilayer_perceptron::{Loss, Task};
+use mulperc::preprocess::Pipeline;
+use mulperc::balance::ClassWeighting;
+use mulperc::trainer::{Mode, Sampling};
+use mulperc::rejection::{Measure, RejectRule};
+use mulperc::run::{EpochRecord, Manifest, RunDir};
+use mulperc::tensorboard::EventWriter;
+use mulperc::error::{Error, Result};
+use cli::output::Format;
+use cli::{dump, html, report};
+
+/// Opens a labelled dataset, making its image files into inputs with `pipeline` and warning about (and skipping)
+/// files that couldn't be read.
+pub fn open_dataset(source: &str, test: bool, pipeline: &Pipeline) -> Result<Box<Dataset>> {
+    let (data, skipped) = dataset::open_with(source, test, pipeline)?;
+    for e in &skipped {
+        let _ = writeln!(io::stderr(), "warning: skipping {}", e);
+    }
+    Ok(data)
+}
+
+/// Number of times the weight histograms (and the images of autoencoders) are written to TensorBoard during a run.
+const TENSORBOARD_SNAPSHOTS: u64 = 50;
+
+/// Maximal number of the learning examples the train accuracy of every epoch is computed on.
+const TRAIN_ACCURACY_SAMPLE: usize = 1000;
+
+pub fn check(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let check_dir = matches.value_of("check-dataset").unwrap();
+    let in_net = matches.value_of("in-net").unwrap();
+    let format: Format = matches.value_of("report").unwrap().parse()?;
+    let top_k: usize = parse_arg(matches, "top-k")?;
+
+    let net = NetFile::load(in_net)?;
+    match net.task {
+        Task::Regression(_) => return check_regressor(matches, Regressor::new(net)?),
+        Task::MultiLabel(_) => return check_multilabel(matches, MultiLabelClassifier::new(net)?),
+        Task::Autoencoder => return Err(Error::net(in_net, "autoencoders can't be checked")),
+        Task::Classification => (),
+    }
+    let mut classifier = Classifier::new(net);
+    if matches.is_present("reject-confidence") {
+        classifier.set_reject_rule(Some(RejectRule::Confidence(parse_arg(matches, "reject-confidence")?)));
+    } else if matches.is_present("reject-margin") {
+        classifier.set_reject_rule(Some(RejectRule::Margin(parse_arg(matches, "reject-margin")?)));
+    }
+
+    let _ = write!(io::stderr(), "Opening checking dataset {}... ", check_dir);
+    let check_imgs = open_dataset(check_dir, true, &classifier.net().preprocessing)?;
+    let _ = writeln!(io::stderr(), "Opened!");
+
+    let outcomes = metrics::outcomes(&classifier, &*check_imgs)?;
+    let mut ks = vec![1, top_k];
+    ks.dedup();
+    let evaluation = Evaluation::new(&outcomes, &ks, classifier.reject_rule());
+
+    print!("{}", report::render(&evaluation, format));
+
+    let dims = dataset::image_dims(&*check_imgs);
+
+    if let Some(path) = matches.value_of("html") {
+        html::write_report(path, &format!("{} on {}", in_net, check_dir), &evaluation, &outcomes, &*check_imgs, dims)?;
+        let _ = writeln!(io::stderr(), "Wrote the report to {}", path);
+    }
+
+    if let Some(dir) = matches.value_of("dump-errors") {
+        let dumped = dump::dump_errors(dir, &outcomes, &*check_imgs, dims)?;
+        let _ = writeln!(io::stderr(), "Wrote {} misclassified examples to {}", dumped, dir);
+    }
+    Ok(())
+}
+
+/// Reports the MAE, RMSE and R² of `regressor` on the checking dataset.
+fn check_regressor(matches: &clap::ArgMatches<'static>, regressor: Regressor) -> Result<()> {
+    for name in &["html", "dump-errors", "reject-confidence", "reject-margin"] {
+        if matches.is_present(name) {
+            return Err(Error::argument(*name, "regressors are only checked by their errors"));
+        }
+    }
+    let check_dir = matches.value_of("check-dataset").unwrap();
+    let format: Format = matches.value_of("report").unwrap().parse()?;
+
+    let _ = write!(io::stderr(), "Opening checking dataset {}... ", check_dir);
+    let check_data = open_dataset(check_dir, true, &regressor.net().preprocessing)?;
+    let _ = writeln!(io::stderr(), "Opened!");
+
+    let evaluation = regression::evaluate(&regressor, &*check_data)?;
+    print!("{}", report::render_regression(&evaluation, format));
+    Ok(())
+}
+
+/// Reports the Hamming loss, subset accuracy and per-label F1 of `classifier` on the checking dataset.
+fn check_multilabel(matches: &clap::ArgMatches<'static>, classifier: MultiLabelClassifier) -> Result<()> {
+    for name in &["html", "dump-errors", "reject-confidence", "reject-margin"] {
+        if matches.is_present(name) {
+            return Err(Error::argument(*name, "multi-label classifiers are only checked by their metrics"));
+        }
+    }
+    let check_dir = matches.value_of("check-dataset").unwrap();
+    let format: Format = matches.value_of("report").unwrap().parse()?;
+
+    let _ = write!(io::stderr(), "Opening checking dataset {}... ", check_dir);
+    let check_data = open_dataset(check_dir, true, &classifier.net().preprocessing)?;
+    let _ = writeln!(io::stderr(), "Opened!");
+
+    let evaluation = multilabel::evaluate(&classifier, &*check_data)?;
+    print!("{}", report::render_multilabel(&evaluation, format));
+    Ok(())
+}
+
+pub fn calibrate(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let held_out_dir = matches.value_of("held-out-dataset").unwrap();
+    let in_net = matches.value_of("in-net").unwrap();
+    let out_net = matches.value_of("out-net").unwrap_or(in_net);
+
+    let net = NetFile::load(in_net)?;
+    match net.task {
+        Task::MultiLabel(_) if matches.is_present("target-coverage") => {
+            return Err(Error::argument("target-coverage", "multi-label classifiers have no reject rule"));
+        }
+        Task::MultiLabel(_) => return tune_thresholds(held_out_dir, out_net, MultiLabelClassifier::new(net)?),
+        Task::Regression(_) => return Err(Error::net(in_net, "regressors can't be calibrated")),
+        Task::Autoencoder => return Err(Error::net(in_net, "autoencoders can't be calibrated")),
+        Task::Classification => (),
+    }
+    let examples = open_dataset(held_out_dir, true, &net.preprocessing)?;
+    let mut classifier = Classifier::new(net);
+
+    let before = Calibration::new(&metrics::outcomes(&classifier, &*examples)?, metrics::RELIABILITY_BINS);
+    let temperature = calibration::calibrate(&mut classifier, &*examples)?;
+    let after = Calibration::new(&metrics::outcomes(&classifier, &*examples)?, metrics::RELIABILITY_BINS);
+
+    println!("temperature: {:.4}", temperature);
+    println!("expected calibration error: {:.4} -> {:.4}",
+             before.expected_calibration_error, after.expected_calibration_error);
+    println!("Brier score: {:.4} -> {:.4}", before.brier_score, after.brier_score);
+
+    if matches.is_present("target-coverage") {
+        let coverage: f64 = parse_arg(matches, "target-coverage")?;
+        let measure: Measure = matches.value_of("reject-by").unwrap().parse()?;
+        let outcomes = metrics::outcomes(&classifier, &*examples)?;
+        let rule = rejection::tune(&outcomes, measure, coverage)?;
+        let point = rejection::apply(&outcomes, &rule);
+        println!("reject rule: {:?}", rule);
+        println!("coverage: {:.4}, accuracy of the accepted: {:.4}", point.coverage, point.accuracy);
+        classifier.set_reject_rule(Some(rule));
+    }
+
+    classifier.net().save(out_net)
+}
+
+/// Tunes the thresholds of the labels of a multi-label classifier on the held-out dataset and saves it to `out_net`.
+fn tune_thresholds(held_out_dir: &str, out_net: &str, mut classifier: MultiLabelClassifier) -> Result<()> {
+    let examples = open_dataset(held_out_dir, true, &classifier.net().preprocessing)?;
+
+    let before = multilabel::evaluate(&classifier, &*examples)?;
+    let thresholds = multilabel::tune_thresholds(&mut classifier, &*examples)?;
+    let after = multilabel::evaluate(&classifier, &*examples)?;
+
+    for (label, threshold) in classifier.labels().iter().zip(&thresholds) {
+        println!("threshold of {}: {:.2}", label, threshold);
+    }
+    println!("macro F1: {:.4} -> {:.4}", before.macro_f1, after.macro_f1);
+    println!("subset accuracy: {:.4} -> {:.4}", before.subset_accuracy, after.subset_accuracy);
+
+    classifier.net().save(out_net)
+}
+
+pub fn parse_arg<T: ::std::str::FromStr>(matches: &clap::ArgMatches<'static>, name: &str) -> Result<T> {
+    let value = matches.value_of(name).unwrap();
+    value.parse().map_err(|_| Error::argument(name, format!("could not parse {:?}", value)))
+}
+
+/// The preprocessing given by `--preprocess`.
+pub fn preprocessing(matches: &clap::ArgMatches<'static>) -> Result<Pipeline> {
+    matches.value_of("preprocess").unwrap().parse()
+}
+
+pub fn train_config(matches: &clap::ArgMatches<'static>) -> Result<TrainConfig> {
+    let loss = match matches.value_of("loss").unwrap() {
+        "huber" => Loss::Huber(parse_arg(matches, "huber-delta")?),
+        _ => Loss::SquaredError,
+    };
+    let class_weighting = match matches.value_of("class-weights").unwrap() {
+        "inverse-frequency" => ClassWeighting::InverseFrequency,
+        "effective-number" => {
+            let beta: f64 = parse_arg(matches, "effective-beta")?;
+            if !(beta >= 0.0 && beta < 1.0) {
+                return Err(Error::argument("effective-beta", format!("{} is not in [0, 1)", beta)));
+            }
+            ClassWeighting::EffectiveNumber(beta)
+        }
+        _ => ClassWeighting::Unweighted,
+    };
+    Ok(TrainConfig {
+        sample_ratio: parse_arg(matches, "learn-sample")?,
+        sampling: match matches.value_of("sampling").unwrap() {
+            "balanced" => Sampling::Balanced,
+            _ => Sampling::Uniform,
+        },
+        class_weighting: class_weighting,
+        max_epochs: parse_arg(matches, "max-epochs")?,
+        learning_rate: parse_arg(matches, "learning-rate")?,
+        parallel: !matches.is_present("no-parallel"),
+        seed: match matches.value_of("seed") {
+            Some(_) => Some(parse_arg(matches, "seed")?),
+            None => None,
+        },
+        loss: loss,
+        preprocessing: preprocessing(matches)?,
+        augmentation: matches.value_of("augment").unwrap().parse()?,
+        ..TrainConfig::default()
+    })
+}
+
+/// Creates the run directory given by `--run-dir`, or the default one for `manifest`.
+pub fn create_run_dir(matches: &clap::ArgMatches<'static>, manifest: Manifest) -> Result<RunDir> {
+    let path = matches.value_of("run-dir").map(PathBuf::from).unwrap_or_else(|| run::default_path(&manifest));
+    let run = RunDir::create(&path, manifest)?;
+    println!("Recording the run in {}", path.display());
+    Ok(run)
+}
+
+/// Creates an event file in the directory of `run` if `--tensorboard` is given.
+pub fn create_event_writer(matches: &clap::ArgMatches<'static>, run: &RunDir) -> Result<Option<EventWriter>> {
+    if !matches.is_present("tensorboard") {
+        return Ok(None);
+    }
+    let events = EventWriter::create(run.path())?;
+    println!("Writing TensorBoard events to {}", events.path().display());
+    Ok(Some(events))
+}
+
+/// Whether the weights (and images) of the epoch `epoch` out of `epochs` are written to TensorBoard.
+pub fn is_snapshot(epoch: u64, epochs: u64) -> bool {
+    epoch % max(epochs / TENSORBOARD_SNAPSHOTS, 1) == 0 || epoch + 1 == epochs
+}
+
+/// Writes the metrics of `record` to TensorBoard, and the weight histograms of `net` if `snapshot` is set.
+pub fn log_events(events: &mut EventWriter, record: &EpochRecord, net: &MultilayerPerceptron, snapshot: bool)
+                  -> Result<()> {
+    events.scalar("loss", record.epoch, record.loss)?;
+    events.scalar("learning_rate", record.epoch, record.learning_rate)?;
+    events.scalar("gradient_norm", record.epoch, record.gradient_norm)?;
+    if let Some(accuracy) = record.train_accuracy {
+        events.scalar("accuracy/train", record.epoch, accuracy)?;
+    }
+    if let Some(accuracy) = record.validation_accuracy {
+        events.scalar("accuracy/validation", record.epoch, accuracy)?;
+    }
+    if snapshot {
+        events.weights(record.epoch, net)?;
+    }
+    Ok(())
+}
+
+/// Indices of every few of `len` examples, at most `n` of them.
+fn subsample(len: usize, n: usize) -> Vec<usize> {
+    let step = max(len / n, 1);
+    (0..len).filter(|&i| i % step == 0).take(n).collect()
+}
+
+/// What `net`, given by `path`, learns when it's trained further.
+fn mode_of(net: &NetFile, path: &str) -> Result<Mode> {
+    match net.task {
+        Task::Classification => Ok(Mode::Classification),
+        Task::Regression(_) => Ok(Mode::Regression),
+        Task::MultiLabel(_) => Ok(Mode::MultiLabel),
+        Task::Autoencoder => Err(Error::net(path, "autoencoders are learnt by the autoencoder subcommand")),
+    }
+}
+
+pub fn learn(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let learn_dir = matches.value_of("learn-dataset").unwrap();
+    let mut config = train_config(matches)?;
+    config.mode = match matches.value_of("task") {
+        Some("regression") => Mode::Regression,
+        Some("multi-label") => Mode::MultiLabel,
+        _ => Mode::Classification,
+    };
+    // nets that are trained further keep their preprocessing and task, which the arguments can't change
+    let input_net = match matches.value_of("in-net") {
+        Some(path) => {
+            let net = NetFile::load(path)?;
+            let mode = mode_of(&net, path)?;
+            if matches.occurrences_of("preprocess") > 0 && config.preprocessing != net.preprocessing {
+                return Err(Error::argument("preprocess", format!("{} is learnt with {}", path, net.preprocessing)));
+            }
+            if matches.occurrences_of("task") > 0 && config.mode != mode {
+                return Err(Error::argument("task", format!("{} is a {}", path, net.task.name())));
+            }
+            config.preprocessing = net.preprocessing.clone();
+            config.mode = mode;
+            Some(net)
+        }
+        None => None,
+    };
+    let seed = config.seed.unwrap_or_else(rand::random);
+    config.seed = Some(seed);
+    let trainer = Trainer::new(config);
+    println!("parallel: {}", trainer.config.parallel);
+    let out_net = matches.value_of("out-net");
+    let pipeline = trainer.config.preprocessing.clone();
+
+    dataset::check_preprocessing(learn_dir, &pipeline)?;
+    let imgs = open_dataset(learn_dir, false, &pipeline)?;
+    let validation_dir = matches.value_of("validation");
+    let validation = match validation_dir {
+        Some(dir) => Some(open_dataset(dir, true, &pipeline)?),
+        None => None,
+    };
+
+    let mut net = match input_net {
+        Some(net) => net,
+        None => trainer.new_net(&*imgs)?,
+    };
+    if let Some(ref examples) = validation {
+        if examples.input_len() != net.num_inputs() {
+            return Err(Error::shape("validation set", net.num_inputs(), examples.input_len()));
+        }
+    }
+
+    let mut manifest = Manifest::new("classifier", &trainer.config, seed);
+    manifest.add_dataset("learning", learn_dir, imgs.len());
+    if let (Some(dir), Some(examples)) = (validation_dir, validation.as_ref()) {
+        manifest.add_dataset("validation", dir, examples.len());
+    }
+    let mut run = create_run_dir(matches, manifest)?;
+    let mut events = create_event_writer(matches, &run)?;
+    let train_sample = Subset::new(&*imgs, subsample(imgs.len(), TRAIN_ACCURACY_SAMPLE));
+    let epochs = trainer.config.max_epochs;
+
+    println!("Learning...");
+    use pbr::ProgressBar;
+    let mut pb = ProgressBar::new(trainer.config.max_epochs);
+    trainer.train(&mut net, &*imgs, |stats, net| {
+        pb.inc();
+        let mut record = EpochRecord::new(stats);
+        let score = match net.task {
+            Task::Classification => {
+                record.train_accuracy = Some(metrics::accuracy(net, &train_sample)?);
+                record.validation_accuracy = match validation {
+                    Some(ref examples) => Some(metrics::accuracy(net, &**examples)?),
+                    None => None,
+                };
+                record.validation_accuracy.or(record.train_accuracy).unwrap()
+            }
+            Task::Regression(_) => {
+                // regressors have no accuracy, the best one has the lowest error
+                let rmse = match validation {
+                    Some(ref examples) => regression::rmse(net, &**examples)?,
+                    None => regression::rmse(net, &train_sample)?,
+                };
+                -rmse
+            }
+            Task::MultiLabel(_) => {
+                // the accuracy of multi-label classifiers is the one of whole label sets
+                record.train_accuracy = Some(multilabel::subset_accuracy(net, &train_sample)?);
+                record.validation_accuracy = match validation {
+                    Some(ref examples) => Some(multilabel::subset_accuracy(net, &**examples)?),
+                    None => None,
+                };
+                record.validation_accuracy.or(record.train_accuracy).unwrap()
+            }
+            // the trainer doesn't learn autoencoders
+            Task::Autoencoder => unreachable!(),
+        };
+        run.log(&record)?;
+        if run.improves(score) {
+            run.save_best(score, net)?;
+        }
+        if let Some(events) = events.as_mut() {
+            log_events(events, &record, &net.net, is_snapshot(record.epoch, epochs))?;
+        }
+        Ok(())
+    })?;
+    pb.finish_println("Finished learning!\n");
+
+    if let Some(mut events) = events {
+        events.flush()?;
+    }
+    run.finish(&net)?;
+    if let Some(path) = out_net {
+        net.save(path)?;
+    }
+
+    Ok(())
+}
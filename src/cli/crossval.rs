@@ -0,0 +1,78 @@
+use clap;
+use std::collections::BTreeMap;
+use pbr::ProgressBar;
+use mulperc::{img, metrics, split, Classifier, Trainer};
+use mulperc::dataset::{self, Dataset, Subset};
+use mulperc::metrics::Evaluation;
+use mulperc::error::Result;
+use cli::classifier::{open_dataset, parse_arg, train_config};
+
+/// Indices of the examples of `fold`, and of all the other ones.
+fn split_fold(folds: &[usize], fold: usize) -> (Vec<usize>, Vec<usize>) {
+    (0..folds.len()).partition(|&i| folds[i] == fold)
+}
+
+/// The author of every example of `data`, as a group for `split::stratified_group_folds`.
+///
+/// Examples without an author (like the MNIST digits) are groups of their own.
+pub fn author_groups(data: &Dataset) -> Vec<String> {
+    (0..data.len())
+        .map(|i| data.source(i).and_then(|p| img::author_of(p)).unwrap_or_else(|| format!("#{}", i)))
+        .collect()
+}
+
+fn mean_std_text(xs: Vec<f64>) -> String {
+    let (mean, std) = metrics::mean_std(&xs);
+    format!("{:.4} ± {:.4}", mean, std)
+}
+
+pub fn crossval(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let dir = matches.value_of("dataset").unwrap();
+    let k: usize = parse_arg(matches, "folds")?;
+    let trainer = Trainer::new(train_config(matches)?);
+
+    dataset::check_preprocessing(dir, &trainer.config.preprocessing)?;
+    let examples = open_dataset(dir, false, &trainer.config.preprocessing)?;
+    let groups = author_groups(&*examples);
+    let labels: Vec<&str> = (0..examples.len()).map(|i| examples.label(i)).collect();
+    let folds = split::stratified_group_folds(&labels, &groups, k)?;
+
+    let mut evaluations = Vec::with_capacity(k);
+    for fold in 0..k {
+        let (test, train) = split_fold(&folds, fold);
+        let (test, train) = (Subset::new(&*examples, test), Subset::new(&*examples, train));
+        println!("Fold {}/{}: learning on {} examples, checking on {}", fold + 1, k, train.len(), test.len());
+
+        // created from all the examples, so that it has an output for every label
+        let mut net = trainer.new_net(&*examples)?;
+        let mut pb = ProgressBar::new(trainer.config.max_epochs);
+        trainer.train(&mut net, &train, |_, _| { pb.inc(); Ok(()) })?;
+        pb.finish_println("");
+
+        let outcomes = metrics::outcomes(&Classifier::new(net), &test)?;
+        let evaluation = Evaluation::new(&outcomes, &[1], None);
+        println!("accuracy: {:.4}\n", evaluation.accuracy);
+        evaluations.push(evaluation);
+    }
+
+    let accuracies: Vec<f64> = evaluations.iter().map(|e| e.accuracy).collect();
+    let (mean, std) = metrics::mean_std(&accuracies);
+    println!("accuracy: {:.4} ± {:.4}", mean, std);
+
+    // metrics of every label over the folds it was checked on
+    let mut per_class: BTreeMap<&str, Vec<(f64, f64, f64)>> = BTreeMap::new();
+    for c in evaluations.iter().flat_map(|e| &e.per_class).filter(|c| c.support > 0) {
+        per_class.entry(c.label.as_str()).or_insert_with(Vec::new).push((c.precision, c.recall, c.f1));
+    }
+
+    let width = per_class.keys().map(|l| l.len()).max().unwrap_or(0);
+    println!("\n{:>w$}  {:>15}  {:>15}  {:>15}  {:>5}", "", "precision", "recall", "f1", "folds", w = width);
+    for (label, values) in &per_class {
+        println!("{:>w$}  {:>15}  {:>15}  {:>15}  {:>5}", label,
+                 mean_std_text(values.iter().map(|v| v.0).collect()),
+                 mean_std_text(values.iter().map(|v| v.1).collect()),
+                 mean_std_text(values.iter().map(|v| v.2).collect()),
+                 values.len(), w = width);
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Exporting misclassified examples.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use mulperc::img;
+use mulperc::dataset::Dataset;
+use mulperc::metrics::Outcome;
+use mulperc::error::{Error, Result};
+use cli::output::csv_field;
+
+/// Makes `label` usable as a part of a file name.
+fn sanitize(label: &str) -> String {
+    label.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' }).collect()
+}
+
+/// Writes every misclassified example to `dir`, along with an `index.csv` describing them.
+///
+/// Examples loaded from files are copied, the others are rendered as `width`x`height` images.
+/// Returns the number of written examples.
+pub fn dump_errors<P: AsRef<Path>>(dir: P,
+                                   outcomes: &[Outcome],
+                                   examples: &Dataset,
+                                   (width, height): (u32, u32)) -> Result<usize> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+
+    let index_path = dir.join("index.csv");
+    let mut index = File::create(&index_path).map_err(|e| Error::io(&index_path, e))?;
+    writeln!(index, "index,source,true,predicted,confidence,file").map_err(|e| Error::io(&index_path, e))?;
+
+    let mut count = 0;
+    for (i, outcome) in outcomes.iter().enumerate() {
+        if outcome.is_correct() {
+            continue;
+        }
+        let source = examples.source(i);
+
+        let extension = source.as_ref()
+            .and_then(|s| s.extension())
+            .and_then(|e| e.to_str())
+            .unwrap_or("png");
+        let name = format!("{:05}_true-{}_pred-{}_conf-{:.3}.{}",
+                           i, sanitize(&outcome.truth), sanitize(outcome.predicted()), outcome.confidence(), extension);
+        let out = dir.join(&name);
+
+        match source {
+            Some(ref source) => { fs::copy(source, &out).map_err(|e| Error::io(source, e))?; }
+            None => img::save(&examples.input(i)?, width, height, &out)?,
+        }
+
+        let source = source.as_ref().map(|s| s.display().to_string()).unwrap_or(String::new());
+        writeln!(index, "{},{},{},{},{},{}", i, csv_field(&source), csv_field(&outcome.truth),
+                 csv_field(outcome.predicted()), outcome.confidence(), csv_field(&name))
+            .map_err(|e| Error::io(&index_path, e))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,151 @@
+//! Self-contained HTML evaluation reports.
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use mulperc::img;
+use mulperc::dataset::Dataset;
+use mulperc::metrics::{Evaluation, Outcome};
+use mulperc::error::{Error, Result};
+
+/// Number of thumbnails in each of the galleries.
+const GALLERY_SIZE: usize = 24;
+
+const STYLE: &'static str = "
+body { font-family: 'Noto Sans', sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; }
+td, th { padding: 0.3em 0.6em; text-align: right; }
+.matrix td { min-width: 2.5em; text-align: center; }
+.bar { background: #e8e8e8; width: 300px; }
+.bar div { background: #d9822b; height: 1em; }
+.gallery { display: flex; flex-wrap: wrap; }
+.thumb { margin: 0.4em; text-align: center; font-size: 0.8em; }
+.thumb img { width: 70px; image-rendering: pixelated; border: 1px solid #ccc; }
+";
+
+/// Escapes `s` for HTML and XML text and attributes.
+pub fn escape(s: &str) -> String {
+    s.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;").replace("\"", "&quot;")
+}
+
+fn summary(e: &Evaluation) -> String {
+    let mut s = String::from("<h2>Summary</h2><table>");
+    s.push_str(&format!("<tr><th>examples</th><td>{}</td></tr>", e.total));
+    s.push_str(&format!("<tr><th>correct</th><td>{}</td></tr>", e.correct));
+    s.push_str(&format!("<tr><th>accuracy</th><td>{:.4}</td></tr>", e.accuracy));
+    for top in &e.top_k {
+        s.push_str(&format!("<tr><th>top-{} accuracy</th><td>{:.4}</td></tr>", top.k, top.accuracy));
+    }
+    s.push_str(&format!("<tr><th>macro avg f1</th><td>{:.4}</td></tr>", e.macro_avg.f1));
+    s.push_str(&format!("<tr><th>weighted avg f1</th><td>{:.4}</td></tr>", e.weighted_avg.f1));
+    s.push_str(&format!("<tr><th>expected calibration error</th><td>{:.4}</td></tr>",
+                        e.calibration.expected_calibration_error));
+    s.push_str(&format!("<tr><th>Brier score</th><td>{:.4}</td></tr>", e.calibration.brier_score));
+    if let Some(ref r) = e.rejection {
+        s.push_str(&format!("<tr><th>coverage with rejection</th><td>{:.4}</td></tr>", r.coverage));
+        s.push_str(&format!("<tr><th>accuracy with rejection</th><td>{:.4}</td></tr>", r.accuracy));
+    }
+    s.push_str("</table>");
+    s
+}
+
+/// Confusion matrix with every cell shaded by its share of the row: green on the diagonal, red elsewhere.
+fn heatmap(e: &Evaluation) -> String {
+    let labels = &e.confusion.labels;
+    let mut s = String::from("<h2>Confusion matrix</h2><p>Rows are true labels, columns predicted ones.</p>");
+    s.push_str("<table class=\"matrix\"><tr><th></th>");
+    for l in labels {
+        s.push_str(&format!("<th>{}</th>", escape(l)));
+    }
+    s.push_str("</tr>");
+    for (t, row) in e.confusion.counts.iter().enumerate() {
+        let total: usize = row.iter().sum();
+        s.push_str(&format!("<tr><th>{}</th>", escape(&labels[t])));
+        for (p, &count) in row.iter().enumerate() {
+            let share = if total == 0 { 0.0 } else { count as f64 / total as f64 };
+            let color = if t == p { "40, 160, 70" } else { "210, 50, 50" };
+            s.push_str(&format!("<td style=\"background: rgba({}, {:.3})\" title=\"{:.1}%\">{}</td>",
+                                color, share, share * 100.0, count));
+        }
+        s.push_str("</tr>");
+    }
+    s.push_str("</table>");
+    s
+}
+
+fn accuracy_bars(e: &Evaluation) -> String {
+    let mut s = String::from("<h2>Per-class accuracy</h2><table>");
+    for c in &e.per_class {
+        s.push_str(&format!("<tr><th>{}</th><td><div class=\"bar\"><div style=\"width: {:.1}%\"></div></div></td>\
+                             <td>{:.4}</td><td>{} examples</td></tr>",
+                            escape(&c.label), c.recall * 100.0, c.recall, c.support));
+    }
+    s.push_str("</table>");
+    s
+}
+
+fn coverage_bars(e: &Evaluation) -> String {
+    let mut s = String::from("<h2>Accuracy vs coverage</h2>\
+                              <p>Accuracy if only the given fraction of the examples the net is the most sure of was accepted.</p>\
+                              <table><tr><th>coverage</th><th>threshold</th><th></th><th>accuracy</th></tr>");
+    for p in &e.coverage_curve {
+        s.push_str(&format!("<tr><td>{:.2}</td><td>{:.4}</td><td><div class=\"bar\"><div style=\"width: {:.1}%\"></div></div></td>\
+                             <td>{:.4}</td></tr>",
+                            p.coverage, p.threshold, p.accuracy * 100.0, p.accuracy));
+    }
+    s.push_str("</table>");
+    s
+}
+
+fn gallery(title: &str, indices: &[usize], outcomes: &[Outcome], examples: &Dataset,
+           (width, height): (u32, u32)) -> Result<String> {
+    let mut s = format!("<h2>{}</h2><div class=\"gallery\">", escape(title));
+    if indices.is_empty() {
+        s.push_str("<p>None.</p>");
+    }
+    for &i in indices {
+        let o = &outcomes[i];
+        s.push_str(&format!("<div class=\"thumb\"><img src=\"{}\"><br>#{}<br>true: {}<br>predicted: {}<br>{:.3}</div>",
+                            img::png_data_uri(&examples.input(i)?, width, height)?, i,
+                            escape(&o.truth), escape(o.predicted()), o.confidence()));
+    }
+    s.push_str("</div>");
+    Ok(s)
+}
+
+/// Writes an HTML report to `path`, with images of `examples` embedded as `width`x`height` PNGs.
+///
+/// Besides the metrics, it shows the correct predictions the net was the least sure of
+/// and the incorrect ones it was the most sure of.
+pub fn write_report<P: AsRef<Path>>(path: P,
+                                    title: &str,
+                                    evaluation: &Evaluation,
+                                    outcomes: &[Outcome],
+                                    examples: &Dataset,
+                                    dims: (u32, u32)) -> Result<()> {
+    let by_confidence = |a: &usize, b: &usize| outcomes[*a].confidence()
+        .partial_cmp(&outcomes[*b].confidence()).unwrap_or(Ordering::Equal);
+
+    let mut correct: Vec<usize> = (0..outcomes.len()).filter(|&i| outcomes[i].is_correct()).collect();
+    correct.sort_by(&by_confidence);
+    correct.truncate(GALLERY_SIZE);
+
+    let mut incorrect: Vec<usize> = (0..outcomes.len()).filter(|&i| !outcomes[i].is_correct()).collect();
+    incorrect.sort_by(|a, b| by_confidence(b, a));
+    incorrect.truncate(GALLERY_SIZE);
+
+    let html = format!("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title><style>{style}</style></head>\
+                        <body><h1>{title}</h1>{summary}{heatmap}{bars}{coverage}{correct}{incorrect}</body></html>\n",
+                       title = escape(title),
+                       style = STYLE,
+                       summary = summary(evaluation),
+                       heatmap = heatmap(evaluation),
+                       bars = accuracy_bars(evaluation),
+                       coverage = coverage_bars(evaluation),
+                       correct = gallery("Least confident correct predictions", &correct, outcomes, examples, dims)?,
+                       incorrect = gallery("Most confident incorrect predictions", &incorrect, outcomes, examples, dims)?);
+
+    let path = path.as_ref();
+    File::create(path).and_then(|mut f| f.write_all(html.as_bytes())).map_err(|e| Error::io(path, e))
+}
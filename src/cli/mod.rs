@@ -0,0 +1,15 @@
+//! Command line interface.
+
+pub mod args;
+pub mod validators;
+pub mod classifier;
+pub mod autoencoder;
+pub mod predict;
+pub mod crossval;
+pub mod split;
+pub mod search;
+pub mod output;
+pub mod report;
+pub mod dump;
+pub mod html;
+pub mod plot;
@@ -0,0 +1,43 @@
+//! Formatting of the results printed by the subcommands.
+
+use std::str::FromStr;
+use mulperc::error::Error;
+
+/// How results are written to the standard output.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    /// Aligned columns for humans.
+    Table,
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Format, Error> {
+        match s {
+            "table" | "text" => Ok(Format::Table),
+            "csv" => Ok(Format::Csv),
+            "jsonl" | "json" => Ok(Format::JsonLines),
+            _ => Err(Error::argument("format", format!("unknown format {:?}", s))),
+        }
+    }
+}
+
+/// Quotes `s` if it can't be put into a CSV file as is.
+pub fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        format!("\"{}\"", s.replace("\"", "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[test]
+fn test_csv_field() {
+    assert_eq!(csv_field("3"), "3");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
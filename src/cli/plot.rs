@@ -0,0 +1,319 @@
+//! Learning curves of runs, rendered to SVG or PNG without a display.
+
+use clap;
+use image::{self, Rgb, RgbImage};
+use rusttype::{point, Font, FontCollection, Scale};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use mulperc::run::{self, EpochRecord};
+use mulperc::error::{Error, Result};
+use cli::classifier::parse_arg;
+use cli::html::escape;
+
+const FONT: &'static [u8] = include_bytes!("../../assets/fonts/NotoSans/NotoSans-Regular.ttf");
+
+/// Colors of the runs, in order.
+const PALETTE: [[u8; 3]; 8] = [
+    [31, 119, 180], [255, 127, 14], [44, 160, 44], [214, 39, 40],
+    [148, 103, 189], [140, 86, 75], [227, 119, 194], [127, 127, 127],
+];
+const TEXT_COLOR: [u8; 3] = [34, 34, 34];
+const AXIS_COLOR: [u8; 3] = [120, 120, 120];
+const GRID_COLOR: [u8; 3] = [225, 225, 225];
+
+const WIDTH: u32 = 800;
+const CHART_HEIGHT: u32 = 400;
+/// Space around the plot area of a chart: left, right, top, bottom.
+const MARGINS: (f64, f64, f64, f64) = (70.0, 20.0, 40.0, 45.0);
+const FONT_SIZE: f64 = 13.0;
+/// Lengths of the dashes and the gaps of dashed lines.
+const DASH: (f64, f64) = (6.0, 4.0);
+
+struct Series {
+    name: String,
+    color: [u8; 3],
+    dashed: bool,
+    points: Vec<(f64, f64)>,
+}
+
+struct Chart {
+    title: &'static str,
+    series: Vec<Series>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Where charts are drawn, in pixels from the top left corner.
+trait Canvas {
+    /// A thin solid line.
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: [u8; 3]);
+
+    /// A thick line through `points`.
+    fn polyline(&mut self, points: &[(f64, f64)], color: [u8; 3], dashed: bool);
+
+    /// `text` with its baseline at `at.1`, starting, centered or ending at `at.0`.
+    fn text(&mut self, at: (f64, f64), text: &str, anchor: Anchor);
+}
+
+struct Svg {
+    body: String,
+}
+
+fn svg_color(color: [u8; 3]) -> String {
+    format!("rgb({},{},{})", color[0], color[1], color[2])
+}
+
+impl Canvas for Svg {
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: [u8; 3]) {
+        self.body.push_str(&format!("<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\"/>\n",
+                                    from.0, from.1, to.0, to.1, svg_color(color)));
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], color: [u8; 3], dashed: bool) {
+        let points: Vec<String> = points.iter().map(|&(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+        let dash = if dashed { format!(" stroke-dasharray=\"{},{}\"", DASH.0, DASH.1) } else { String::new() };
+        self.body.push_str(&format!("<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"{}/>\n",
+                                    points.join(" "), svg_color(color), dash));
+    }
+
+    fn text(&mut self, at: (f64, f64), text: &str, anchor: Anchor) {
+        let anchor = match anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        };
+        self.body.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\" fill=\"{}\">{}</text>\n",
+                                    at.0, at.1, anchor, svg_color(TEXT_COLOR), escape(text)));
+    }
+}
+
+impl Svg {
+    fn finish(self, width: u32, height: u32) -> String {
+        format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+                 font-family=\"Noto Sans, sans-serif\" font-size=\"{size}\">\n\
+                 <rect width=\"{w}\" height=\"{h}\" fill=\"white\"/>\n{body}</svg>\n",
+                w = width, h = height, size = FONT_SIZE, body = self.body)
+    }
+}
+
+struct Png {
+    image: RgbImage,
+    font: Font<'static>,
+}
+
+/// Mixes `color` into the pixel at `(x, y)` of `image` with the given opacity.
+fn blend(image: &mut RgbImage, x: i64, y: i64, color: [u8; 3], opacity: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    for c in 0..3 {
+        pixel.data[c] = (pixel.data[c] as f32 * (1.0 - opacity) + color[c] as f32 * opacity).round() as u8;
+    }
+}
+
+impl Png {
+    /// Walks `points` in half pixel steps, plotting a square of `width` pixels wherever the dash pattern is on.
+    fn stroke(&mut self, points: &[(f64, f64)], color: [u8; 3], width: i64, dashed: bool) {
+        let mut walked = 0.0;
+        for pair in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            let length = ((x1 - x0) * (x1 - x0) + (y1 - y0) * (y1 - y0)).sqrt();
+            let steps = (length * 2.0).ceil() as usize;
+            for i in 0..steps + 1 {
+                let t = if steps == 0 { 0.0 } else { i as f64 / steps as f64 };
+                if dashed && (walked + t * length) % (DASH.0 + DASH.1) >= DASH.0 {
+                    continue;
+                }
+                let (x, y) = ((x0 + t * (x1 - x0)).round() as i64, (y0 + t * (y1 - y0)).round() as i64);
+                for dx in 0..width {
+                    for dy in 0..width {
+                        blend(&mut self.image, x + dx, y + dy, color, 1.0);
+                    }
+                }
+            }
+            walked += length;
+        }
+    }
+}
+
+impl Canvas for Png {
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), color: [u8; 3]) {
+        self.stroke(&[from, to], color, 1, false);
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], color: [u8; 3], dashed: bool) {
+        self.stroke(points, color, 2, dashed);
+    }
+
+    fn text(&mut self, at: (f64, f64), text: &str, anchor: Anchor) {
+        let scale = Scale::uniform(FONT_SIZE as f32);
+        let width = self.font.layout(text, scale, point(0.0, 0.0)).last()
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+            .unwrap_or(0.0) as f64;
+        let x = match anchor {
+            Anchor::Start => at.0,
+            Anchor::Middle => at.0 - width / 2.0,
+            Anchor::End => at.0 - width,
+        };
+
+        let image = &mut self.image;
+        for glyph in self.font.layout(text, scale, point(x as f32, at.1 as f32)) {
+            if let Some(bb) = glyph.pixel_bounding_box() {
+                glyph.draw(|gx, gy, v| {
+                    blend(image, bb.min.x as i64 + gx as i64, bb.min.y as i64 + gy as i64, TEXT_COLOR, v)
+                });
+            }
+        }
+    }
+}
+
+/// About `n` round values covering `[min, max]`.
+fn nice_ticks(min: f64, max: f64, n: usize) -> Vec<f64> {
+    let raw_step = (max - min) / n as f64;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0].iter().map(|m| m * magnitude).find(|&s| s >= raw_step).unwrap_or(10.0 * magnitude);
+    let first = (min / step).floor() as i64;
+    let last = (max / step).ceil() as i64;
+    (first..last + 1).map(|i| i as f64 * step).collect()
+}
+
+/// `value` with as many decimals as the ticks `step` apart need.
+fn tick_label(value: f64, step: f64) -> String {
+    let decimals = if step >= 1.0 { 0 } else { (-step.log10().floor()) as usize };
+    format!("{:.*}", decimals, value)
+}
+
+fn draw_chart<C: Canvas>(canvas: &mut C, chart: &Chart, top: f64) {
+    let (left, right) = (MARGINS.0, WIDTH as f64 - MARGINS.1);
+    let (plot_top, bottom) = (top + MARGINS.2, top + CHART_HEIGHT as f64 - MARGINS.3);
+
+    let points = || chart.series.iter().flat_map(|s| s.points.iter());
+    let x_max = points().map(|p| p.0).fold(1.0, f64::max);
+    let (mut y_min, mut y_max) = points().fold((::std::f64::INFINITY, ::std::f64::NEG_INFINITY),
+                                               |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    if !(y_min < y_max) {
+        let pad = if y_min.is_finite() && y_min != 0.0 { y_min.abs() / 2.0 } else { 1.0 };
+        y_min = if y_min.is_finite() { y_min - pad } else { 0.0 };
+        y_max = y_min + 2.0 * pad;
+    }
+
+    let x_ticks = nice_ticks(0.0, x_max, 8);
+    let y_ticks = nice_ticks(y_min, y_max, 5);
+    let (x_to, y_from, y_to) = (*x_ticks.last().unwrap(), y_ticks[0], *y_ticks.last().unwrap());
+    let px = |x: f64| left + x / x_to * (right - left);
+    let py = |y: f64| bottom - (y - y_from) / (y_to - y_from) * (bottom - plot_top);
+
+    canvas.text(((left + right) / 2.0, top + MARGINS.2 / 2.0 + FONT_SIZE / 2.0), chart.title, Anchor::Middle);
+
+    let x_step = x_ticks.get(1).map(|t| t - x_ticks[0]).unwrap_or(1.0);
+    for &x in &x_ticks {
+        canvas.line((px(x), plot_top), (px(x), bottom), GRID_COLOR);
+        canvas.text((px(x), bottom + FONT_SIZE + 4.0), &tick_label(x, x_step), Anchor::Middle);
+    }
+    let y_step = y_ticks.get(1).map(|t| t - y_ticks[0]).unwrap_or(1.0);
+    for &y in &y_ticks {
+        canvas.line((left, py(y)), (right, py(y)), GRID_COLOR);
+        canvas.text((left - 6.0, py(y) + FONT_SIZE / 3.0), &tick_label(y, y_step), Anchor::End);
+    }
+    canvas.line((left, plot_top), (left, bottom), AXIS_COLOR);
+    canvas.line((left, bottom), (right, bottom), AXIS_COLOR);
+    canvas.text(((left + right) / 2.0, bottom + 2.0 * FONT_SIZE + 8.0), "epoch", Anchor::Middle);
+
+    for series in &chart.series {
+        let points: Vec<(f64, f64)> = series.points.iter().map(|&(x, y)| (px(x), py(y))).collect();
+        canvas.polyline(&points, series.color, series.dashed);
+    }
+
+    for (i, series) in chart.series.iter().enumerate() {
+        let y = plot_top + 16.0 * (i + 1) as f64;
+        canvas.polyline(&[(right - 200.0, y - 4.0), (right - 170.0, y - 4.0)], series.color, series.dashed);
+        canvas.text((right - 164.0, y), &series.name, Anchor::Start);
+    }
+}
+
+/// Mean of every value and the `window - 1` ones before it.
+fn smooth(points: Vec<(f64, f64)>, window: usize) -> Vec<(f64, f64)> {
+    if window <= 1 {
+        return points;
+    }
+    let mut sum = 0.0;
+    (0..points.len()).map(|i| {
+        sum += points[i].1;
+        if i >= window {
+            sum -= points[i - window].1;
+        }
+        (points[i].0, sum / ::std::cmp::min(i + 1, window) as f64)
+    }).collect()
+}
+
+fn series<F>(name: String, color: [u8; 3], dashed: bool, records: &[EpochRecord], window: usize, value: F) -> Option<Series>
+    where F: Fn(&EpochRecord) -> Option<f64>
+{
+    let points: Vec<(f64, f64)> = records.iter().filter_map(|r| value(r).map(|v| (r.epoch as f64, v))).collect();
+    if points.is_empty() {
+        return None;
+    }
+    Some(Series { name: name, color: color, dashed: dashed, points: smooth(points, window) })
+}
+
+pub fn plot(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let out = Path::new(matches.value_of("output").unwrap());
+    let window: usize = parse_arg(matches, "smooth")?;
+
+    let mut loss = Chart { title: "Loss", series: Vec::new() };
+    let mut accuracy = Chart { title: "Accuracy (dashed: validation)", series: Vec::new() };
+    for (i, dir) in matches.values_of("runs").unwrap().enumerate() {
+        let records = run::read_metrics(dir)?;
+        let name = Path::new(dir).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or(dir.to_string());
+        let color = PALETTE[i % PALETTE.len()];
+
+        loss.series.extend(series(name.clone(), color, false, &records, window, |r| Some(r.loss)));
+        accuracy.series.extend(series(format!("{} train", name), color, false, &records, window, |r| r.train_accuracy));
+        accuracy.series.extend(series(format!("{} validation", name), color, true, &records, window,
+                                      |r| r.validation_accuracy));
+    }
+
+    let charts: Vec<&Chart> = vec![&loss, &accuracy].into_iter().filter(|c| !c.series.is_empty()).collect();
+    let height = CHART_HEIGHT * charts.len() as u32;
+
+    match out.extension().and_then(|e| e.to_str()) {
+        Some("svg") => {
+            let mut svg = Svg { body: String::new() };
+            for (i, chart) in charts.iter().enumerate() {
+                draw_chart(&mut svg, chart, (i as u32 * CHART_HEIGHT) as f64);
+            }
+            let text = svg.finish(WIDTH, height);
+            File::create(out).and_then(|mut f| f.write_all(text.as_bytes())).map_err(|e| Error::io(out, e))?;
+        }
+        Some("png") => {
+            let font = FontCollection::from_bytes(FONT).into_font().expect("the bundled font is valid");
+            let mut png = Png { image: RgbImage::from_pixel(WIDTH, height, Rgb { data: [255, 255, 255] }), font: font };
+            for (i, chart) in charts.iter().enumerate() {
+                draw_chart(&mut png, chart, (i as u32 * CHART_HEIGHT) as f64);
+            }
+            let ref mut file = File::create(out).map_err(|e| Error::io(out, e))?;
+            image::DynamicImage::ImageRgb8(png.image).save(file, image::PNG).map_err(|e| Error::image(out, e))?;
+        }
+        _ => return Err(Error::argument("output", format!("{} is neither .svg nor .png", out.display()))),
+    }
+
+    println!("Wrote the plot to {}", out.display());
+    Ok(())
+}
+
+#[test]
+fn test_nice_ticks() {
+    assert_eq!(nice_ticks(0.0, 50.0, 5), vec![0.0, 10.0, 20.0, 30.0, 40.0, 50.0]);
+    let ticks = nice_ticks(0.12, 0.93, 4);
+    assert_eq!(ticks.len(), 6);
+    assert!(ticks[0] == 0.0 && (ticks[5] - 1.0).abs() < 1e-12);
+    assert_eq!(tick_label(0.4, 0.2), "0.4");
+    assert_eq!(tick_label(40.0, 10.0), "40");
+}
@@ -0,0 +1,257 @@
+use clap;
+use serde_json;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use mulperc::{Classifier, MultiLabelClassifier, NetFile, Regressor};
+use mulperc::dataset::IMAGE_EXTENSIONS;
+use mulperc::inference::{Decision, Ranking};
+use mulperc::multilayer_perceptron::Task;
+use mulperc::error::{Error, Result};
+use cli::output::{self, Format};
+
+#[derive(Serialize)]
+struct Prediction<'a> {
+    label: &'a str,
+    score: f64,
+}
+
+#[derive(Serialize)]
+struct Output<'a> {
+    target: &'a str,
+    value: f64,
+}
+
+/// A line of the JSON output of regressors.
+#[derive(Serialize)]
+struct Outputs<'a> {
+    file: String,
+    outputs: Vec<Output<'a>>,
+}
+
+/// A line of the JSON output of multi-label classifiers.
+#[derive(Serialize)]
+struct Labels<'a> {
+    file: String,
+    /// The labels above their thresholds, the most probable first.
+    labels: Vec<Prediction<'a>>,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    file: String,
+    /// The best label, `None` if the net's reject rule rejected it.
+    decision: Option<&'a str>,
+    predictions: Vec<Prediction<'a>>,
+}
+
+/// Expands the directories among `inputs` into the images they contain, skipping hidden files.
+fn input_files(inputs: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(path).map_err(|e| Error::io(path, e))? {
+                let entry = entry.map_err(|e| Error::io(path, e))?.path();
+                let hidden = entry.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with('.')).unwrap_or(true);
+                let extension = entry.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+                let is_image = extension.map(|e| IMAGE_EXTENSIONS.contains(&e.as_str())).unwrap_or(false);
+                if entry.is_file() && !hidden && is_image {
+                    entries.push(entry);
+                }
+            }
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+pub fn predict(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let in_net = matches.value_of("in-net").unwrap();
+    let inputs: Vec<&str> = matches.values_of("inputs").unwrap().collect();
+    let format: Format = matches.value_of("format").unwrap().parse()?;
+    let top_k: usize = matches.value_of("top-k").unwrap().parse()
+        .map_err(|_| Error::argument("top-k", "not a number"))?;
+
+    let net = NetFile::load(in_net)?;
+    let files = input_files(&inputs)?;
+    match net.task {
+        Task::Regression(_) => return regress(&Regressor::new(net)?, &files, format),
+        Task::MultiLabel(_) => return tag(&MultiLabelClassifier::new(net)?, &files, format),
+        Task::Autoencoder => return Err(Error::net(in_net, "autoencoders predict nothing")),
+        Task::Classification => (),
+    }
+    let classifier = Classifier::new(net);
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let file_width = files.iter().map(|f| f.display().to_string().len()).max().unwrap_or(0);
+
+    if format == Format::Csv {
+        let mut header = "file,decision".to_string();
+        for i in 1..top_k + 1 {
+            header.push_str(&format!(",label_{0},score_{0}", i));
+        }
+        writeln!(out, "{}", header).map_err(|e| Error::io("<stdout>", e))?;
+    }
+
+    for file in &files {
+        let pixels = classifier.net().preprocessing.load(file);
+        let mut ranking: Ranking = match pixels.and_then(|pixels| classifier.classify(&pixels)) {
+            Ok(ranking) => ranking,
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "warning: skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let decision = match classifier.decision(ranking.clone()) {
+            Decision::Label(label, _) => Some(label),
+            Decision::Unknown => None,
+        };
+        ranking.truncate(top_k);
+        let name = file.display().to_string();
+
+        let line = match format {
+            Format::Table => {
+                let mut line = format!("{:<1$}", name, file_width);
+                if decision.is_none() {
+                    line.push_str("  unknown:");
+                }
+                for &(ref label, score) in &ranking {
+                    line.push_str(&format!("  {} ({:.3})", label, score));
+                }
+                line
+            }
+            Format::Csv => {
+                let mut line = format!("{},{}", output::csv_field(&name),
+                                       decision.as_ref().map(|l| output::csv_field(l)).unwrap_or(String::new()));
+                for &(ref label, score) in &ranking {
+                    line.push_str(&format!(",{},{}", output::csv_field(label), score));
+                }
+                line
+            }
+            Format::JsonLines => {
+                let line = Line {
+                    file: name,
+                    decision: decision.as_ref().map(|l| l.as_str()),
+                    predictions: ranking.iter()
+                        .map(|&(ref label, score)| Prediction { label: label, score: score })
+                        .collect(),
+                };
+                serde_json::to_string(&line).unwrap()
+            }
+        };
+        writeln!(out, "{}", line).map_err(|e| Error::io("<stdout>", e))?;
+    }
+
+    Ok(())
+}
+
+/// Prints the raw outputs of `regressor` for every file.
+fn regress(regressor: &Regressor, files: &[PathBuf], format: Format) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let file_width = files.iter().map(|f| f.display().to_string().len()).max().unwrap_or(0);
+    let targets = regressor.targets();
+
+    if format == Format::Csv {
+        let mut header = "file".to_string();
+        for target in targets {
+            header.push(',');
+            header.push_str(&output::csv_field(target));
+        }
+        writeln!(out, "{}", header).map_err(|e| Error::io("<stdout>", e))?;
+    }
+
+    for file in files {
+        let values = match regressor.net().preprocessing.load(file).and_then(|pixels| regressor.predict(&pixels)) {
+            Ok(values) => values,
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "warning: skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let name = file.display().to_string();
+
+        let line = match format {
+            Format::Table => {
+                let mut line = format!("{:<1$}", name, file_width);
+                for (target, value) in targets.iter().zip(&values) {
+                    line.push_str(&format!("  {}={}", target, value));
+                }
+                line
+            }
+            Format::Csv => {
+                let mut line = output::csv_field(&name);
+                for value in &values {
+                    line.push_str(&format!(",{}", value));
+                }
+                line
+            }
+            Format::JsonLines => {
+                let line = Outputs {
+                    file: name,
+                    outputs: targets.iter().zip(&values)
+                        .map(|(target, &value)| Output { target: target, value: value })
+                        .collect(),
+                };
+                serde_json::to_string(&line).unwrap()
+            }
+        };
+        writeln!(out, "{}", line).map_err(|e| Error::io("<stdout>", e))?;
+    }
+
+    Ok(())
+}
+
+/// Prints the labels `classifier` predicts for every file, with their probabilities.
+fn tag(classifier: &MultiLabelClassifier, files: &[PathBuf], format: Format) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let file_width = files.iter().map(|f| f.display().to_string().len()).max().unwrap_or(0);
+
+    if format == Format::Csv {
+        writeln!(out, "file,labels").map_err(|e| Error::io("<stdout>", e))?;
+    }
+
+    for file in files {
+        let labels = match classifier.net().preprocessing.load(file).and_then(|pixels| classifier.predict(&pixels)) {
+            Ok(labels) => labels,
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "warning: skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let name = file.display().to_string();
+
+        let line = match format {
+            Format::Table => {
+                let mut line = format!("{:<1$}", name, file_width);
+                for &(ref label, score) in &labels {
+                    line.push_str(&format!("  {} ({:.3})", label, score));
+                }
+                line
+            }
+            Format::Csv => {
+                let names: Vec<&str> = labels.iter().map(|&(ref label, _)| label.as_str()).collect();
+                format!("{},{}", output::csv_field(&name), output::csv_field(&names.join(";")))
+            }
+            Format::JsonLines => {
+                let line = Labels {
+                    file: name,
+                    labels: labels.iter()
+                        .map(|&(ref label, score)| Prediction { label: label, score: score })
+                        .collect(),
+                };
+                serde_json::to_string(&line).unwrap()
+            }
+        };
+        writeln!(out, "{}", line).map_err(|e| Error::io("<stdout>", e))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Rendering of `check` evaluations.
+
+use serde_json;
+use mulperc::metrics::{Averages, Evaluation};
+use mulperc::regression::RegressionMetrics;
+use mulperc::multilabel::MultiLabelMetrics;
+use cli::output::{csv_field, Format};
+
+pub fn render(evaluation: &Evaluation, format: Format) -> String {
+    match format {
+        Format::Table => text(evaluation),
+        Format::Csv => csv(evaluation),
+        Format::JsonLines => serde_json::to_string_pretty(evaluation).unwrap(),
+    }
+}
+
+pub fn render_regression(metrics: &RegressionMetrics, format: Format) -> String {
+    match format {
+        Format::Table => regression_text(metrics),
+        Format::Csv => regression_csv(metrics),
+        Format::JsonLines => serde_json::to_string_pretty(metrics).unwrap(),
+    }
+}
+
+pub fn render_multilabel(metrics: &MultiLabelMetrics, format: Format) -> String {
+    match format {
+        Format::Table => multilabel_text(metrics),
+        Format::Csv => multilabel_csv(metrics),
+        Format::JsonLines => serde_json::to_string_pretty(metrics).unwrap(),
+    }
+}
+
+fn multilabel_text(m: &MultiLabelMetrics) -> String {
+    let mut s = format!("{} examples\nHamming loss: {:.4}\nsubset accuracy: {:.4}\nmacro F1: {:.4}\nmicro F1: {:.4}\n",
+                        m.total, m.hamming_loss, m.subset_accuracy, m.macro_f1, m.micro_f1);
+    let width = m.per_label.iter().map(|l| l.label.len()).chain(Some("label".len())).max().unwrap();
+    s.push_str(&format!("\n{:>w$}  {:>9}  {:>9}  {:>9}  {:>9}\n", "label", "precision", "recall", "f1", "support",
+                        w = width));
+    for l in &m.per_label {
+        s.push_str(&format!("{:>w$}  {:>9.4}  {:>9.4}  {:>9.4}  {:>9}\n",
+                            l.label, l.precision, l.recall, l.f1, l.support, w = width));
+    }
+    s
+}
+
+fn multilabel_csv(m: &MultiLabelMetrics) -> String {
+    let mut s = String::from("label,precision,recall,f1,support\n");
+    for l in &m.per_label {
+        s.push_str(&format!("{},{},{},{},{}\n", csv_field(&l.label), l.precision, l.recall, l.f1, l.support));
+    }
+    s.push_str(&format!("macro f1,,,{},{}\n", m.macro_f1, m.total));
+    s.push_str(&format!("micro f1,,,{},{}\n", m.micro_f1, m.total));
+    s.push_str(&format!("hamming loss,,,{},{}\n", m.hamming_loss, m.total));
+    s.push_str(&format!("subset accuracy,,,{},{}\n", m.subset_accuracy, m.total));
+    s
+}
+
+fn regression_text(m: &RegressionMetrics) -> String {
+    let mut s = format!("{} examples\nMAE: {:.4}\nRMSE: {:.4}\nR²: {:.4}\n", m.total, m.mae, m.rmse, m.r2);
+    let width = m.per_target.iter().map(|t| t.name.len()).chain(Some("target".len())).max().unwrap();
+    s.push_str(&format!("\n{:>w$}  {:>9}  {:>9}  {:>9}\n", "target", "MAE", "RMSE", "R²", w = width));
+    for t in &m.per_target {
+        s.push_str(&format!("{:>w$}  {:>9.4}  {:>9.4}  {:>9.4}\n", t.name, t.mae, t.rmse, t.r2, w = width));
+    }
+    s
+}
+
+fn regression_csv(m: &RegressionMetrics) -> String {
+    let mut s = String::from("target,mae,rmse,r2,support\n");
+    for t in &m.per_target {
+        s.push_str(&format!("{},{},{},{},{}\n", csv_field(&t.name), t.mae, t.rmse, t.r2, m.total));
+    }
+    s.push_str(&format!("all,{},{},{},{}\n", m.mae, m.rmse, m.r2, m.total));
+    s
+}
+
+fn text(e: &Evaluation) -> String {
+    let mut s = format!("{} / {} correct\n", e.correct, e.total);
+    for top in &e.top_k {
+        s.push_str(&format!("top-{} accuracy: {:.4}\n", top.k, top.accuracy));
+    }
+    s.push_str(&format!("expected calibration error: {:.4}\n", e.calibration.expected_calibration_error));
+    s.push_str(&format!("Brier score: {:.4}\n", e.calibration.brier_score));
+    if let Some(ref r) = e.rejection {
+        s.push_str(&format!("with rejection: coverage {:.4}, accuracy {:.4}\n", r.coverage, r.accuracy));
+    }
+
+    let width = e.per_class.iter().map(|c| c.label.len()).chain(Some("weighted avg".len())).max().unwrap();
+    s.push_str(&format!("\n{:>w$}  {:>9}  {:>9}  {:>9}  {:>9}\n", "", "precision", "recall", "f1", "support", w = width));
+    for c in &e.per_class {
+        s.push_str(&format!("{:>w$}  {:>9.4}  {:>9.4}  {:>9.4}  {:>9}\n",
+                            c.label, c.precision, c.recall, c.f1, c.support, w = width));
+    }
+    for &(name, avg) in &[("macro avg", &e.macro_avg), ("weighted avg", &e.weighted_avg)] {
+        s.push_str(&format!("{:>w$}  {:>9.4}  {:>9.4}  {:>9.4}  {:>9}\n",
+                            name, avg.precision, avg.recall, avg.f1, e.total, w = width));
+    }
+
+    let labels = &e.confusion.labels;
+    let cell = labels.iter().map(|l| l.len())
+        .chain(e.confusion.counts.iter().flat_map(|row| row.iter().map(|c| c.to_string().len())))
+        .max().unwrap_or(1);
+    s.push_str("\nconfusion matrix (rows: true labels, columns: predicted labels)\n");
+    s.push_str(&format!("{:>w$}", "", w = width));
+    for l in labels {
+        s.push_str(&format!("  {:>w$}", l, w = cell));
+    }
+    s.push('\n');
+    for (l, row) in labels.iter().zip(&e.confusion.counts) {
+        s.push_str(&format!("{:>w$}", l, w = width));
+        for c in row {
+            s.push_str(&format!("  {:>w$}", c, w = cell));
+        }
+        s.push('\n');
+    }
+
+    s.push_str("\nreliability diagram\n");
+    s.push_str(&format!("{:>11}  {:>7}  {:>10}  {:>8}\n", "confidence", "count", "mean conf.", "accuracy"));
+    for b in &e.calibration.reliability_diagram {
+        s.push_str(&format!("{:>4.2}..{:>4.2}  {:>7}  {:>10.4}  {:>8.4}\n", b.lower, b.upper, b.count, b.confidence, b.accuracy));
+    }
+
+    s.push_str("\naccuracy vs coverage\n");
+    s.push_str(&format!("{:>8}  {:>9}  {:>8}\n", "coverage", "threshold", "accuracy"));
+    for p in &e.coverage_curve {
+        s.push_str(&format!("{:>8.4}  {:>9.4}  {:>8.4}\n", p.coverage, p.threshold, p.accuracy));
+    }
+    s
+}
+
+fn csv_averages(name: &str, avg: &Averages, support: usize) -> String {
+    format!("{},{},{},{},{}\n", name, avg.precision, avg.recall, avg.f1, support)
+}
+
+fn csv(e: &Evaluation) -> String {
+    let mut s = String::from("label,precision,recall,f1,support\n");
+    for c in &e.per_class {
+        s.push_str(&format!("{},{},{},{},{}\n", csv_field(&c.label), c.precision, c.recall, c.f1, c.support));
+    }
+    s.push_str(&csv_averages("macro avg", &e.macro_avg, e.total));
+    s.push_str(&csv_averages("weighted avg", &e.weighted_avg, e.total));
+    s.push_str(&format!("accuracy,,,{},{}\n", e.accuracy, e.total));
+    for top in &e.top_k {
+        s.push_str(&format!("top-{} accuracy,,,{},{}\n", top.k, top.accuracy, e.total));
+    }
+    s.push_str(&format!("expected calibration error,,,{},{}\n", e.calibration.expected_calibration_error, e.total));
+    s.push_str(&format!("Brier score,,,{},{}\n", e.calibration.brier_score, e.total));
+    if let Some(ref r) = e.rejection {
+        s.push_str(&format!("coverage with rejection,,,{},{}\n", r.coverage, e.total));
+        s.push_str(&format!("accuracy with rejection,,,{},{}\n", r.accuracy, e.total));
+    }
+
+    s.push_str("\ntrue\\predicted");
+    for l in &e.confusion.labels {
+        s.push(',');
+        s.push_str(&csv_field(l));
+    }
+    s.push('\n');
+    for (l, row) in e.confusion.labels.iter().zip(&e.confusion.counts) {
+        s.push_str(&csv_field(l));
+        for c in row {
+            s.push_str(&format!(",{}", c));
+        }
+        s.push('\n');
+    }
+
+    s.push_str("\nlower,upper,count,confidence,accuracy\n");
+    for b in &e.calibration.reliability_diagram {
+        s.push_str(&format!("{},{},{},{},{}\n", b.lower, b.upper, b.count, b.confidence, b.accuracy));
+    }
+
+    s.push_str("\ncoverage,threshold,accuracy\n");
+    for p in &e.coverage_curve {
+        s.push_str(&format!("{},{},{}\n", p.coverage, p.threshold, p.accuracy));
+    }
+    s
+}
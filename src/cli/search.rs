@@ -0,0 +1,153 @@
+use clap;
+use serde_json;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use mulperc::{split, util, NetFile};
+use mulperc::autoencoder::AutoencoderConfig;
+use mulperc::dataset::{self, Dataset, Subset};
+use mulperc::search::{self, AutoencoderSearch, ClassifierSearch, Objective, Params, Schedule, Space, Trial};
+use mulperc::error::{Error, Result};
+use cli::classifier::{open_dataset, parse_arg, preprocessing, train_config};
+use cli::crossval::author_groups;
+use cli::output::csv_field;
+
+/// Number of folds the dataset is split into when there's no validation dataset; one of them is held out.
+const HOLDOUT_FOLDS: usize = 5;
+
+/// Indices of the learning examples of `data`, and of the ones held out for validation.
+fn holdout(data: &Dataset) -> Result<(Vec<usize>, Vec<usize>)> {
+    let labels: Vec<&str> = (0..data.len()).map(|i| data.label(i)).collect();
+    let folds = split::stratified_group_folds(&labels, &author_groups(data), HOLDOUT_FOLDS)?;
+    let (validation, learning) = (0..data.len()).partition(|&i| folds[i] == 0);
+    Ok((learning, validation))
+}
+
+fn space(matches: &clap::ArgMatches<'static>) -> Result<Space> {
+    let mut space = Space::new();
+    for param in matches.values_of("param").unwrap() {
+        let i = param.find('=').ok_or_else(|| Error::argument("param", format!("{:?} is not NAME=VALUES", param)))?;
+        space.add(&param[..i], param[i + 1..].parse()?);
+    }
+    Ok(space)
+}
+
+fn write_leaderboard<P: AsRef<Path>>(path: P, leaderboard: &[Trial]) -> Result<()> {
+    let path = path.as_ref();
+    let text = if path.extension().map(|e| e == "json").unwrap_or(false) {
+        serde_json::to_string_pretty(&leaderboard).unwrap()
+    } else {
+        let names: Vec<&String> = leaderboard[0].params.keys().collect();
+        let mut s = String::from("rank,trial,epochs,score");
+        for name in &names {
+            s.push(',');
+            s.push_str(&csv_field(name));
+        }
+        s.push('\n');
+        for (rank, trial) in leaderboard.iter().enumerate() {
+            s.push_str(&format!("{},{},{},{}", rank + 1, trial.id, trial.epochs, trial.score));
+            for name in &names {
+                s.push_str(&format!(",{}", trial.params[*name]));
+            }
+            s.push('\n');
+        }
+        s
+    };
+    File::create(path).and_then(|mut f| f.write_all(text.as_bytes())).map_err(|e| Error::io(path, e))
+}
+
+fn run<O: Objective>(matches: &clap::ArgMatches<'static>, objective: &O) -> Result<O::Model> {
+    let space = space(matches)?;
+    let candidates: Vec<Params> = match matches.value_of("strategy").unwrap() {
+        "grid" => space.grid()?,
+        _ => {
+            let seed = match matches.value_of("seed") {
+                Some(_) => Some(parse_arg(matches, "seed")?),
+                None => None,
+            };
+            space.random(parse_arg(matches, "trials")?, &mut util::rng(seed, search::CANDIDATES_STREAM))
+        }
+    };
+    let schedule = Schedule {
+        epochs: parse_arg(matches, "max-epochs")?,
+        halving: match matches.value_of("halving") {
+            Some(_) => Some(parse_arg(matches, "halving")?),
+            None => None,
+        },
+    };
+
+    println!("Running {} trials...", candidates.len());
+    let (leaderboard, best) = search::search(objective, candidates, &schedule)?;
+
+    for (rank, trial) in leaderboard.iter().enumerate().take(10) {
+        let params: Vec<String> = trial.params.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        println!("{:>3}. score {:>9.4} after {:>5} epochs  {}", rank + 1, trial.score, trial.epochs, params.join(" "));
+    }
+    if let Some(path) = matches.value_of("leaderboard") {
+        write_leaderboard(path, &leaderboard)?;
+        println!("Wrote the leaderboard to {}", path);
+    }
+    Ok(best)
+}
+
+pub fn search(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let pipeline = preprocessing(matches)?;
+    let source = matches.value_of("dataset").unwrap();
+    dataset::check_preprocessing(source, &pipeline)?;
+    let data = open_dataset(source, false, &pipeline)?;
+    // the given validation dataset, or one fold of the learning one
+    let validation_data = match matches.value_of("validation") {
+        Some(validation) => Some(open_dataset(validation, true, &pipeline)?),
+        None => None,
+    };
+    let (learning, validation) = match validation_data {
+        Some(ref validation) => (Subset::new(&*data, (0..data.len()).collect()),
+                                 Subset::new(&**validation, (0..validation.len()).collect())),
+        None => {
+            let (learning, validation) = holdout(&*data)?;
+            (Subset::new(&*data, learning), Subset::new(&*data, validation))
+        }
+    };
+    if validation.is_empty() {
+        return Err(Error::EmptyDataset { source: "validation set".into() });
+    }
+    if validation.input_len() != learning.input_len() {
+        return Err(Error::shape("validation set", learning.input_len(), validation.input_len()));
+    }
+
+    let net = match matches.value_of("model").unwrap() {
+        "autoencoder" => {
+            // the settings autoencoders share with classifiers
+            let learn = train_config(matches)?;
+            let config = AutoencoderConfig {
+                learning_rate: learn.learning_rate,
+                sample_ratio: learn.sample_ratio,
+                epochs: learn.max_epochs,
+                seed: learn.seed,
+                ..AutoencoderConfig::default()
+            };
+            let objective = AutoencoderSearch {
+                config: config,
+                train: &learning,
+                validation: &validation,
+            };
+            let mut net = NetFile::autoencoder(run(matches, &objective)?);
+            net.preprocessing = pipeline.clone();
+            net
+        }
+        _ => {
+            let objective = ClassifierSearch {
+                config: train_config(matches)?,
+                train: &learning,
+                validation: &validation,
+            };
+            run(matches, &objective)?
+        }
+    };
+
+    if let Some(path) = matches.value_of("out-net") {
+        net.save(path)?;
+        println!("Saved the best net to {}", path);
+    }
+    Ok(())
+}
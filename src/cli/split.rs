@@ -0,0 +1,94 @@
+//! Dividing datasets into train, validation and test portions.
+
+use clap;
+use std::fs;
+use std::path::Path;
+use mulperc::{img, split};
+use mulperc::dataset::Dataset;
+use mulperc::preprocess::Pipeline;
+use mulperc::error::{Error, Result};
+use cli::classifier::{open_dataset, parse_arg};
+use cli::crossval::author_groups;
+
+/// The group of every example of `data` given by `--group`: its author or a field of its file name, counted from 0.
+/// Examples without one, or all of them without `--group`, are groups of their own.
+fn groups(data: &Dataset, group: Option<&str>) -> Result<Vec<String>> {
+    let field = match group {
+        None => return Ok((0..data.len()).map(|i| format!("#{}", i)).collect()),
+        Some("author") => return Ok(author_groups(data)),
+        Some(field) => field.parse::<usize>()
+            .map_err(|_| Error::argument("group", format!("{:?} is neither author nor a field number", field)))?,
+    };
+    Ok((0..data.len())
+        .map(|i| data.source(i).and_then(|p| img::field_of(p, field)).unwrap_or_else(|| format!("#{}", i)))
+        .collect())
+}
+
+/// Copies the examples of `data` at `indices` to subdirectories of `dir` named after their labels, rendering the
+/// ones without a file of their own as PNG images named `LABEL_INDEX.png`.
+fn write_dir(dir: &Path, data: &Dataset, indices: &[usize]) -> Result<()> {
+    let shape = data.shape();
+    for &i in indices {
+        let label = data.label(i);
+        if label.is_empty() || label == "." || label == ".." || label.contains('/') || label.contains('\\') {
+            return Err(Error::argument("dataset", format!("label {:?} can't name a directory", label)));
+        }
+        let label_dir = dir.join(label);
+        fs::create_dir_all(&label_dir).map_err(|e| Error::io(&label_dir, e))?;
+
+        match data.source(i) {
+            Some(source) => {
+                let name = source.file_name().map(|n| n.to_os_string()).unwrap_or_else(|| format!("{}", i).into());
+                let out = label_dir.join(name);
+                if out.exists() {
+                    return Err(Error::argument("output", format!("{} already exists", out.display())));
+                }
+                fs::copy(&source, &out).map_err(|e| Error::io(&source, e))?;
+            }
+            None if shape.len() == 2 => {
+                let out = label_dir.join(format!("{}_{:05}.png", label, i));
+                img::save(&data.input(i)?, shape[1] as u32, shape[0] as u32, &out)?;
+            }
+            None => {
+                return Err(Error::argument("format", format!("example {} is neither a file nor an image, write an \
+                                                              index instead", i)));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn split(matches: &clap::ArgMatches<'static>) -> Result<()> {
+    let source = matches.value_of("dataset").unwrap();
+    let out = Path::new(matches.value_of("out").unwrap());
+    let ratios = matches.value_of("ratios").unwrap().split(',')
+        .map(|r| r.trim().parse::<f64>().map_err(|_| Error::argument("ratios", format!("{:?} is not a number", r))))
+        .collect::<Result<Vec<f64>>>()?;
+    if ratios.len() != split::PORTIONS.len() {
+        return Err(Error::shape("ratios of the train, validation and test portions", split::PORTIONS.len(),
+                                ratios.len()));
+    }
+    let seed = match matches.value_of("seed") {
+        Some(_) => Some(parse_arg(matches, "seed")?),
+        None => None,
+    };
+
+    let data = open_dataset(source, false, &Pipeline::default())?;
+    if data.is_empty() {
+        return Err(Error::EmptyDataset { source: source.into() });
+    }
+    let groups = groups(&*data, matches.value_of("group"))?;
+    let labels: Vec<&str> = (0..data.len()).map(|i| data.label(i)).collect();
+    let portions = split::stratified_group_split(&labels, &groups, &ratios, seed)?;
+
+    fs::create_dir_all(out).map_err(|e| Error::io(out, e))?;
+    for (p, name) in split::PORTIONS.iter().enumerate().filter(|&(p, _)| ratios[p] > 0.0) {
+        let indices: Vec<usize> = (0..data.len()).filter(|&i| portions[i] == p).collect();
+        match matches.value_of("format").unwrap() {
+            "dirs" => write_dir(&out.join(name), &*data, &indices)?,
+            _ => split::write_index(out.join(format!("{}.csv", name)), &*data, &indices)?,
+        }
+        println!("{}: {} examples", name, indices.len());
+    }
+    Ok(())
+}
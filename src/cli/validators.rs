@@ -0,0 +1,51 @@
+pub fn path_exists(s: String) -> Result<(), String> {
+    use std::path::*;
+    if s == "mnist" { return Ok(())}
+    let meta = Path::new(&s).metadata().map_err(|e| e.to_string())?;
+    if meta.is_dir() {
+        Ok(())
+    } else {
+        Err(format!("{} is not a directory", s))
+    }
+}
+
+/// Accepts the datasets named like `dataset::Source` describes, whose file or directory exists.
+pub fn dataset_exists(s: String) -> Result<(), String> {
+    use std::path::*;
+    use mulperc::dataset::Source;
+    let source: Source = s.parse().map_err(|e: ::mulperc::Error| e.to_string())?;
+    if source.kind == "mnist" { return Ok(()) }
+    Path::new(&source.path).metadata().map(|_| ()).map_err(|e| format!("{}: {}", source.path, e))
+}
+
+/// Accepts the preprocessing pipelines written like `preprocess::Pipeline` parses them.
+pub fn preprocessing_is_valid(s: String) -> Result<(), String> {
+    use mulperc::preprocess::Pipeline;
+    s.parse::<Pipeline>().map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Accepts the augmentations written like `augment::Augmentation` parses them.
+pub fn augmentation_is_valid(s: String) -> Result<(), String> {
+    use mulperc::augment::Augmentation;
+    s.parse::<Augmentation>().map(|_| ()).map_err(|e| e.to_string())
+}
+
+pub fn file_exists(s: String) -> Result<(), String> {
+    use std::path::*;
+    let meta = Path::new(&s).metadata().map_err(|e| e.to_string())?;
+    if meta.is_file() {
+        Ok(())
+    } else {
+        Err(format!("{} is not a file", s))
+    }
+}
+
+pub fn str_is_float(s: String) -> Result<(), String> {
+    use std::str::FromStr;
+    f64::from_str(&s).map(|_| ()).map_err(|_| format!("{} is not a float", s))
+}
+
+pub fn str_is_integer(s: String) -> Result<(), String> {
+    use std::str::FromStr;
+    i64::from_str(&s).map(|_| ()).map_err(|_| format!("{} is not an integer", s))
+}
\ No newline at end of file
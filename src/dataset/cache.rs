@@ -0,0 +1,267 @@
+//! Binary caches of datasets: the preprocessed inputs of all the examples with their labels and targets, and a
+//! fingerprint of the files they were read from, so that reopening an unchanged dataset decodes nothing.
+//!
+//! A cache file is the magic `MLPD`, a version byte, the fingerprint and the offset of the header as big endian 64 bit
+//! integers, the inputs as big endian 32 bit floats, one after the other, and the header with everything else.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use bincode;
+use rayon::prelude::*;
+use idx::DataType;
+use super::{Dataset, SharedFile};
+use error::{Error, Result};
+
+const CACHE_MAGIC: &'static [u8; 4] = b"MLPD";
+const CACHE_VERSION: u8 = 1;
+/// Offset of the inputs: after the magic, the version, the fingerprint and the offset of the header.
+const INPUTS_OFFSET: u64 = 4 + 1 + 8 + 8;
+/// Number of examples decoded in parallel while writing a cache.
+const WRITE_CHUNK: usize = 1024;
+
+/// Everything cached about the examples but their inputs.
+#[derive(Serialize, Deserialize)]
+struct CacheHeader {
+    shape: Vec<usize>,
+    labels: Vec<String>,
+    sources: Vec<Option<PathBuf>>,
+    target_names: Vec<String>,
+    /// The targets of every example if there are target names, none otherwise.
+    targets: Vec<Vec<f64>>,
+}
+
+/// A hash of `description` and of the path, size and modification time of every one of `files`, which changes when
+/// any of them does.
+pub fn fingerprint<P: AsRef<Path>>(description: &str, files: &[P]) -> Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    CACHE_VERSION.hash(&mut hasher);
+    description.hash(&mut hasher);
+    for path in files {
+        let path = path.as_ref();
+        let meta = path.metadata().map_err(|e| Error::io(path, e))?;
+        path.hash(&mut hasher);
+        meta.len().hash(&mut hasher);
+        meta.modified().ok().hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+fn encode_u64(x: u64) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = (x >> (8 * (7 - i))) as u8;
+    }
+    bytes
+}
+
+fn decode_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| acc << 8 | b as u64)
+}
+
+/// The mapped cache file, which is only ever read.
+#[cfg(feature = "mmap")]
+struct Mapping(::memmap::Mmap);
+
+#[cfg(feature = "mmap")]
+unsafe impl Sync for Mapping {}
+
+#[cfg(feature = "mmap")]
+unsafe impl Send for Mapping {}
+
+/// Where the inputs of a cache are read from.
+enum CacheStorage {
+    File(SharedFile),
+    #[cfg(feature = "mmap")]
+    Mapped(Mapping),
+}
+
+/// A dataset read from its cache file, an input at a time.
+pub struct Cached {
+    header: CacheHeader,
+    storage: CacheStorage,
+    input_len: usize,
+}
+
+impl Cached {
+    /// Writes all the examples of `data` to the cache file `path` with `fingerprint`. The examples whose input or
+    /// targets can't be read are left out, and their errors returned.
+    ///
+    /// The cache is written next to `path` first, so that an interrupted write leaves no cache behind.
+    pub fn write<P: AsRef<Path>>(path: P, data: &Dataset, fingerprint: u64) -> Result<Vec<Error>> {
+        let path = path.as_ref();
+        let partial = path.with_extension("partial");
+        let input_len = data.input_len();
+        let mut header = CacheHeader {
+            shape: data.shape(),
+            labels: Vec::with_capacity(data.len()),
+            sources: Vec::with_capacity(data.len()),
+            target_names: data.target_names(),
+            targets: Vec::new(),
+        };
+        let mut skipped = Vec::new();
+
+        let file = File::create(&partial).map_err(|e| Error::io(&partial, e))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(CACHE_MAGIC)
+            .and_then(|_| out.write_all(&[CACHE_VERSION]))
+            .and_then(|_| out.write_all(&encode_u64(fingerprint)))
+            .and_then(|_| out.write_all(&encode_u64(0)))
+            .map_err(|e| Error::io(&partial, e))?;
+
+        let indices: Vec<usize> = (0..data.len()).collect();
+        let has_targets = !header.target_names.is_empty();
+        let mut buf = Vec::with_capacity(input_len * DataType::F32.size());
+        for chunk in indices.chunks(WRITE_CHUNK) {
+            let mut loaded = Vec::with_capacity(chunk.len());
+            chunk.par_iter()
+                .map(|&i| -> Result<(Vec<f64>, Vec<f64>)> {
+                    let input = data.input(i)?;
+                    let target = if has_targets { data.target(i)? } else { Vec::new() };
+                    Ok((input, target))
+                })
+                .collect_into(&mut loaded);
+
+            for (&i, example) in chunk.iter().zip(loaded) {
+                let (input, target): (Vec<f64>, Vec<f64>) = match example {
+                    Ok(example) => example,
+                    Err(e) => { skipped.push(e); continue }
+                };
+                if input.len() != input_len {
+                    skipped.push(Error::shape(format!("example {} of the cached dataset", i), input_len, input.len()));
+                    continue;
+                }
+                buf.clear();
+                for &x in &input {
+                    DataType::F32.encode(x, &mut buf);
+                }
+                out.write_all(&buf).map_err(|e| Error::io(&partial, e))?;
+                header.labels.push(data.label(i).to_string());
+                header.sources.push(data.source(i));
+                if has_targets {
+                    header.targets.push(target);
+                }
+            }
+        }
+
+        let header_offset = INPUTS_OFFSET + (header.labels.len() * input_len * DataType::F32.size()) as u64;
+        bincode::serde::serialize_into(&mut out, &header, bincode::SizeLimit::Infinite)
+            .map_err(|e| Error::format(&partial, e.to_string()))?;
+        let mut file = out.into_inner().map_err(|e| Error::io(&partial, e.into()))?;
+        file.seek(SeekFrom::Start(INPUTS_OFFSET - 8))
+            .and_then(|_| file.write_all(&encode_u64(header_offset)))
+            .map_err(|e| Error::io(&partial, e))?;
+        fs::rename(&partial, path).map_err(|e| Error::io(path, e))?;
+        Ok(skipped)
+    }
+
+    /// Opens the cache file `path` if it was written with `fingerprint`, mapping it into memory if `mmap` is set.
+    /// `None` if there is no such file, or if it is stale or incomplete.
+    pub fn open<P: AsRef<Path>>(path: P, fingerprint: u64, mmap: bool) -> Result<Option<Cached>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = SharedFile::open(path)?;
+        let file_len = path.metadata().map_err(|e| Error::io(path, e))?.len();
+        if file_len < INPUTS_OFFSET {
+            return Ok(None);
+        }
+        let start = file.read_at(0, INPUTS_OFFSET as usize)?;
+        if !start.starts_with(CACHE_MAGIC) || start[4] != CACHE_VERSION || decode_u64(&start[5..13]) != fingerprint {
+            return Ok(None);
+        }
+        let header_offset = decode_u64(&start[13..21]);
+        if header_offset < INPUTS_OFFSET || header_offset > file_len {
+            return Ok(None);
+        }
+
+        let bytes = file.read_at(header_offset, (file_len - header_offset) as usize)?;
+        let header: CacheHeader = match bincode::serde::deserialize(&bytes) {
+            Ok(header) => header,
+            Err(_) => return Ok(None),
+        };
+        let input_len: usize = header.shape.iter().product();
+        if INPUTS_OFFSET + (header.labels.len() * input_len * DataType::F32.size()) as u64 != header_offset {
+            return Ok(None);
+        }
+
+        let storage = if mmap { Cached::map(path)? } else { CacheStorage::File(file) };
+        Ok(Some(Cached { header: header, storage: storage, input_len: input_len }))
+    }
+
+    #[cfg(feature = "mmap")]
+    fn map(path: &Path) -> Result<CacheStorage> {
+        use memmap::{Mmap, Protection};
+        let mapped = Mmap::open_path(path, Protection::Read).map_err(|e| Error::io(path, e))?;
+        Ok(CacheStorage::Mapped(Mapping(mapped)))
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn map(_path: &Path) -> Result<CacheStorage> {
+        Err(Error::Unsupported { what: "memory-mapped dataset caches (enable the `mmap` feature)".into() })
+    }
+}
+
+impl Dataset for Cached {
+    fn len(&self) -> usize {
+        self.header.labels.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.header.shape.clone()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self.header.labels[i]
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        let size = DataType::F32.size();
+        let offset = INPUTS_OFFSET as usize + i * self.input_len * size;
+        let read;
+        let bytes = match self.storage {
+            CacheStorage::File(ref file) => {
+                read = file.read_at(offset as u64, self.input_len * size)?;
+                &read[..]
+            }
+            #[cfg(feature = "mmap")]
+            CacheStorage::Mapped(ref mapping) => {
+                unsafe { &mapping.0.as_slice()[offset..offset + self.input_len * size] }
+            }
+        };
+        Ok(bytes.chunks(size).map(|x| DataType::F32.decode(x)).collect())
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        self.header.sources[i].clone()
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.header.target_names.clone()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        match self.header.targets.get(i) {
+            Some(target) => Ok(target.clone()),
+            None => Err(Error::argument("dataset", "the examples have no targets")),
+        }
+    }
+}
+
+#[test]
+fn test_cache() {
+    use std::env;
+    let examples: Vec<(Vec<f64>, String)> = vec![(vec![0.25, -1.0], "a".into()), (vec![0.5, 3.0], "b".into())];
+    let path = env::temp_dir().join("mulperc-test-cache.bin");
+    assert!(Cached::write(&path, &examples, 7).unwrap().is_empty());
+
+    assert!(Cached::open(&path, 8, false).unwrap().is_none());
+    let cached = Cached::open(&path, 7, false).unwrap().unwrap();
+    assert_eq!((cached.len(), cached.shape()), (2, vec![2]));
+    assert_eq!(cached.get(1).unwrap(), examples[1]);
+    assert!(cached.target(0).is_err());
+    let _ = fs::remove_file(&path);
+}
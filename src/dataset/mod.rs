@@ -0,0 +1,1173 @@
+//! Labelled datasets.
+//!
+//! A [`Dataset`](trait.Dataset.html) knows the labels of its examples up front, but only has to produce their inputs
+//! when asked to, so that the image directories, IDX, CSV and LibSVM files it is read from can be larger than the
+//! memory.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use regex::Regex;
+use rayon::prelude::*;
+use img;
+use gzip;
+use idx::IdxHeader;
+use mnist::MnistDigits;
+use preprocess::Pipeline;
+use split;
+use error::{Error, Result};
+
+mod tabular;
+mod cache;
+
+pub use self::tabular::{Column, CsvDataset, CsvOptions, LibSvmDataset};
+pub use self::cache::{fingerprint, Cached};
+
+/// Examples as `(input, label)` pairs.
+pub type Examples = Vec<(Vec<f64>, String)>;
+
+/// Labelled examples, read by index.
+pub trait Dataset: Sync {
+    fn len(&self) -> usize;
+
+    /// Dimensions of every input, `[height, width]` for images.
+    fn shape(&self) -> Vec<usize>;
+
+    fn label(&self, i: usize) -> &str;
+
+    /// Input of the example `i`, of `input_len()` values.
+    fn input(&self, i: usize) -> Result<Vec<f64>>;
+
+    /// File the example `i` was loaded from, if it has one of its own.
+    fn source(&self, _i: usize) -> Option<PathBuf> {
+        None
+    }
+
+    /// Names of the real values regression nets learn to output, empty if the examples only have labels.
+    fn target_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Targets of the example `i`, one for every name of `target_names()`.
+    fn target(&self, _i: usize) -> Result<Vec<f64>> {
+        Err(Error::argument("dataset", "the examples have no targets"))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of values of every input.
+    fn input_len(&self) -> usize {
+        self.shape().iter().fold(1, |acc, &d| acc * d)
+    }
+
+    /// The distinct labels of the examples.
+    fn labels(&self) -> BTreeSet<String> {
+        (0..self.len()).map(|i| self.label(i).to_string()).collect()
+    }
+
+    /// The labels of the example `i` for multi-label classification, just `label(i)` unless the dataset says how to
+    /// split it.
+    fn label_set(&self, i: usize) -> Vec<String> {
+        vec![self.label(i).to_string()]
+    }
+
+    /// The distinct labels of the label sets of the examples.
+    fn set_labels(&self) -> BTreeSet<String> {
+        (0..self.len()).flat_map(|i| self.label_set(i)).collect()
+    }
+
+    /// The example `i` as an `(input, label)` pair.
+    fn get(&self, i: usize) -> Result<(Vec<f64>, String)> {
+        Ok((self.input(i)?, self.label(i).to_string()))
+    }
+}
+
+/// Width and height of the inputs of `data` rendered as images, grayscale or RGB: a single row if they are neither.
+pub fn image_dims<D: Dataset + ?Sized>(data: &D) -> (u32, u32) {
+    let shape = data.shape();
+    if shape.len() == 2 || (shape.len() == 3 && shape[2] == 3) {
+        (shape[1] as u32, shape[0] as u32)
+    } else {
+        (data.input_len() as u32, 1)
+    }
+}
+
+/// Reads the examples at `indices` of `data` in parallel.
+pub fn load_batch<D: Dataset + ?Sized>(data: &D, indices: &[usize]) -> Result<Examples> {
+    let mut examples = Vec::with_capacity(indices.len());
+    indices.par_iter().map(|&i| data.get(i)).collect_into(&mut examples);
+    examples.into_iter().collect()
+}
+
+/// Reads the targets of the examples at `indices` of `data` in parallel.
+pub fn load_targets<D: Dataset + ?Sized>(data: &D, indices: &[usize]) -> Result<Vec<Vec<f64>>> {
+    let mut targets = Vec::with_capacity(indices.len());
+    indices.par_iter().map(|&i| data.target(i)).collect_into(&mut targets);
+    targets.into_iter().collect()
+}
+
+/// The examples of a dataset in order, read one by one.
+pub struct Stream<'a, D: Dataset + ?Sized + 'a> {
+    data: &'a D,
+    next: usize,
+}
+
+impl<'a, D: Dataset + ?Sized + 'a> Iterator for Stream<'a, D> {
+    type Item = Result<(Vec<f64>, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.data.len() {
+            return None;
+        }
+        self.next += 1;
+        Some(self.data.get(self.next - 1))
+    }
+}
+
+pub fn stream<D: Dataset + ?Sized>(data: &D) -> Stream<D> {
+    Stream { data: data, next: 0 }
+}
+
+/// The input examples themselves are a dataset of flat inputs.
+impl Dataset for [(Vec<f64>, String)] {
+    fn len(&self) -> usize {
+        <[(Vec<f64>, String)]>::len(self)
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        vec![self.first().map(|&(ref input, _)| input.len()).unwrap_or(0)]
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self[i].1
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        Ok(self[i].0.clone())
+    }
+}
+
+impl Dataset for Vec<(Vec<f64>, String)> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self[..].shape()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self[i].1
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        Ok(self[i].0.clone())
+    }
+}
+
+/// Examples held in memory, with the shape of their inputs, where they were loaded from and their targets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InMemory {
+    pub examples: Examples,
+    pub shape: Vec<usize>,
+    pub sources: Vec<Option<PathBuf>>,
+    pub target_names: Vec<String>,
+    /// Targets of every example, empty if there are no target names.
+    pub targets: Vec<Vec<f64>>,
+    pub label_sets: Vec<Vec<String>>,
+}
+
+impl InMemory {
+    /// Reads all the examples of `data` into memory.
+    pub fn load<D: Dataset + ?Sized>(data: &D) -> Result<InMemory> {
+        let indices: Vec<usize> = (0..data.len()).collect();
+        let target_names = data.target_names();
+        Ok(InMemory {
+            examples: load_batch(data, &indices)?,
+            shape: data.shape(),
+            sources: indices.iter().map(|&i| data.source(i)).collect(),
+            targets: if target_names.is_empty() { Vec::new() } else { load_targets(data, &indices)? },
+            target_names: target_names,
+            label_sets: indices.iter().map(|&i| data.label_set(i)).collect(),
+        })
+    }
+}
+
+impl Dataset for InMemory {
+    fn len(&self) -> usize {
+        self.examples.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.shape.clone()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self.examples[i].1
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        Ok(self.examples[i].0.clone())
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        self.sources[i].clone()
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.target_names.clone()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        match self.targets.get(i) {
+            Some(target) => Ok(target.clone()),
+            None => Err(Error::argument("dataset", "the examples have no targets")),
+        }
+    }
+
+    fn label_set(&self, i: usize) -> Vec<String> {
+        self.label_sets[i].clone()
+    }
+}
+
+/// Some of the examples of another dataset, like a fold of it.
+pub struct Subset<'a, D: Dataset + ?Sized + 'a> {
+    data: &'a D,
+    indices: Vec<usize>,
+}
+
+impl<'a, D: Dataset + ?Sized + 'a> Subset<'a, D> {
+    /// The examples of `data` at `indices`, in that order.
+    pub fn new(data: &'a D, indices: Vec<usize>) -> Subset<'a, D> {
+        Subset { data: data, indices: indices }
+    }
+}
+
+impl<'a, D: Dataset + ?Sized + 'a> Dataset for Subset<'a, D> {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.data.shape()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        self.data.label(self.indices[i])
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.input(self.indices[i])
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        self.data.source(self.indices[i])
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.data.target_names()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.target(self.indices[i])
+    }
+
+    fn label_set(&self, i: usize) -> Vec<String> {
+        self.data.label_set(self.indices[i])
+    }
+}
+
+/// Some of the examples of another dataset, listed in an index file written by `split::write_index`.
+pub struct Indexed {
+    data: Box<Dataset>,
+    indices: Vec<usize>,
+}
+
+impl Indexed {
+    /// The examples of `data` listed in the index file at `path`, which fails if they don't have the labels it
+    /// lists, like when the dataset changed since the index was written.
+    pub fn open<P: AsRef<Path>>(data: Box<Dataset>, path: P) -> Result<Indexed> {
+        let path = path.as_ref();
+        let mut indices = Vec::new();
+        for (line, (i, label)) in split::read_index(path)?.into_iter().enumerate() {
+            if i >= data.len() {
+                return Err(Error::record(path, line, format!("the dataset has no example {}", i)));
+            }
+            if data.label(i) != label {
+                return Err(Error::record(path, line, format!("example {} is labelled {:?}, not {:?}",
+                                                             i, data.label(i), label)));
+            }
+            indices.push(i);
+        }
+        Ok(Indexed { data: data, indices: indices })
+    }
+}
+
+impl Dataset for Indexed {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.data.shape()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        self.data.label(self.indices[i])
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.input(self.indices[i])
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        self.data.source(self.indices[i])
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.data.target_names()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.target(self.indices[i])
+    }
+
+    fn label_set(&self, i: usize) -> Vec<String> {
+        self.data.label_set(self.indices[i])
+    }
+}
+
+/// A file read piece by piece, from any thread.
+struct SharedFile {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl SharedFile {
+    fn open(path: &Path) -> Result<SharedFile> {
+        let file = File::open(path).map_err(|e| Error::io(path, e))?;
+        Ok(SharedFile { path: path.to_path_buf(), file: Mutex::new(file) })
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(offset)).and_then(|_| file.read_exact(&mut buf))
+            .map_err(|e| Error::io(&self.path, e))?;
+        Ok(buf)
+    }
+}
+
+/// How the examples of an image directory get their labels.
+#[derive(Debug, Clone)]
+pub enum Labelling {
+    /// The part of the file name before the first `_`.
+    Prefix,
+    /// The top level subdirectory the file is in, like `DIR/LABEL/image.png`.
+    Folders,
+    /// The match of the regex in the path of the file relative to the directory, or its `label` or first group if it
+    /// has one. Files it doesn't match are skipped.
+    Regex(Regex),
+    /// The file listing the images of the dataset as `path,label` lines, the paths being relative to the directory.
+    Manifest(PathBuf),
+}
+
+/// Which files of an image directory are examples, and how they are labelled.
+#[derive(Debug, Clone)]
+pub struct ImageDirOptions {
+    /// `Folders` if all the files are in subdirectories, `Prefix` otherwise, if not set.
+    pub labelling: Option<Labelling>,
+    /// Extensions of the image files, in lower case. Other files are ignored, unless listed in a manifest.
+    pub extensions: Vec<String>,
+    /// Whether to include the files and directories whose name starts with a dot.
+    pub hidden: bool,
+    /// How the images are made into inputs.
+    pub pipeline: Pipeline,
+}
+
+/// Extensions of the image formats that can be decoded.
+pub const IMAGE_EXTENSIONS: &'static [&'static str] = &["png", "jpg", "jpeg", "gif", "bmp", "ico", "tif", "tiff", "webp",
+                                                      "ppm"];
+
+impl Default for ImageDirOptions {
+    fn default() -> Self {
+        ImageDirOptions {
+            labelling: None,
+            extensions: IMAGE_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            hidden: false,
+            pipeline: Pipeline::default(),
+        }
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with('.')).unwrap_or(false)
+}
+
+/// Adds the files under `dir` that `options` accept to `files`, recursively.
+fn list_files(dir: &Path, options: &ImageDirOptions, files: &mut Vec<PathBuf>, skipped: &mut Vec<Error>) -> Result<()> {
+    for entry in dir.read_dir().map_err(|e| Error::io(dir, e))? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => { skipped.push(Error::io(dir, e)); continue }
+        };
+        if !options.hidden && is_hidden(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            list_files(&path, options, files, skipped)?;
+            continue;
+        }
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+        if extension.map(|e| options.extensions.contains(&e)).unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The `path,label` lines of the manifest at `path`, with an optional `path,label` header.
+fn read_manifest(path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let file = File::open(path).map_err(|e| Error::io(path, e))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| Error::io(path, e))?;
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line == "path,label") {
+            continue;
+        }
+        let comma = line.rfind(',').ok_or_else(|| Error::record(path, i, "expected a path and a label"))?;
+        entries.push((PathBuf::from(line[..comma].trim()), line[comma + 1..].trim().to_string()));
+    }
+    Ok(entries)
+}
+
+/// A directory of labelled images, decoded whenever they are read.
+pub struct ImageDir {
+    paths: Vec<PathBuf>,
+    labels: Vec<String>,
+    width: u32,
+    height: u32,
+    pipeline: Pipeline,
+}
+
+impl ImageDir {
+    /// Lists the images in `dir` named `LABEL(_.*)?`, or in subdirectories named after their labels.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<(ImageDir, Vec<Error>)> {
+        ImageDir::open_with(dir, &ImageDirOptions::default())
+    }
+
+    /// Lists the images in `dir` as `options` say, taking the size of the inputs from the first one that can be
+    /// decoded and the preprocessing.
+    ///
+    /// Along with the dataset, returns the errors of the files that were skipped: the ones without a label, the
+    /// undecodable ones and the ones whose inputs would have another size than the first image's.
+    pub fn open_with<P: AsRef<Path>>(dir: P, options: &ImageDirOptions) -> Result<(ImageDir, Vec<Error>)> {
+        let dir = dir.as_ref();
+        let mut skipped = Vec::new();
+        let mut labelled: Vec<(PathBuf, Result<String>)> = Vec::new();
+
+        if let Some(Labelling::Manifest(ref manifest)) = options.labelling {
+            for (path, label) in read_manifest(manifest)? {
+                labelled.push((dir.join(path), Ok(label)));
+            }
+        } else {
+            let mut paths = Vec::new();
+            list_files(dir, options, &mut paths, &mut skipped)?;
+            paths.sort();
+
+            let relative = |path: &Path| path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+            let labelling = match options.labelling {
+                Some(ref labelling) => labelling.clone(),
+                None if paths.iter().all(|p| relative(p).components().count() > 1) => Labelling::Folders,
+                None => Labelling::Prefix,
+            };
+            for path in paths {
+                let label = match labelling {
+                    Labelling::Prefix => img::label_of(&path),
+                    Labelling::Folders => relative(&path).components().next()
+                        .and_then(|c| c.as_os_str().to_str()).map(|c| c.to_string())
+                        .ok_or_else(|| Error::format(&path, "not in a subdirectory")),
+                    Labelling::Regex(ref regex) => {
+                        let name = relative(&path).to_string_lossy().replace('\\', "/");
+                        regex.captures(&name)
+                            .and_then(|c| c.name("label").or_else(|| c.get(1)).or_else(|| c.get(0)))
+                            .map(|m| m.as_str().to_string())
+                            .ok_or_else(|| Error::format(&path, format!("label regex {:?} doesn't match", regex.as_str())))
+                    }
+                    Labelling::Manifest(_) => unreachable!(),
+                };
+                labelled.push((path, label));
+            }
+        }
+
+        let mut files = Vec::with_capacity(labelled.len());
+        for (path, label) in labelled {
+            match label {
+                Ok(label) => files.push((path, label)),
+                Err(e) => skipped.push(e),
+            }
+        }
+        // every image is decoded once up front, so that a bad one is skipped here instead of failing a later read
+        let mut dims = Vec::with_capacity(files.len());
+        files.par_iter().map(|&(ref path, _)| img::dimensions(path)).collect_into(&mut dims);
+
+        let mut listed = ImageDir {
+            paths: Vec::new(),
+            labels: Vec::new(),
+            width: 0,
+            height: 0,
+            pipeline: options.pipeline.clone(),
+        };
+        for ((path, label), dims) in files.into_iter().zip(dims) {
+            let (width, height) = match dims {
+                Ok(dims) => dims,
+                Err(e) => { skipped.push(e); continue }
+            };
+            if listed.paths.is_empty() {
+                listed.width = width;
+                listed.height = height;
+            } else if options.pipeline.shape(width, height) != listed.shape() {
+                let len = options.pipeline.shape(width, height).iter().fold(1, |acc, &d| acc * d);
+                skipped.push(Error::shape(path.display().to_string(), listed.input_len(), len));
+                continue;
+            }
+            listed.paths.push(path);
+            listed.labels.push(label);
+        }
+
+        if listed.is_empty() {
+            return Err(Error::EmptyDataset { source: dir.display().to_string() });
+        }
+        Ok((listed, skipped))
+    }
+}
+
+impl Dataset for ImageDir {
+    fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.pipeline.shape(self.width, self.height)
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self.labels[i]
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        let pixels = self.pipeline.load(&self.paths[i])?;
+        if pixels.len() != self.input_len() {
+            return Err(Error::shape(self.paths[i].display().to_string(), self.input_len(), pixels.len()));
+        }
+        Ok(pixels)
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        Some(self.paths[i].clone())
+    }
+}
+
+/// Where the values of an IDX file are read from.
+enum IdxStorage {
+    /// The whole file, for gzipped files.
+    Memory(Vec<u8>),
+    File(SharedFile),
+}
+
+/// Inputs in an IDX file with their labels in another one, like the MNIST digits.
+///
+/// Raw files are read item by item, gzipped ones are decompressed into memory, but only decoded item by item.
+pub struct IdxDataset {
+    header: IdxHeader,
+    storage: IdxStorage,
+    labels: Vec<String>,
+}
+
+impl IdxDataset {
+    pub fn open<P: AsRef<Path>, Q: AsRef<Path>>(inputs: P, labels: Q) -> Result<IdxDataset> {
+        let (inputs, labels_path) = (inputs.as_ref(), labels.as_ref());
+        let file = SharedFile::open(inputs)?;
+        let start = file.read_at(0, 4).map_err(|_| Error::format(inputs, "unexpected end of header"))?;
+
+        let (header, storage) = if start.starts_with(&gzip::MAGIC) {
+            let mut bytes = Vec::new();
+            File::open(inputs).and_then(|mut f| f.read_to_end(&mut bytes)).map_err(|e| Error::io(inputs, e))?;
+            let bytes = gzip::decompress(&bytes, inputs)?;
+            (IdxHeader::parse(&bytes, inputs)?, IdxStorage::Memory(bytes))
+        } else {
+            let header_len = 4 + 4 * start[3] as usize;
+            let header = file.read_at(0, header_len).map_err(|_| Error::format(inputs, "unexpected end of header"))?;
+            (IdxHeader::parse(&header, inputs)?, IdxStorage::File(file))
+        };
+
+        if header.dims.len() < 2 {
+            return Err(Error::format(inputs, format!("expected a list of inputs, got dimensions {:?}", header.dims)));
+        }
+        let expected = (header.size() + header.len() * header.data_type.size()) as u64;
+        let actual = match storage {
+            IdxStorage::Memory(ref bytes) => bytes.len() as u64,
+            IdxStorage::File(_) => inputs.metadata().map_err(|e| Error::io(inputs, e))?.len(),
+        };
+        if actual != expected {
+            return Err(Error::format(inputs, format!("header declares {:?} values ({} bytes), file contains {} bytes",
+                                                     header.dims, expected, actual)));
+        }
+
+        let labels = MnistDigits::read_labels(labels_path)?;
+        if labels.len() != header.dims[0] {
+            return Err(Error::format(labels_path, format!("{} labels for {} inputs in {}",
+                                                          labels.len(), header.dims[0], inputs.display())));
+        }
+
+        Ok(IdxDataset { header: header, storage: storage, labels: labels })
+    }
+}
+
+impl Dataset for IdxDataset {
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.header.dims[1..].to_vec()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self.labels[i]
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        let data_type = self.header.data_type;
+        let item_size = self.header.item_len() * data_type.size();
+        let offset = self.header.size() + i * item_size;
+        let read;
+        let bytes = match self.storage {
+            IdxStorage::Memory(ref bytes) => &bytes[offset..offset + item_size],
+            IdxStorage::File(ref file) => {
+                read = file.read_at(offset as u64, item_size)?;
+                &read[..]
+            }
+        };
+        let scale = data_type.scale();
+        Ok(bytes.chunks(data_type.size()).map(|x| data_type.decode(x) / scale).collect())
+    }
+}
+
+/// Reads the numbers, separated by whitespace or commas, in the sidecar file `path`.
+fn read_sidecar(path: &Path) -> Result<Vec<f64>> {
+    let mut text = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut text)).map_err(|e| Error::io(path, e))?;
+    text.split(|c: char| c == ',' || c.is_whitespace()).filter(|x| !x.is_empty())
+        .map(|x| x.parse().map_err(|_| Error::format(path, format!("{:?} is not a number", x))))
+        .collect()
+}
+
+/// Another dataset whose examples get their targets from sidecar files: the files of the examples with their
+/// extension replaced, like `house_12.txt` next to `house_12.png`, holding numbers separated by whitespace or commas.
+pub struct Sidecars {
+    data: Box<Dataset>,
+    extension: String,
+    names: Vec<String>,
+}
+
+impl Sidecars {
+    /// Gives the examples of `data` the targets in their sidecar files with `extension`, as many of them as in the
+    /// sidecar of the first example.
+    pub fn new(data: Box<Dataset>, extension: &str) -> Result<Sidecars> {
+        let mut sidecars = Sidecars { data: data, extension: extension.into(), names: Vec::new() };
+        if sidecars.data.is_empty() {
+            return Ok(sidecars);
+        }
+        let len = read_sidecar(&sidecars.path(0)?)?.len();
+        sidecars.names = if len == 1 {
+            vec!["target".to_string()]
+        } else {
+            (0..len).map(|i| format!("target_{}", i)).collect()
+        };
+        Ok(sidecars)
+    }
+
+    /// Path of the sidecar of the example `i`.
+    fn path(&self, i: usize) -> Result<PathBuf> {
+        self.data.source(i).map(|source| source.with_extension(&self.extension))
+            .ok_or_else(|| Error::argument("dataset", format!("example {} has no file to find a sidecar next to", i)))
+    }
+}
+
+impl Dataset for Sidecars {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.data.shape()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        self.data.label(i)
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.input(i)
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        self.data.source(i)
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        let path = self.path(i)?;
+        let target = read_sidecar(&path)?;
+        if target.len() != self.names.len() {
+            return Err(Error::format(&path, format!("expected {} targets, got {}", self.names.len(), target.len())));
+        }
+        Ok(target)
+    }
+
+    fn label_set(&self, i: usize) -> Vec<String> {
+        self.data.label_set(i)
+    }
+}
+
+/// Another dataset whose labels are sets of labels joined by a separator, like `cat+dog_12.png` or `cat;dog` in a
+/// manifest, for multi-label classification.
+pub struct SeparatedLabels {
+    data: Box<Dataset>,
+    separator: String,
+}
+
+impl SeparatedLabels {
+    pub fn new(data: Box<Dataset>, separator: &str) -> SeparatedLabels {
+        SeparatedLabels { data: data, separator: separator.into() }
+    }
+}
+
+impl Dataset for SeparatedLabels {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        self.data.shape()
+    }
+
+    fn label(&self, i: usize) -> &str {
+        self.data.label(i)
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.input(i)
+    }
+
+    fn source(&self, i: usize) -> Option<PathBuf> {
+        self.data.source(i)
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.data.target_names()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        self.data.target(i)
+    }
+
+    fn label_set(&self, i: usize) -> Vec<String> {
+        self.data.label(i).split(self.separator.as_str()).map(|l| l.trim()).filter(|l| !l.is_empty())
+            .map(|l| l.to_string()).collect()
+    }
+}
+
+/// A dataset named on the command line: `KIND:PATH?NAME=VALUE&...`, or just a path.
+///
+/// The kinds are:
+///
+/// * `mnist:train` and `mnist:test`, the MNIST digits in `res/mnist`,
+/// * `idx:IMAGES?labels=LABELS`, inputs and labels in IDX files (see `IdxDataset`); the labels default to the
+///   inputs' path with `images` and `idx3` replaced by `labels` and `idx1`,
+/// * `csv:FILE?label=COLUMN&header=true&delimiter=;&categorical=COLUMN,...&targets=COLUMN,...`, see `CsvDataset`;
+///   the columns are positions starting at 0 or names in the header, and the delimiter can also be `tab`. With
+///   targets, there is no label column unless `label` is given,
+/// * `libsvm:FILE?features=N`, see `LibSvmDataset`,
+/// * `images:DIR?labels=prefix|folders&regex=REGEX&manifest=FILE&ext=png,jpg&hidden=true&sidecar=EXT`, see
+///   `ImageDir`, and `Sidecars` for the targets in sidecar files.
+///
+/// Any of them can also have a `separator=SEP` option splitting their labels into label sets, see `SeparatedLabels`,
+/// an `index=FILE` option keeping only the examples listed in an index file, see `Indexed`, and a `cache=FILE`
+/// option reading the examples from a cache file, written when the files of the dataset change, see `Cached`; with
+/// `mmap=true` the cache is memory-mapped.
+///
+/// A bare path is a `csv` one if it ends with `.csv`, a `libsvm` one if it ends with `.svm` or `.libsvm`, an `images`
+/// one otherwise, and `mnist` is the MNIST training or test set depending on what the dataset is used for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    pub kind: String,
+    pub path: String,
+    pub options: BTreeMap<String, String>,
+}
+
+const SOURCE_KINDS: &'static [&'static str] = &["mnist", "idx", "csv", "libsvm", "images"];
+
+impl FromStr for Source {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Source> {
+        let (spec, query) = match s.find('?') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+
+        let mut options = BTreeMap::new();
+        for option in query.split('&').filter(|o| !o.is_empty()) {
+            let i = option.find('=')
+                .ok_or_else(|| Error::argument("dataset", format!("option {:?} is not NAME=VALUE", option)))?;
+            options.insert(option[..i].to_string(), option[i + 1..].to_string());
+        }
+
+        let (kind, path) = match spec.find(':') {
+            Some(i) if SOURCE_KINDS.contains(&&spec[..i]) => (&spec[..i], &spec[i + 1..]),
+            _ if spec == "mnist" => ("mnist", ""),
+            _ if spec.ends_with(".csv") => ("csv", spec),
+            _ if spec.ends_with(".svm") || spec.ends_with(".libsvm") => ("libsvm", spec),
+            _ => ("images", spec),
+        };
+        Ok(Source { kind: kind.into(), path: path.into(), options: options })
+    }
+}
+
+impl Source {
+    /// Fails if there are options other than `names` and the ones of all the kinds.
+    fn allow_options(&self, names: &[&str]) -> Result<()> {
+        let common = ["separator", "index", "cache", "mmap"];
+        match self.options.keys().find(|name| !names.contains(&name.as_str()) && !common.contains(&name.as_str())) {
+            Some(name) => Err(Error::argument("dataset", format!("{} datasets have no option {:?}", self.kind, name))),
+            None => Ok(()),
+        }
+    }
+
+    /// Parses the option `name` if it is set.
+    fn parse_option<T: FromStr>(&self, name: &str, what: &str) -> Result<Option<T>> {
+        match self.options.get(name) {
+            Some(value) => value.parse().map(Some)
+                .map_err(|_| Error::argument("dataset", format!("{} {:?} is not {}", name, value, what))),
+            None => Ok(None),
+        }
+    }
+
+    fn csv_options(&self) -> Result<CsvOptions> {
+        self.allow_options(&["label", "header", "delimiter", "categorical", "targets"])?;
+        let mut options = CsvOptions::default();
+        if let Some(columns) = self.options.get("targets") {
+            options.targets = columns.split(',').map(|c| Column::from(c.trim())).collect();
+            options.label = None;
+        }
+        if let Some(label) = self.options.get("label") {
+            options.label = Some(Column::from(label.as_str()));
+        }
+        options.header = self.parse_option("header", "true or false")?.unwrap_or(false);
+        if let Some(delimiter) = self.options.get("delimiter") {
+            options.delimiter = match delimiter.as_str() {
+                "tab" | "\\t" => '\t',
+                "space" => ' ',
+                d if d.chars().count() == 1 => d.chars().next().unwrap(),
+                d => return Err(Error::argument("dataset", format!("delimiter {:?} is not a single character", d))),
+            };
+        }
+        if let Some(columns) = self.options.get("categorical") {
+            options.categorical = columns.split(',').map(|c| Column::from(c.trim())).collect();
+        }
+        Ok(options)
+    }
+
+    fn image_dir_options(&self) -> Result<ImageDirOptions> {
+        self.allow_options(&["labels", "regex", "manifest", "ext", "hidden", "sidecar"])?;
+        let mut options = ImageDirOptions::default();
+        options.labelling = match self.options.get("labels").map(|l| l.as_str()) {
+            Some("prefix") => Some(Labelling::Prefix),
+            Some("folders") => Some(Labelling::Folders),
+            Some(other) => return Err(Error::argument("dataset", format!("unknown labelling {:?}", other))),
+            None => None,
+        };
+        if let Some(regex) = self.options.get("regex") {
+            let regex = Regex::new(regex).map_err(|e| Error::argument("label regex", e.to_string()))?;
+            options.labelling = Some(Labelling::Regex(regex));
+        }
+        if let Some(manifest) = self.options.get("manifest") {
+            options.labelling = Some(Labelling::Manifest(manifest.into()));
+        }
+        if let Some(extensions) = self.options.get("ext") {
+            options.extensions = extensions.split(',').map(|e| e.trim().trim_left_matches('.').to_lowercase()).collect();
+        }
+        options.hidden = self.parse_option("hidden", "true or false")?.unwrap_or(options.hidden);
+        Ok(options)
+    }
+
+    /// Opens the dataset. `test` selects the MNIST test set for `mnist` without a set.
+    ///
+    /// Along with the dataset, returns the errors of the files that were skipped.
+    pub fn open(&self, test: bool) -> Result<(Box<Dataset>, Vec<Error>)> {
+        self.open_with(test, &Pipeline::default())
+    }
+
+    /// Like `open`, but makes image files into inputs with `pipeline`.
+    pub fn open_with(&self, test: bool, pipeline: &Pipeline) -> Result<(Box<Dataset>, Vec<Error>)> {
+        let (mut data, skipped) = match self.options.get("cache") {
+            Some(cache) => self.open_cached(Path::new(cache), test, pipeline)?,
+            None => self.open_kind(test, pipeline)?,
+        };
+        if let Some(separator) = self.options.get("separator") {
+            data = Box::new(SeparatedLabels::new(data, separator));
+        }
+        if let Some(index) = self.options.get("index") {
+            data = Box::new(Indexed::open(data, index)?);
+        }
+        Ok((data, skipped))
+    }
+
+    /// Paths of the inputs and the labels of the MNIST set, the test one for `test` if the path names no set.
+    fn mnist_paths(&self, test: bool) -> Result<(PathBuf, PathBuf)> {
+        let test = match self.path.as_str() {
+            "" => test,
+            "train" => false,
+            "test" => true,
+            other => return Err(Error::argument("dataset", format!("MNIST has no {:?} set", other))),
+        };
+        Ok(MnistDigits::default_paths(test))
+    }
+
+    /// Path of the labels of an `idx` dataset.
+    fn idx_labels(&self) -> String {
+        self.options.get("labels").cloned()
+            .unwrap_or_else(|| self.path.replace("images", "labels").replace("idx3", "idx1"))
+    }
+
+    /// The files the dataset is read from, the ones in its directory for `images`.
+    fn files(&self, test: bool) -> Result<Vec<PathBuf>> {
+        fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+            for entry in dir.read_dir().map_err(|e| Error::io(dir, e))? {
+                let path = entry.map_err(|e| Error::io(dir, e))?.path();
+                if path.is_dir() {
+                    walk(&path, files)?;
+                } else {
+                    files.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = Vec::new();
+        match self.kind.as_str() {
+            "mnist" => {
+                let (inputs, labels) = self.mnist_paths(test)?;
+                files.push(inputs);
+                files.push(labels);
+            }
+            "idx" => {
+                files.push(PathBuf::from(&self.path));
+                files.push(PathBuf::from(self.idx_labels()));
+            }
+            "images" => {
+                walk(Path::new(&self.path), &mut files)?;
+                files.extend(self.options.get("manifest").map(PathBuf::from));
+            }
+            _ => files.push(PathBuf::from(&self.path)),
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Opens the dataset from the cache file `cache` if the fingerprint of its files matches, or opens it and writes
+    /// the cache otherwise.
+    fn open_cached(&self, cache: &Path, test: bool, pipeline: &Pipeline) -> Result<(Box<Dataset>, Vec<Error>)> {
+        let mmap = self.parse_option("mmap", "true or false")?.unwrap_or(false);
+        // everything but the options applied to the cached examples
+        let options: BTreeMap<&String, &String> = self.options.iter()
+            .filter(|&(name, _)| !["cache", "mmap", "separator", "index"].contains(&name.as_str()))
+            .collect();
+        let mut description = format!("{}:{}?{:?}", self.kind, self.path, options);
+        if self.kind == "mnist" {
+            description.push_str(&format!(" test={}", test));
+        } else if self.kind == "images" {
+            description.push_str(&format!(" preprocess={}", pipeline));
+        }
+        // a cache kept in the directory of the dataset isn't one of its files
+        let partial = cache.with_extension("partial");
+        let excluded = [cache.file_name(), partial.file_name()];
+        let files: Vec<PathBuf> = self.files(test)?.into_iter()
+            .filter(|f| !excluded.contains(&f.file_name()))
+            .collect();
+        let fingerprint = fingerprint(&description, &files)?;
+
+        if let Some(cached) = Cached::open(cache, fingerprint, mmap)? {
+            return Ok((Box::new(cached), Vec::new()));
+        }
+        let (data, mut skipped) = self.open_kind(test, pipeline)?;
+        skipped.extend(Cached::write(cache, &*data, fingerprint)?);
+        match Cached::open(cache, fingerprint, mmap)? {
+            Some(cached) => Ok((Box::new(cached), skipped)),
+            None => Err(Error::format(cache, "the cache that was just written can't be read")),
+        }
+    }
+
+    fn open_kind(&self, test: bool, pipeline: &Pipeline) -> Result<(Box<Dataset>, Vec<Error>)> {
+        match self.kind.as_str() {
+            "mnist" => {
+                self.allow_options(&[])?;
+                let (inputs, labels) = self.mnist_paths(test)?;
+                Ok((Box::new(IdxDataset::open(inputs, labels)?), Vec::new()))
+            }
+            "idx" => {
+                self.allow_options(&["labels"])?;
+                Ok((Box::new(IdxDataset::open(&self.path, self.idx_labels())?), Vec::new()))
+            }
+            "csv" => {
+                Ok((Box::new(CsvDataset::open_with(&self.path, &self.csv_options()?)?), Vec::new()))
+            }
+            "libsvm" => {
+                self.allow_options(&["features"])?;
+                let features = self.parse_option("features", "a number")?;
+                Ok((Box::new(LibSvmDataset::open(&self.path, features)?), Vec::new()))
+            }
+            _ => {
+                let options = ImageDirOptions { pipeline: pipeline.clone(), ..self.image_dir_options()? };
+                let (dir, skipped) = ImageDir::open_with(&self.path, &options)?;
+                match self.options.get("sidecar") {
+                    Some(extension) => Ok((Box::new(Sidecars::new(Box::new(dir), extension)?), skipped)),
+                    None => Ok((Box::new(dir), skipped)),
+                }
+            }
+        }
+    }
+}
+
+/// Opens the dataset named by `source`, see `Source`.
+///
+/// `test` selects the MNIST test set instead of the training one for plain `mnist`. Along with the dataset, returns
+/// the errors of the files that were skipped.
+pub fn open(source: &str, test: bool) -> Result<(Box<Dataset>, Vec<Error>)> {
+    source.parse::<Source>()?.open(test)
+}
+
+/// Like `open`, but makes image files into inputs with `pipeline`.
+pub fn open_with(source: &str, test: bool, pipeline: &Pipeline) -> Result<(Box<Dataset>, Vec<Error>)> {
+    source.parse::<Source>()?.open_with(test, pipeline)
+}
+
+/// Fails if `pipeline` isn't the default one but the examples of `source` aren't image files, the only ones it is
+/// applied to, so that nets learnt on them don't keep steps their inputs never went through.
+pub fn check_preprocessing(source: &str, pipeline: &Pipeline) -> Result<()> {
+    let source: Source = source.parse()?;
+    if source.kind != "images" && *pipeline != Pipeline::default() {
+        return Err(Error::argument("preprocess", format!("the examples of {} datasets aren't image files, they can't \
+                                                         be preprocessed with {}", source.kind, pipeline)));
+    }
+    Ok(())
+}
+
+/// Loads the dataset named by `source` into memory, see `open`.
+///
+/// Along with the examples, returns the errors of the files that were skipped, including the ones that couldn't be
+/// decoded.
+pub fn load(source: &str, test: bool) -> Result<(Examples, Vec<Error>)> {
+    load_with_sources(source, test).map(|(examples, _, skipped)| (examples, skipped))
+}
+
+/// Like `load`, but also returns the file every example was loaded from, `None` for the ones without a file of
+/// their own, like the MNIST digits.
+pub fn load_with_sources(source: &str, test: bool) -> Result<(Examples, Vec<Option<PathBuf>>, Vec<Error>)> {
+    let (data, mut skipped) = open(source, test)?;
+    let mut examples = Vec::with_capacity(data.len());
+    let mut sources = Vec::with_capacity(data.len());
+    for i in 0..data.len() {
+        match data.get(i) {
+            Ok(example) => {
+                examples.push(example);
+                sources.push(data.source(i));
+            }
+            Err(e) => skipped.push(e),
+        }
+    }
+    if examples.is_empty() {
+        return Err(Error::EmptyDataset { source: source.into() });
+    }
+    Ok((examples, sources, skipped))
+}
+
+#[test]
+fn test_source() {
+    let source: Source = "images:res/x?regex=^([a-z]+)&hidden=true".parse().unwrap();
+    assert_eq!((source.kind.as_str(), source.path.as_str()), ("images", "res/x"));
+    assert_eq!(source.options["regex"], "^([a-z]+)");
+    assert!(source.image_dir_options().unwrap().hidden);
+    assert_eq!("mnist".parse::<Source>().unwrap().kind, "mnist");
+    assert_eq!("data/iris.csv".parse::<Source>().unwrap().kind, "csv");
+    assert_eq!("a9a.libsvm".parse::<Source>().unwrap().kind, "libsvm");
+    let csv: Source = "csv:iris.csv?label=species&header=true&delimiter=tab".parse().unwrap();
+    let options = csv.csv_options().unwrap();
+    assert_eq!((options.label, options.header, options.delimiter), (Some(Column::Name("species".into())), true, '\t'));
+    let targets: Source = "csv:houses.csv?header=true&targets=price".parse().unwrap();
+    let options = targets.csv_options().unwrap();
+    assert_eq!((options.label, options.targets), (None, vec![Column::Name("price".into())]));
+    assert_eq!("C:/digits".parse::<Source>().unwrap().path, "C:/digits");
+}
+
+#[test]
+fn test_check_preprocessing() {
+    let resized: Pipeline = "gray,resize=7x10".parse().unwrap();
+    assert!(check_preprocessing("res/digits", &resized).is_ok());
+    assert!(check_preprocessing("mnist", &resized).is_err());
+    assert!(check_preprocessing("data/iris.csv", &resized).is_err());
+    assert!(check_preprocessing("mnist", &Pipeline::default()).is_ok());
+}
+
+#[test]
+fn test_image_dir_skips_bad_images() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    let dir = env::temp_dir().join("mulperc-test-bad-images");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    img::save(&[0.0, 1.0, 1.0, 0.0], 2, 2, dir.join("a_1.png")).unwrap();
+    img::save(&[1.0, 0.0, 0.0, 1.0], 2, 2, dir.join("b_2.png")).unwrap();
+    img::save(&[0.0, 1.0, 0.0, 1.0, 0.0, 1.0], 3, 2, dir.join("c_3.png")).unwrap();
+    let png = img::png(&[0.0, 1.0, 1.0, 0.0], 2, 2).unwrap();
+    File::create(dir.join("d_4.png")).unwrap().write_all(&png[..png.len() / 2]).unwrap();
+
+    let (data, skipped) = ImageDir::open(&dir).unwrap();
+    assert_eq!(data.len(), 2);
+    assert_eq!(skipped.len(), 2);
+    assert!(load_batch(&data, &[0, 1]).is_ok());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_separated_labels() {
+    let examples: Examples = vec![(vec![0.0], "cat+dog".into()), (vec![1.0], "dog".into())];
+    let data = SeparatedLabels::new(Box::new(examples), "+");
+    assert_eq!(data.label_set(0), vec!["cat", "dog"]);
+    assert_eq!(data.set_labels().into_iter().collect::<Vec<_>>(), vec!["cat", "dog"]);
+}
+
+#[test]
+fn test_subset() {
+    let examples: Examples = vec![(vec![0.0, 1.0], "a".into()), (vec![1.0, 0.0], "b".into()), (vec![1.0, 1.0], "a".into())];
+    let subset = Subset::new(&examples[..], vec![2, 0]);
+    assert_eq!(subset.len(), 2);
+    assert_eq!(subset.input_len(), 2);
+    assert_eq!(subset.get(0).unwrap(), examples[2]);
+    assert_eq!(subset.labels().into_iter().collect::<Vec<_>>(), vec!["a".to_string()]);
+    let streamed: Result<Examples> = stream(&subset).collect();
+    assert_eq!(streamed.unwrap(), vec![examples[2].clone(), examples[0].clone()]);
+}
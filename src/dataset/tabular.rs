@@ -0,0 +1,459 @@
+//! Tabular datasets: CSV files and sparse LibSVM files, indexed once and then read line by line.
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use super::{Dataset, SharedFile};
+use error::{Error, Result};
+
+/// Largest number of distinct values of a one-hot encoded column.
+pub const MAX_CATEGORIES: usize = 1000;
+
+/// The non-empty lines of a file, any of which can be read again.
+struct Lines {
+    file: SharedFile,
+    /// Offset and length of every line.
+    spans: Vec<(u64, usize)>,
+}
+
+impl Lines {
+    /// Indexes the non-empty lines of `path`, passing every one of them, trimmed, to `keep` along with the number of
+    /// lines kept so far. The lines it returns false for aren't indexed.
+    fn index<F>(path: &Path, mut keep: F) -> Result<Lines>
+        where F: FnMut(usize, &str) -> Result<bool>
+    {
+        let mut reader = BufReader::new(File::open(path).map_err(|e| Error::io(path, e))?);
+        let mut lines = Lines { file: SharedFile::open(path)?, spans: Vec::new() };
+
+        let mut offset = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let len = reader.read_until(b'\n', &mut line).map_err(|e| Error::io(path, e))?;
+            if len == 0 {
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim();
+            if !text.is_empty() && keep(lines.spans.len(), text)? {
+                lines.spans.push((offset, len));
+            }
+            offset += len as u64;
+        }
+
+        if lines.spans.is_empty() {
+            return Err(Error::EmptyDataset { source: path.display().to_string() });
+        }
+        Ok(lines)
+    }
+
+    fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    fn read(&self, i: usize) -> Result<String> {
+        let (offset, len) = self.spans[i];
+        let line = self.file.read_at(offset, len)?;
+        Ok(String::from_utf8_lossy(&line).trim().to_string())
+    }
+}
+
+/// A column of a CSV file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// The position of the column, starting at 0.
+    Index(usize),
+    /// The name of the column in the header.
+    Name(String),
+}
+
+impl Column {
+    /// The position of the column in records of `len` fields, `names` being the header if there is one.
+    fn resolve(&self, names: Option<&[String]>, len: usize, path: &Path) -> Result<usize> {
+        let i = match *self {
+            Column::Index(i) => i,
+            Column::Name(ref name) => {
+                let names = names
+                    .ok_or_else(|| Error::format(path, format!("no header to find column {:?} in", name)))?;
+                names.iter().position(|n| n == name)
+                    .ok_or_else(|| Error::format(path, format!("no column {:?} in the header", name)))?
+            }
+        };
+        if i >= len {
+            return Err(Error::format(path, format!("no column {}, the records have {} fields", i, len)));
+        }
+        Ok(i)
+    }
+}
+
+impl<'a> From<&'a str> for Column {
+    /// A number is the position of the column, anything else its name.
+    fn from(s: &'a str) -> Column {
+        match s.parse() {
+            Ok(i) => Column::Index(i),
+            Err(_) => Column::Name(s.to_string()),
+        }
+    }
+}
+
+/// How a CSV file is read.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// The column of the labels, the first one by default. Without one every example is labelled `""`.
+    pub label: Option<Column>,
+    /// Whether the first line names the columns.
+    pub header: bool,
+    pub delimiter: char,
+    /// Columns to one-hot encode even though their values are numbers. The columns with values that aren't numbers
+    /// always are.
+    pub categorical: Vec<Column>,
+    /// Columns of numbers regression nets learn to output, which aren't part of the input.
+    pub targets: Vec<Column>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            label: Some(Column::Index(0)),
+            header: false,
+            delimiter: ',',
+            categorical: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+}
+
+/// How the values of a column become input values.
+#[derive(Debug, Clone, PartialEq)]
+enum Encoding {
+    /// The label column, which isn't part of the input.
+    Label,
+    /// A target column, which isn't part of the input either.
+    Target,
+    Number,
+    /// One input value per category, 1 for the value of the record and 0 for the others.
+    OneHot(Vec<String>),
+}
+
+/// Splits a CSV record on `delimiter`, outside of double quoted fields, and trims the fields.
+fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                field.push('"');
+                chars.next();
+            } else {
+                quoted = false;
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(field.trim().to_string());
+            field.clear();
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// A CSV file whose records are a label, the values of an input and possibly targets, read line by line, so quoted
+/// fields can't span lines.
+///
+/// The values that are numbers are inputs as they are. The columns with other values, and the `categorical` ones, are
+/// one-hot encoded over the values found in the file, so a file checked against a net has to have the same ones.
+pub struct CsvDataset {
+    lines: Lines,
+    delimiter: char,
+    labels: Vec<String>,
+    columns: Vec<Encoding>,
+    input_len: usize,
+    target_names: Vec<String>,
+    /// Positions of the target columns, in the order of `target_names`.
+    target_columns: Vec<usize>,
+}
+
+impl CsvDataset {
+    /// Opens a CSV file without a header whose first column is the label.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<CsvDataset> {
+        CsvDataset::open_with(path, &CsvOptions::default())
+    }
+
+    pub fn open_with<P: AsRef<Path>>(path: P, options: &CsvOptions) -> Result<CsvDataset> {
+        let path = path.as_ref();
+        let mut names: Option<Vec<String>> = None;
+        let mut label = None;
+        let mut targets = Vec::new();
+        let mut forced = BTreeSet::new();
+        let mut labels = Vec::new();
+        // For every column, whether its values are all numbers, and its distinct values unless there are too many.
+        let mut numeric: Vec<bool> = Vec::new();
+        let mut categories: Vec<Option<BTreeSet<String>>> = Vec::new();
+
+        let lines = Lines::index(path, |i, text| -> Result<bool> {
+            let fields = split_record(text, options.delimiter);
+            if options.header && names.is_none() {
+                names = Some(fields);
+                return Ok(false);
+            }
+            if i == 0 {
+                let header = names.as_ref().map(|n| &n[..]);
+                if header.map(|n| n.len() != fields.len()).unwrap_or(false) {
+                    return Err(Error::record(path, i, format!("{} fields for {} columns in the header",
+                                                              fields.len(), header.unwrap().len())));
+                }
+                label = match options.label {
+                    Some(ref column) => Some(column.resolve(header, fields.len(), path)?),
+                    None => None,
+                };
+                for column in &options.targets {
+                    targets.push(column.resolve(header, fields.len(), path)?);
+                }
+                for column in &options.categorical {
+                    forced.insert(column.resolve(header, fields.len(), path)?);
+                }
+                numeric = vec![true; fields.len()];
+                categories = vec![Some(BTreeSet::new()); fields.len()];
+            } else if fields.len() != numeric.len() {
+                return Err(Error::record(path, i, format!("expected {} fields, got {}", numeric.len(), fields.len())));
+            }
+
+            for &j in &targets {
+                if fields[j].parse::<f64>().is_err() {
+                    return Err(Error::record(path, i, format!("target {:?} is not a number", fields[j])));
+                }
+            }
+            for (j, field) in fields.iter().enumerate().filter(|&(j, _)| Some(j) != label && !targets.contains(&j)) {
+                if numeric[j] && field.parse::<f64>().is_err() {
+                    numeric[j] = false;
+                }
+                let too_many = match categories[j] {
+                    Some(ref mut values) => {
+                        values.insert(field.clone());
+                        values.len() > MAX_CATEGORIES
+                    }
+                    None => false,
+                };
+                if too_many {
+                    categories[j] = None;
+                }
+            }
+            labels.push(label.map(|l| fields[l].clone()).unwrap_or(String::new()));
+            Ok(true)
+        })?;
+
+        let mut columns = Vec::with_capacity(numeric.len());
+        for (j, values) in categories.into_iter().enumerate() {
+            columns.push(if Some(j) == label {
+                Encoding::Label
+            } else if targets.contains(&j) {
+                Encoding::Target
+            } else if numeric[j] && !forced.contains(&j) {
+                Encoding::Number
+            } else {
+                let values = values.ok_or_else(|| Error::format(path, format!("column {} has more than {} categories",
+                                                                               j, MAX_CATEGORIES)))?;
+                Encoding::OneHot(values.into_iter().collect())
+            });
+        }
+        let input_len: usize = columns.iter().map(|c| match *c {
+            Encoding::Label | Encoding::Target => 0,
+            Encoding::Number => 1,
+            Encoding::OneHot(ref values) => values.len(),
+        }).sum();
+
+        let target_names = targets.iter()
+            .map(|&j| names.as_ref().map(|n| n[j].clone()).unwrap_or_else(|| format!("column {}", j)))
+            .collect();
+        Ok(CsvDataset {
+            lines: lines,
+            delimiter: options.delimiter,
+            labels: labels,
+            columns: columns,
+            input_len: input_len,
+            target_names: target_names,
+            target_columns: targets,
+        })
+    }
+
+    /// The fields of the record `i`.
+    fn fields(&self, i: usize) -> Result<Vec<String>> {
+        let fields = split_record(&self.lines.read(i)?, self.delimiter);
+        if fields.len() != self.columns.len() {
+            return Err(Error::record(self.lines.path(), i,
+                                     format!("expected {} fields, got {}", self.columns.len(), fields.len())));
+        }
+        Ok(fields)
+    }
+}
+
+impl Dataset for CsvDataset {
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        vec![self.input_len]
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self.labels[i]
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        let fields = self.fields(i)?;
+        let mut input = Vec::with_capacity(self.input_len);
+        for (field, column) in fields.iter().zip(&self.columns) {
+            match *column {
+                Encoding::Label | Encoding::Target => {}
+                Encoding::Number => {
+                    let x = field.parse()
+                        .map_err(|_| Error::record(self.lines.path(), i, format!("{:?} is not a number", field)))?;
+                    input.push(x);
+                }
+                Encoding::OneHot(ref values) => {
+                    let hot = values.binary_search(field).ok();
+                    input.extend((0..values.len()).map(|k| if Some(k) == hot { 1.0 } else { 0.0 }));
+                }
+            }
+        }
+        Ok(input)
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        self.target_names.clone()
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        let fields = self.fields(i)?;
+        self.target_columns.iter()
+            .map(|&j| fields[j].parse()
+                .map_err(|_| Error::record(self.lines.path(), i, format!("target {:?} is not a number", fields[j]))))
+            .collect()
+    }
+}
+
+/// Splits a LibSVM line into its label and its `(index, value)` pairs, the indices starting at 1. Comments after `#`
+/// and `qid:` ranking ids are left out.
+fn parse_libsvm(line: &str) -> ::std::result::Result<(&str, Vec<(usize, f64)>), String> {
+    let line = line.split('#').next().unwrap();
+    let mut tokens = line.split_whitespace();
+    let label = tokens.next().ok_or_else(|| "no label".to_string())?;
+
+    let mut values = Vec::new();
+    for token in tokens.filter(|t| !t.starts_with("qid:")) {
+        let i = token.find(':').ok_or_else(|| format!("{:?} is not INDEX:VALUE", token))?;
+        let index = match token[..i].parse::<usize>() {
+            Ok(index) if index > 0 => index,
+            _ => return Err(format!("{:?} is not an index starting at 1", &token[..i])),
+        };
+        let value = token[i + 1..].parse::<f64>().map_err(|_| format!("{:?} is not a number", &token[i + 1..]))?;
+        values.push((index, value));
+    }
+    Ok((label, values))
+}
+
+/// A sparse LibSVM file: `LABEL INDEX:VALUE ...` lines, the indices starting at 1 and the values left out being 0,
+/// read line by line.
+///
+/// If all the labels are numbers, they are also the targets of regression nets.
+pub struct LibSvmDataset {
+    lines: Lines,
+    labels: Vec<String>,
+    input_len: usize,
+    numeric: bool,
+}
+
+impl LibSvmDataset {
+    /// Opens `path`, whose inputs have `features` values, or as many as the largest index in the file.
+    pub fn open<P: AsRef<Path>>(path: P, features: Option<usize>) -> Result<LibSvmDataset> {
+        let path = path.as_ref();
+        let mut labels = Vec::new();
+        let mut max_index = 0;
+        let lines = Lines::index(path, |i, text| -> Result<bool> {
+            if text.starts_with('#') {
+                return Ok(false);
+            }
+            let (label, values) = parse_libsvm(text).map_err(|msg| Error::record(path, i, msg))?;
+            for &(index, _) in &values {
+                if features.map(|n| index > n).unwrap_or(false) {
+                    return Err(Error::record(path, i, format!("index {} is past the {} features",
+                                                              index, features.unwrap())));
+                }
+                max_index = ::std::cmp::max(max_index, index);
+            }
+            labels.push(label.to_string());
+            Ok(true)
+        })?;
+
+        Ok(LibSvmDataset {
+            numeric: labels.iter().all(|label| label.parse::<f64>().is_ok()),
+            lines: lines,
+            labels: labels,
+            input_len: features.unwrap_or(max_index),
+        })
+    }
+}
+
+impl Dataset for LibSvmDataset {
+    fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    fn shape(&self) -> Vec<usize> {
+        vec![self.input_len]
+    }
+
+    fn label(&self, i: usize) -> &str {
+        &self.labels[i]
+    }
+
+    fn input(&self, i: usize) -> Result<Vec<f64>> {
+        let line = self.lines.read(i)?;
+        let (_, values) = parse_libsvm(&line).map_err(|msg| Error::record(self.lines.path(), i, msg))?;
+        let mut input = vec![0.0; self.input_len];
+        for (index, value) in values {
+            if index > self.input_len {
+                return Err(Error::record(self.lines.path(), i, format!("index {} is past the {} features",
+                                                                       index, self.input_len)));
+            }
+            input[index - 1] = value;
+        }
+        Ok(input)
+    }
+
+    fn target_names(&self) -> Vec<String> {
+        if self.numeric { vec!["label".to_string()] } else { Vec::new() }
+    }
+
+    fn target(&self, i: usize) -> Result<Vec<f64>> {
+        match self.labels[i].parse() {
+            Ok(target) if self.numeric => Ok(vec![target]),
+            _ => Err(Error::argument("dataset", "the labels aren't numbers, so the examples have no targets")),
+        }
+    }
+}
+
+#[test]
+fn test_parse_records() {
+    assert_eq!(split_record("a, \"b,c\" ,\"say \"\"hi\"\"\"", ','), vec!["a", "b,c", "say \"hi\""]);
+    assert_eq!(split_record("1;2;", ';'), vec!["1", "2", ""]);
+
+    let (label, values) = parse_libsvm("+1 qid:3 2:0.5 10:-1 # comment").unwrap();
+    assert_eq!(label, "+1");
+    assert_eq!(values, vec![(2, 0.5), (10, -1.0)]);
+    assert!(parse_libsvm("1 0:1").is_err());
+    assert!(parse_libsvm("1 3").is_err());
+}
@@ -0,0 +1,111 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+use image;
+
+/// Crate-wide result type.
+pub type Result<T> = result::Result<T, Error>;
+
+/// Everything that can go wrong while loading data, reading or writing nets, and training.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing `path` failed.
+    Io { path: PathBuf, err: io::Error },
+    /// `path` could not be decoded as an image.
+    Image { path: PathBuf, err: image::ImageError },
+    /// `path` exists, but its contents are not what we expected (bad magic number, truncated data...).
+    Format { path: PathBuf, msg: String },
+    /// Record number `index` of `path` is invalid.
+    Record { path: PathBuf, index: usize, msg: String },
+    /// A vector has a different length than the one required in `context`.
+    Shape { context: String, expected: usize, actual: usize },
+    /// A net file could not be encoded or decoded.
+    Net { path: PathBuf, msg: String },
+    /// A dataset turned out to have no usable examples.
+    EmptyDataset { source: String },
+    /// A command line argument has an invalid value.
+    Argument { name: String, msg: String },
+    /// The requested functionality wasn't compiled in.
+    Unsupported { what: String },
+}
+
+impl Error {
+    pub fn io<P: AsRef<Path>>(path: P, err: io::Error) -> Error {
+        Error::Io { path: path.as_ref().to_path_buf(), err: err }
+    }
+
+    pub fn image<P: AsRef<Path>>(path: P, err: image::ImageError) -> Error {
+        Error::Image { path: path.as_ref().to_path_buf(), err: err }
+    }
+
+    pub fn format<P: AsRef<Path>, S: Into<String>>(path: P, msg: S) -> Error {
+        Error::Format { path: path.as_ref().to_path_buf(), msg: msg.into() }
+    }
+
+    pub fn record<P: AsRef<Path>, S: Into<String>>(path: P, index: usize, msg: S) -> Error {
+        Error::Record { path: path.as_ref().to_path_buf(), index: index, msg: msg.into() }
+    }
+
+    pub fn shape<S: Into<String>>(context: S, expected: usize, actual: usize) -> Error {
+        Error::Shape { context: context.into(), expected: expected, actual: actual }
+    }
+
+    pub fn net<P: AsRef<Path>, E: fmt::Display>(path: P, err: E) -> Error {
+        Error::Net { path: path.as_ref().to_path_buf(), msg: err.to_string() }
+    }
+
+    pub fn argument<N: Into<String>, S: Into<String>>(name: N, msg: S) -> Error {
+        Error::Argument { name: name.into(), msg: msg.into() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io { ref path, ref err } =>
+                write!(f, "{}: {}", path.display(), err),
+            Error::Image { ref path, ref err } =>
+                write!(f, "{}: could not decode image: {}", path.display(), err),
+            Error::Format { ref path, ref msg } =>
+                write!(f, "{}: {}", path.display(), msg),
+            Error::Record { ref path, index, ref msg } =>
+                write!(f, "{}: record {}: {}", path.display(), index, msg),
+            Error::Shape { ref context, expected, actual } =>
+                write!(f, "{}: expected {} values, got {}", context, expected, actual),
+            Error::Net { ref path, ref msg } =>
+                write!(f, "{}: invalid net file: {}", path.display(), msg),
+            Error::EmptyDataset { ref source } =>
+                write!(f, "{}: no usable examples found", source),
+            Error::Argument { ref name, ref msg } =>
+                write!(f, "invalid value for {}: {}", name, msg),
+            Error::Unsupported { ref what } =>
+                write!(f, "{} is not supported by this build", what),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Io { .. } => "I/O error",
+            Error::Image { .. } => "image decoding error",
+            Error::Format { .. } => "invalid file format",
+            Error::Record { .. } => "invalid record",
+            Error::Shape { .. } => "shape mismatch",
+            Error::Net { .. } => "invalid net file",
+            Error::EmptyDataset { .. } => "empty dataset",
+            Error::Argument { .. } => "invalid argument",
+            Error::Unsupported { .. } => "unsupported",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::Io { ref err, .. } => Some(err),
+            Error::Image { ref err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
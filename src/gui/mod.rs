@@ -0,0 +1,6 @@
+//! Conrod based graphical interface.
+
+mod window;
+mod window_gui;
+
+pub use self::window::window_loop;
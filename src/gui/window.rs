@@ -1,19 +1,35 @@
 use conrod;
 use conrod::backend::glium::glium;
 use conrod::backend::glium::glium::{DisplayBuild, Surface};
-use window_gui;
+use gui::window_gui;
 use std;
 use image;
 use find_folder;
-use img;
+use std::io::{self, Write};
 use std::path::Path;
 use std::cell::{RefCell, Cell};
-use mnist::MnistDigits;
+use std::sync::mpsc;
+use std::thread;
+use mulperc::dataset::{self, Examples};
+use mulperc::mnist;
+use mulperc::error::Result;
 
 const WIN_W: u32 = window_gui::WIN_W;
 const WIN_H: u32 = window_gui::WIN_H;
 
-pub fn window_loop() {
+/// The MNIST training set previewed by the window, decoded once into a cache.
+const MNIST_TRAINING_SET: &'static str = "mnist:train?cache=res/mnist/train.cache";
+
+/// Loads the MNIST training set in the background, so that the window opens right away.
+fn load_mnist_in_background() -> mpsc::Receiver<Result<Examples>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(dataset::load(MNIST_TRAINING_SET, false).map(|(examples, _)| examples));
+    });
+    receiver
+}
+
+pub fn window_loop() -> Result<()> {
     let display = glium::glutin::WindowBuilder::new()
         .with_vsync()
         .with_dimensions(WIN_W, WIN_H)
@@ -26,7 +42,8 @@ pub fn window_loop() {
     let mut ids = RefCell::new(window_gui::Ids::new(ui.widget_id_generator()));
     let assets = find_folder::Search::KidsThenParents(3, 5).for_folder("assets").unwrap();
     let font_path = assets.join("fonts/NotoSans/NotoSans-Regular.ttf");
-    let mnist = MnistDigits::default_training_set().unwrap();
+    let mnist_loading = load_mnist_in_background();
+    let mnist: RefCell<Option<Examples>> = RefCell::new(None);
     ui.fonts.insert_from_file(font_path).unwrap();
 
     let mut force_update = Cell::new(false);
@@ -35,7 +52,7 @@ pub fn window_loop() {
         &*ids.borrow(),
         load_image(&display, app.borrow().classifier.image.path
             .as_ref().map(|s| s.as_str()).unwrap_or("assets/images/rust.png")),
-        load_mnist(&display, &mnist[app.borrow().mnist.idx])
+        load_mnist(&display, &blank_digit())
     ));
 
     let mut classifier_img_updater = Updater::new(|| {
@@ -47,13 +64,18 @@ pub fn window_loop() {
     });
 
     let mut mnist_updater = Updater::new(|| {
-        app.borrow().mnist.idx
-    }, |idx| {
-        let data = &mnist[app.borrow().mnist.idx];
-        app.borrow_mut().mnist.label = data.1.clone();
+        (app.borrow().mnist.idx, mnist.borrow().is_some())
+    }, |&(idx, _)| {
+        let texture = match *mnist.borrow() {
+            Some(ref mnist) => {
+                let data = &mnist[idx];
+                app.borrow_mut().mnist.label = data.1.clone();
+                load_mnist(&display, data)
+            }
+            None => load_mnist(&display, &blank_digit()),
+        };
         force_update.set(true);
-        image_map.borrow_mut().insert(ids.borrow().mnist_img,
-            load_mnist(&display, data));
+        image_map.borrow_mut().insert(ids.borrow().mnist_img, texture);
     });
 
     let mut renderer = conrod::backend::glium::Renderer::new(&display).unwrap();
@@ -88,6 +110,12 @@ pub fn window_loop() {
             }
         }
 
+        match mnist_loading.try_recv() {
+            Ok(Ok(examples)) => *mnist.borrow_mut() = Some(examples),
+            Ok(Err(e)) => { let _ = writeln!(io::stderr(), "warning: can't preview the MNIST digits: {}", e); }
+            Err(_) => {}
+        }
+
         classifier_img_updater.update();
         mnist_updater.update();
 
@@ -107,6 +135,8 @@ pub fn window_loop() {
 
         std::thread::sleep(std::time::Duration::from_millis(16));
     }
+
+    Ok(())
 }
 
 fn load_image<P: AsRef<Path>>(display: &glium::Display, path: P) -> glium::texture::Texture2d {
@@ -117,6 +147,11 @@ fn load_image<P: AsRef<Path>>(display: &glium::Display, path: P) -> glium::textu
     texture
 }
 
+/// The empty digit shown until the MNIST digits are loaded.
+fn blank_digit() -> (Vec<f64>, String) {
+    (vec![0.0; (mnist::SIZE * mnist::SIZE) as usize], String::new())
+}
+
 fn load_mnist(display: &glium::Display, datapoint: &(Vec<f64>, String)) -> glium::texture::Texture2d {
     let rgba_image = image::DynamicImage::ImageLuma8(image::ImageBuffer::<image::Luma<u8>, _>::from_raw(28, 28, datapoint.0.iter()
         .map(|f| (f * 255.0) as u8).collect::<Vec<u8>>()).unwrap()).to_rgba();
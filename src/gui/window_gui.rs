@@ -1,19 +1,13 @@
-extern crate rand;
-
 use conrod;
 use std;
 use nfd;
-use multilayer_perceptron::{MultilayerPerceptron, NetFile};
-use std::collections::HashMap;
-use std::path::Path;
-use bincode;
-use na::Iterable;
-use mnist::MnistDigits;
+use mulperc::multilayer_perceptron::MultilayerPerceptron;
+use mulperc::Classifier;
+use mulperc::inference::Decision;
 use std::ops::{Deref, DerefMut};
-use img;
-use std::fs::File;
-#[macro_use]
-use util;
+use mulperc::img;
+use mulperc::preprocess::Image;
+use mulperc::error::Result;
 
 pub const WIN_W: u32 = 600;
 pub const WIN_H: u32 = 720;
@@ -56,7 +50,7 @@ impl<T> DerefMut for WithPath<T> {
 
 pub struct ClassifierState {
     pub image: WithPath<Vec<f64>>,
-    pub net: WithPath<NetFile>,
+    pub net: WithPath<Classifier>,
     pub drawing: bool,
     pub drawn_image: Vec<bool>,
 }
@@ -218,8 +212,13 @@ fn classifier_tab(ui: &mut conrod::UiCell, ids: &mut Ids, classifier: &mut Class
             {
                 if let Ok(response) = nfd::open_file_dialog(None, None) {
                     if let nfd::Response::Okay(path) = response {
-                        classifier.image.data = Some(img::get_pixels(&path));
-                        classifier.image.path = Some(path);
+                        match read_image(classifier.net.data.as_ref(), &path) {
+                            Ok(pixels) => {
+                                classifier.image.data = Some(pixels);
+                                classifier.image.path = Some(path);
+                            }
+                            Err(e) => println!("{}", e),
+                        }
                     }
                 }
             }
@@ -232,10 +231,18 @@ fn classifier_tab(ui: &mut conrod::UiCell, ids: &mut Ids, classifier: &mut Class
             {
                 if let Ok(response) = nfd::open_file_dialog(None, None) {
                     if let nfd::Response::Okay(path) = response {
-                        classifier.net.data = ignore_err! {
-                            bincode::serde::deserialize_from(&mut File::open(&path)?, bincode::SizeLimit::Infinite)
+                        classifier.net.data = match Classifier::load(&path) {
+                            Ok(net) => Some(net),
+                            Err(e) => { println!("{}", e); None }
                         };
                         classifier.net.path = Some(path);
+                        // the image is read again with the preprocessing of the new net
+                        if let Some(ref path) = classifier.image.path {
+                            classifier.image.data = match read_image(classifier.net.data.as_ref(), path) {
+                                Ok(pixels) => Some(pixels),
+                                Err(e) => { println!("{}", e); None }
+                            };
+                        }
                     }
                 }
             }
@@ -276,25 +283,20 @@ fn classifier_tab(ui: &mut conrod::UiCell, ids: &mut Ids, classifier: &mut Class
                 }
         }
 
-        if let Some(NetFile(ref perc, ref labels)) = classifier.net.data {
-            let decoded = if perc.layers[0].num_inputs() - 1 == cols * rows {
-                let img: Vec<_> = classifier.drawn_image.iter()
-                    .map(|&x| if x { 1.0 } else { 0.0 }).collect();
-                let out = perc.feed_forward(&img).0;
-                &labels[&out.iter().enumerate()
-                    .max_by(|a, b|
-                        a.1.partial_cmp(b.1).unwrap()
-                    ).unwrap().0]
-            } else {
-                "ERR"
-            };
+        if let Some(ref net) = classifier.net.data {
+            // dark ink on white paper, like in the image files
+            let drawn: Vec<_> = classifier.drawn_image.iter()
+                .map(|&x| if x { 0.0 } else { 1.0 }).collect();
+            let paper = Image::gray(cols as u32, rows as u32, drawn).unwrap();
+            let img = net.net().preprocessing.process(paper).pixels;
+            let (decoded, font_size) = result_text(net, &img, image_h);
 
             widget::Text::new(&decoded)
                 .align_text_middle()
                 .w(half_width)
                 .right(GAP)
                 .y_relative_to(ids.classifier_drawing_matrix, image_h * 0.15)
-                .font_size((image_h * 1.2) as u32)
+                .font_size(font_size)
                 .set(ids.classifier_res, ui);
         }
     } else {
@@ -304,24 +306,16 @@ fn classifier_tab(ui: &mut conrod::UiCell, ids: &mut Ids, classifier: &mut Class
             .down(GAP)
             .set(ids.classifier_preview_img, ui);
 
-        if let Some(NetFile(ref perc, ref labels)) = classifier.net.data {
+        if let Some(ref net) = classifier.net.data {
             if let Some(ref image) = classifier.image.data {
-                let decoded = if perc.layers[0].num_inputs() - 1 == image.len() {
-                    let out = perc.feed_forward(&*image).0;
-                    &labels[&out.iter().enumerate()
-                        .max_by(|a, b|
-                            a.1.partial_cmp(b.1).unwrap()
-                        ).unwrap().0]
-                } else {
-                    "ERR"
-                };
+                let (decoded, font_size) = result_text(net, image, image_h);
 
                 widget::Text::new(&decoded)
                     .align_text_middle()
                     .w(half_width)
                     .right(GAP)
                     .y_relative(image_h * 0.15)
-                    .font_size((image_h * 1.2) as u32)
+                    .font_size(font_size)
                     .set(ids.classifier_res, ui);
             }
         }
@@ -330,6 +324,24 @@ fn classifier_tab(ui: &mut conrod::UiCell, ids: &mut Ids, classifier: &mut Class
     widget::Scrollbar::y_axis(ids.classifier_canvas).auto_hide(true).set(ids.classifier_scrollbar, ui);
 }
 
+/// Reads the image at `path` as an input of `net`, with the default preprocessing if no net is loaded.
+fn read_image(net: Option<&Classifier>, path: &str) -> Result<Vec<f64>> {
+    match net {
+        Some(net) => net.net().preprocessing.load(path),
+        None => img::get_pixels(path),
+    }
+}
+
+/// The best label for `image` (`?` if the net rejects it) in a font as big as the image, or the reason it couldn't
+/// be classified in a small one.
+fn result_text(net: &Classifier, image: &[f64], image_h: conrod::Scalar) -> (String, conrod::FontSize) {
+    match net.decide(image) {
+        Ok(Decision::Label(label, _)) => (label, (image_h * 1.2) as conrod::FontSize),
+        Ok(Decision::Unknown) => ("?".into(), (image_h * 1.2) as conrod::FontSize),
+        Err(e) => (e.to_string(), 12),
+    }
+}
+
 fn mnist_preview_tab(ui: &mut conrod::UiCell, ids: &Ids, mnist_state: &mut MnistPreviewState) {
     use conrod::{widget, Labelable, Positionable, Sizeable, Widget};
 
@@ -1,12 +1,38 @@
 extern crate flate2;
 extern crate libc;
 
+use self::flate2::Compression;
 use self::flate2::read::GzDecoder;
+use self::flate2::write::GzEncoder;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::io;
+use std::path::Path;
 use self::libc::*;
 use std::mem;
+use error::{Error, Result};
+
+/// The first bytes of every gzip stream.
+pub const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompresses the gzip stream `bytes`, read from `fname`.
+pub fn decompress<P: AsRef<Path>>(bytes: &[u8], fname: P) -> Result<Vec<u8>> {
+    let fname = fname.as_ref();
+    let mut r: Vec<u8> = Vec::new();
+    GzDecoder::new(bytes)
+        .map_err(|_| Error::format(fname, "invalid gzip header"))?
+        .read_to_end(&mut r)
+        .map_err(|e| Error::format(fname, format!("could not unzip data: {}", e)))?;
+    Ok(r)
+}
+
+/// Compresses `bytes` into a new gzip file at `fname`.
+pub fn write_file<P: AsRef<Path>>(fname: P, bytes: &[u8]) -> Result<()> {
+    let fname = fname.as_ref();
+    let file = File::create(fname).map_err(|e| Error::io(fname, e))?;
+    let mut encoder = GzEncoder::new(file, Compression::Default);
+    encoder.write_all(bytes).and_then(|_| encoder.finish()).map(|_| ()).map_err(|e| Error::io(fname, e))
+}
 
 /// Struct to decompress gzip streams.
 pub struct GzipData {
@@ -15,15 +41,13 @@ pub struct GzipData {
 }
 
 impl GzipData {
-    pub fn from_file(fname: &str) -> Result<GzipData, &'static str> {
-        let mut r: Vec<u8> = Vec::new();
-        GzDecoder::new(File::open(fname).map_err(|_| "Could not open file")?)
-            .map_err(|_| "Invalid gzip header.")?
-            .read_to_end(&mut r)
-            .map_err(|_| "Could not unzip data.")?;
+    pub fn from_file<P: AsRef<Path>>(fname: P) -> Result<GzipData> {
+        let fname = fname.as_ref();
+        let mut bytes: Vec<u8> = Vec::new();
+        File::open(fname).and_then(|mut f| f.read_to_end(&mut bytes)).map_err(|e| Error::io(fname, e))?;
 
         Ok(GzipData {
-            v: r,
+            v: decompress(&bytes, fname)?,
             idx: 0
         })
     }
@@ -0,0 +1,280 @@
+//! The IDX file format of MNIST and its relatives: a big endian array of any number of dimensions, of one of six
+//! data types, gzipped or not.
+//!
+//! The header is two zero bytes, the data type, the number of dimensions, and the size of every dimension as a
+//! 32 bit integer.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::mem;
+use std::path::Path;
+use gzip;
+use error::{Error, Result};
+
+/// Type of the values of an IDX file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    U8,
+    I8,
+    I16,
+    I32,
+    F32,
+    F64,
+}
+
+impl DataType {
+    fn from_code(code: u8) -> Option<DataType> {
+        match code {
+            0x08 => Some(DataType::U8),
+            0x09 => Some(DataType::I8),
+            0x0b => Some(DataType::I16),
+            0x0c => Some(DataType::I32),
+            0x0d => Some(DataType::F32),
+            0x0e => Some(DataType::F64),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            DataType::U8 => 0x08,
+            DataType::I8 => 0x09,
+            DataType::I16 => 0x0b,
+            DataType::I32 => 0x0c,
+            DataType::F32 => 0x0d,
+            DataType::F64 => 0x0e,
+        }
+    }
+
+    /// Size of a value in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            DataType::U8 | DataType::I8 => 1,
+            DataType::I16 => 2,
+            DataType::I32 | DataType::F32 => 4,
+            DataType::F64 => 8,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        self != DataType::F32 && self != DataType::F64
+    }
+
+    /// The largest value of integer types, which inputs are divided by to fit in `[-1, 1]`; 1 for the others.
+    pub fn scale(self) -> f64 {
+        match self {
+            DataType::U8 => u8::max_value() as f64,
+            DataType::I8 => i8::max_value() as f64,
+            DataType::I16 => i16::max_value() as f64,
+            DataType::I32 => i32::max_value() as f64,
+            DataType::F32 | DataType::F64 => 1.0,
+        }
+    }
+
+    /// The value encoded in the `size()` big endian `bytes`.
+    pub fn decode(self, bytes: &[u8]) -> f64 {
+        let bits = bytes.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        match self {
+            DataType::U8 => bits as u8 as f64,
+            DataType::I8 => bits as u8 as i8 as f64,
+            DataType::I16 => bits as u16 as i16 as f64,
+            DataType::I32 => bits as u32 as i32 as f64,
+            DataType::F32 => unsafe { mem::transmute::<u32, f32>(bits as u32) as f64 },
+            DataType::F64 => unsafe { mem::transmute::<u64, f64>(bits) },
+        }
+    }
+
+    /// Appends `x` to `buf`, rounded and saturated if the type is an integer one.
+    pub fn encode(self, x: f64, buf: &mut Vec<u8>) {
+        let clamp = |min: f64, max: f64| if x < min { min } else if x > max { max } else { x.round() };
+        let bits = match self {
+            DataType::U8 => clamp(0.0, u8::max_value() as f64) as u64,
+            DataType::I8 => clamp(i8::min_value() as f64, i8::max_value() as f64) as i8 as u8 as u64,
+            DataType::I16 => clamp(i16::min_value() as f64, i16::max_value() as f64) as i16 as u16 as u64,
+            DataType::I32 => clamp(i32::min_value() as f64, i32::max_value() as f64) as i32 as u32 as u64,
+            DataType::F32 => unsafe { mem::transmute::<f32, u32>(x as f32) as u64 },
+            DataType::F64 => unsafe { mem::transmute::<f64, u64>(x) },
+        };
+        let size = self.size();
+        for i in 0..size {
+            buf.push((bits >> (8 * (size - 1 - i))) as u8);
+        }
+    }
+}
+
+/// The header of an IDX file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxHeader {
+    pub data_type: DataType,
+    /// Size of every dimension, the first one being the number of items.
+    pub dims: Vec<usize>,
+}
+
+impl IdxHeader {
+    /// Decodes the header at the start of `bytes`, read from `path`.
+    pub fn parse<P: AsRef<Path>>(bytes: &[u8], path: P) -> Result<IdxHeader> {
+        let path = path.as_ref();
+        if bytes.len() < 4 {
+            return Err(Error::format(path, "unexpected end of header"));
+        }
+        if bytes[0] != 0 || bytes[1] != 0 {
+            return Err(Error::format(path, format!("invalid magic number {:#04x}{:02x}{:02x}{:02x}",
+                                                   bytes[0], bytes[1], bytes[2], bytes[3])));
+        }
+        let data_type = DataType::from_code(bytes[2])
+            .ok_or_else(|| Error::format(path, format!("unknown data type {:#x}", bytes[2])))?;
+        if bytes[3] == 0 {
+            return Err(Error::format(path, "no dimensions"));
+        }
+
+        let header = IdxHeader { data_type: data_type, dims: vec![0; bytes[3] as usize] };
+        if bytes.len() < header.size() {
+            return Err(Error::format(path, "unexpected end of header"));
+        }
+        let dims: Vec<usize> = bytes[4..header.size()].chunks(4)
+            .map(|d| d.iter().fold(0, |acc, &b| acc << 8 | b as usize))
+            .collect();
+        // so that the number of values and bytes of the file can be computed
+        let size = dims.iter().fold(Some(data_type.size()), |acc, &d| acc.and_then(|acc| acc.checked_mul(d)));
+        if size.is_none() {
+            return Err(Error::format(path, format!("dimensions {:?} are too large", dims)));
+        }
+        Ok(IdxHeader { dims: dims, ..header })
+    }
+
+    /// Size of the header in bytes.
+    pub fn size(&self) -> usize {
+        4 + 4 * self.dims.len()
+    }
+
+    /// Number of values in the file.
+    pub fn len(&self) -> usize {
+        self.dims.iter().fold(1, |acc, &d| acc * d)
+    }
+
+    /// Number of values of every item.
+    pub fn item_len(&self) -> usize {
+        self.dims[1..].iter().fold(1, |acc, &d| acc * d)
+    }
+}
+
+/// The contents of an IDX file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdxArray {
+    pub data_type: DataType,
+    /// Size of every dimension, the first one being the number of items.
+    pub dims: Vec<usize>,
+    /// The values in row-major order.
+    pub data: Vec<f64>,
+}
+
+impl IdxArray {
+    /// An array of `dims` holding `data`, whose length has to be the product of `dims`.
+    pub fn new(data_type: DataType, dims: Vec<usize>, data: Vec<f64>) -> Result<IdxArray> {
+        if dims.is_empty() || dims.len() > u8::max_value() as usize {
+            return Err(Error::argument("IDX dimensions", format!("{} dimensions", dims.len())));
+        }
+        let len = dims.iter().fold(1, |acc, &d| acc * d);
+        if data.len() != len {
+            return Err(Error::shape(format!("{:?} IDX array", dims), len, data.len()));
+        }
+        Ok(IdxArray { data_type: data_type, dims: dims, data: data })
+    }
+
+    /// An array of `rows.len()` items of shape `item_dims`.
+    pub fn from_rows(data_type: DataType, item_dims: &[usize], rows: &[Vec<f64>]) -> Result<IdxArray> {
+        let mut dims = vec![rows.len()];
+        dims.extend_from_slice(item_dims);
+        IdxArray::new(data_type, dims, rows.iter().flat_map(|row| row.iter().cloned()).collect())
+    }
+
+    /// Decodes the IDX file `bytes` read from `path`.
+    pub fn parse<P: AsRef<Path>>(bytes: &[u8], path: P) -> Result<IdxArray> {
+        let path = path.as_ref();
+        let header = IdxHeader::parse(bytes, path)?;
+        let body = &bytes[header.size()..];
+        let data_type = header.data_type;
+        if body.len() != header.len() * data_type.size() {
+            return Err(Error::format(path, format!("header declares {:?} values ({} bytes), file contains {} bytes",
+                                                   header.dims, header.len() * data_type.size(), body.len())));
+        }
+        let data = body.chunks(data_type.size()).map(|x| data_type.decode(x)).collect();
+        IdxArray::new(data_type, header.dims, data)
+    }
+
+    /// Reads the IDX file at `path`, gunzipping it if it is gzipped.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<IdxArray> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        File::open(path).and_then(|mut f| f.read_to_end(&mut bytes)).map_err(|e| Error::io(path, e))?;
+        if bytes.starts_with(&gzip::MAGIC) {
+            bytes = gzip::decompress(&bytes, path)?;
+        }
+        IdxArray::parse(&bytes, path)
+    }
+
+    /// The IDX file of the array.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0, 0, self.data_type.code(), self.dims.len() as u8];
+        for &d in &self.dims {
+            bytes.extend_from_slice(&[(d >> 24) as u8, (d >> 16) as u8, (d >> 8) as u8, d as u8]);
+        }
+        for &x in &self.data {
+            self.data_type.encode(x, &mut bytes);
+        }
+        bytes
+    }
+
+    /// Writes the array to `path`, gzipped if its extension is `gz`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let bytes = self.to_bytes();
+        if path.extension().map(|e| e == "gz").unwrap_or(false) {
+            return gzip::write_file(path, &bytes);
+        }
+        File::create(path).and_then(|mut f| f.write_all(&bytes)).map_err(|e| Error::io(path, e))
+    }
+
+    /// Number of items, the size of the first dimension.
+    pub fn len(&self) -> usize {
+        self.dims[0]
+    }
+
+    /// Number of values of every item.
+    pub fn item_len(&self) -> usize {
+        self.dims[1..].iter().fold(1, |acc, &d| acc * d)
+    }
+
+    /// The values of every item.
+    pub fn items(&self) -> Vec<&[f64]> {
+        if self.item_len() == 0 {
+            return vec![&self.data[..0]; self.len()];
+        }
+        self.data.chunks(self.item_len()).collect()
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    for &data_type in &[DataType::U8, DataType::I8, DataType::I16, DataType::I32, DataType::F32, DataType::F64] {
+        let array = IdxArray::from_rows(data_type, &[2, 2], &[
+            vec![0.0, 1.0, 2.0, 100.0],
+            vec![-1.0, -2.0, 3.0, 4.0],
+        ]).unwrap();
+        let parsed = IdxArray::parse(&array.to_bytes(), "<memory>").unwrap();
+        assert_eq!(parsed.dims, vec![2, 2, 2]);
+        assert_eq!(parsed.items().len(), 2);
+        if data_type == DataType::U8 {
+            assert_eq!(parsed.data[4], 0.0);
+        } else {
+            assert_eq!(parsed, array);
+        }
+    }
+    assert!(IdxArray::parse(&[0, 0, 8, 1, 0, 0, 0, 2, 7], "<memory>").is_err());
+}
+
+#[test]
+fn test_overflowing_header() {
+    let bytes = [0, 0, 0x08, 3, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+    assert!(IdxArray::parse(&bytes, "<memory>").is_err());
+}
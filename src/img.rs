@@ -1,14 +1,17 @@
 use std::path::Path;
 use std::fs::File;
 use image;
+use preprocess::Pipeline;
+use error::{Error, Result};
 
-pub fn get_pixels<P: AsRef<Path>>(p: P) -> Vec<f64> {
-    image::open(p).unwrap().to_luma().into_raw().into_iter()
-        .map(|byte| 1.0 - (byte as f64 / u8::max_value() as f64)).collect()
+/// Reads the image at `p` with the default preprocessing: its luma, dark being high values.
+pub fn get_pixels<P: AsRef<Path>>(p: P) -> Result<Vec<f64>> {
+    Pipeline::default().load(p)
 }
 
-pub fn save<P: AsRef<Path>>(v: &[f64], w: u32, h: u32, p: P) {
-    let ref mut fout = File::create(p).unwrap();
+/// Renders `v` as a `w`x`h` image, grayscale or RGB if it has three interleaved values per pixel, stretching its
+/// values to the full range of brightness, high values being dark.
+pub fn render(v: &[f64], w: u32, h: u32) -> Result<image::DynamicImage> {
     let min = {
         let mut m = 1.0 / 0.0;
         for x in v {
@@ -25,18 +28,134 @@ pub fn save<P: AsRef<Path>>(v: &[f64], w: u32, h: u32, p: P) {
     };
     let delta = max - min;
 
-    image::DynamicImage::ImageLuma8(image::GrayImage::from_raw(w, h, v.iter()
+    let pixels: Vec<u8> = v.iter()
         .map(|&f| {
             let f = (f - min) / delta;
             let f = if f < 0.0 { 0.0 } else if f > 1.0 { 1.0 } else { f };
             let x = ((1.0 - f) * 255.0) as i32;
             let x = if x > 255 { 255 } else if x < 0 { 0 } else { x };
             x as u8
-        }).collect()).unwrap()).save(fout, image::PNG).unwrap();
+        }).collect();
+
+    if v.len() == 3 * (w * h) as usize {
+        let buf = image::RgbImage::from_raw(w, h, pixels)
+            .ok_or_else(|| Error::shape(format!("{}x{} image", w, h), 3 * (w * h) as usize, v.len()))?;
+        return Ok(image::DynamicImage::ImageRgb8(buf));
+    }
+    let buf = image::GrayImage::from_raw(w, h, pixels)
+        .ok_or_else(|| Error::shape(format!("{}x{} image", w, h), (w * h) as usize, v.len()))?;
+
+    Ok(image::DynamicImage::ImageLuma8(buf))
+}
+
+pub fn save<P: AsRef<Path>>(v: &[f64], w: u32, h: u32, p: P) -> Result<()> {
+    let p = p.as_ref();
+    let image = render(v, w, h)?;
+    let ref mut fout = File::create(p).map_err(|e| Error::io(p, e))?;
+    image.save(fout, image::PNG).map_err(|e| Error::image(p, e))
+}
+
+/// Renders `v` like `save` does and returns the PNG file contents.
+pub fn png(v: &[f64], w: u32, h: u32) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    render(v, w, h)?.save(&mut png, image::PNG).map_err(|e| Error::image("<memory>", e))?;
+    Ok(png)
+}
+
+/// Renders `v` like `save` does and returns it as a `data:` URI of a PNG, ready to be embedded in HTML.
+pub fn png_data_uri(v: &[f64], w: u32, h: u32) -> Result<String> {
+    Ok(format!("data:image/png;base64,{}", base64(&png(v, w, h)?)))
+}
+
+/// Lays the `w`x`h` `images` out in rows of `columns`, one pixel apart, each stretched to `[0, 1]` on its own.
+///
+/// Returns the pixels of the tiled image, its width and its height.
+pub fn tile(images: &[Vec<f64>], w: u32, h: u32, columns: usize) -> (Vec<f64>, u32, u32) {
+    let rows = (images.len() + columns - 1) / columns;
+    let (tile_w, tile_h) = (w as usize + 1, h as usize + 1);
+    let (width, height) = (columns * tile_w - 1, rows * tile_h - 1);
+    let mut pixels = vec![0.0; width * height];
+    for (i, image) in images.iter().enumerate() {
+        let min = image.iter().cloned().fold(1.0 / 0.0, f64::min);
+        let max = image.iter().cloned().fold(-1.0 / 0.0, f64::max);
+        let delta = if max > min { max - min } else { 1.0 };
+        let (left, top) = (i % columns * tile_w, i / columns * tile_h);
+        for (j, &x) in image.iter().enumerate() {
+            let (px, py) = (left + j % w as usize, top + j / w as usize);
+            pixels[py * width + px] = (x - min) / delta;
+        }
+    }
+    (pixels, width as u32, height as u32)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &'static [u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(ALPHABET[(n >> (18 - 6 * i)) & 63] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+#[test]
+fn test_render_rgb() {
+    match render(&[0.0, 0.5, 1.0, 1.0, 0.5, 0.0], 2, 1).unwrap() {
+        image::DynamicImage::ImageRgb8(ref buf) => assert_eq!(buf.dimensions(), (2, 1)),
+        _ => panic!("expected an RGB image"),
+    }
+    match render(&[0.0, 0.5], 2, 1).unwrap() {
+        image::DynamicImage::ImageLuma8(ref buf) => assert_eq!(buf.dimensions(), (2, 1)),
+        _ => panic!("expected a grayscale image"),
+    }
+    assert!(render(&[0.0, 0.5, 1.0], 2, 1).is_err());
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+}
+
+/// Width and height of the image at `p`.
+pub fn dimensions<P: AsRef<Path>>(p: P) -> Result<(u32, u32)> {
+    use image::GenericImage;
+    let p = p.as_ref();
+    image::open(p).map(|img| img.dimensions()).map_err(|e| Error::image(p, e))
 }
 
-pub fn get_img_and_label<P: AsRef<Path>>(p: P) -> (Vec<f64>, String) {
-    let image = get_pixels(&p);
-    let label = p.as_ref().file_name().unwrap().to_str().unwrap().split("_").next().unwrap();
-    (image, String::from(label))
-}
\ No newline at end of file
+/// Returns the part of the file name before the first `_`.
+pub fn label_of<P: AsRef<Path>>(p: P) -> Result<String> {
+    let p = p.as_ref();
+    let name = p.file_name().and_then(|n| n.to_str())
+        .ok_or_else(|| Error::format(p, "file name is not valid UTF-8"))?;
+    Ok(name.split("_").next().unwrap_or(name).into())
+}
+
+/// Returns the middle field of a file named `LABEL_AUTHOR_N`, `None` if the name has fewer than three fields.
+pub fn author_of<P: AsRef<Path>>(p: P) -> Option<String> {
+    let stem = match p.as_ref().file_stem().and_then(|n| n.to_str()) {
+        Some(stem) => stem,
+        None => return None,
+    };
+    let fields: Vec<&str> = stem.split("_").collect();
+    if fields.len() < 3 {
+        return None;
+    }
+    Some(fields[1..fields.len() - 1].join("_"))
+}
+
+/// Returns the field `n` of a file named `FIELD_FIELD_...`, counted from 0, `None` if the name has no such field.
+pub fn field_of<P: AsRef<Path>>(p: P, n: usize) -> Option<String> {
+    p.as_ref().file_stem().and_then(|s| s.to_str()).and_then(|stem| stem.split("_").nth(n)).map(|f| f.to_string())
+}
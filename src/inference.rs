@@ -0,0 +1,210 @@
+//! Running trained nets.
+
+use std::cmp::Ordering;
+use std::path::Path;
+use std::ops::Deref;
+use rayon::prelude::*;
+use multilayer_perceptron::{NetFile, Task};
+use rejection::RejectRule;
+use error::{Error, Result};
+
+/// Index of the largest value in `output`, `None` if it's empty.
+pub fn argmax(output: &[f64]) -> Option<usize> {
+    output.iter().enumerate()
+        .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(Ordering::Equal))
+        .map(|(i, _)| i)
+}
+
+/// Labels with their scores, the most probable first.
+pub type Ranking = Vec<(String, f64)>;
+
+/// The answer of a classifier with a reject rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decision {
+    /// The best label and its score.
+    Label(String, f64),
+    /// The classifier wasn't sure enough of any label.
+    Unknown,
+}
+
+/// A trained net together with the labels of its output neurons.
+#[derive(Clone, Debug)]
+pub struct Classifier {
+    net: NetFile,
+}
+
+impl Classifier {
+    pub fn new(net: NetFile) -> Classifier {
+        Classifier { net: net }
+    }
+
+    /// Loads the classifier in `path`, which fails for nets doing another task.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Classifier> {
+        let net = NetFile::load(&path)?;
+        if net.task != Task::Classification {
+            return Err(Error::net(path, format!("the net is a {}, not a classifier", net.task.name())));
+        }
+        Ok(Classifier::new(net))
+    }
+
+    pub fn net(&self) -> &NetFile {
+        &self.net
+    }
+
+    pub fn into_net(self) -> NetFile {
+        self.net
+    }
+
+    pub fn set_temperature(&mut self, temperature: f64) {
+        self.net.temperature = temperature;
+    }
+
+    /// Sets the rule by which `decide` rejects inputs, `None` to accept all of them.
+    pub fn set_reject_rule(&mut self, rule: Option<RejectRule>) {
+        self.net.reject = rule;
+    }
+
+    pub fn reject_rule(&self) -> Option<&RejectRule> {
+        self.net.reject.as_ref()
+    }
+
+    /// Length of the inputs the classifier accepts.
+    pub fn num_inputs(&self) -> usize {
+        self.net.num_inputs()
+    }
+
+    /// Labels of the output neurons, in order.
+    pub fn labels(&self) -> Vec<&str> {
+        (0..self.net.labels.len()).map(|i| self.net.labels[&i].as_str()).collect()
+    }
+
+    /// Raw activations of the output neurons.
+    pub fn outputs(&self, input: &[f64]) -> Result<Vec<f64>> {
+        if input.len() != self.num_inputs() {
+            return Err(Error::shape("classifier input", self.num_inputs(), input.len()));
+        }
+        Ok(self.net.net.feed_forward(input).0.at)
+    }
+
+    /// Outputs for `input` divided by the temperature of the net.
+    pub fn logits(&self, input: &[f64]) -> Result<Vec<f64>> {
+        let temperature = self.net.temperature;
+        self.outputs(input).map(|out| out.into_iter().map(|x| x / temperature).collect())
+    }
+
+    /// Ranks all the labels by the softmax of the logits for `input`.
+    ///
+    /// The scores are probabilities only once the net has been calibrated, see `calibration::fit_temperature`.
+    pub fn classify(&self, input: &[f64]) -> Result<Ranking> {
+        let scores = softmax(&self.logits(input)?);
+        let mut ranking: Ranking = scores.into_iter().enumerate()
+            .map(|(i, score)| (self.net.labels[&i].clone(), score))
+            .collect();
+        ranking.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        Ok(ranking)
+    }
+
+    /// The `k` best labels for `input`.
+    pub fn top_k(&self, input: &[f64], k: usize) -> Result<Ranking> {
+        let mut ranking = self.classify(input)?;
+        ranking.truncate(k);
+        Ok(ranking)
+    }
+
+    /// The best label for `input` and its score.
+    pub fn best(&self, input: &[f64]) -> Result<(String, f64)> {
+        self.classify(input).map(|ranking| ranking.into_iter().next().unwrap())
+    }
+
+    /// Like `best`, but `Unknown` if the reject rule of the net doesn't accept the ranking.
+    pub fn decide(&self, input: &[f64]) -> Result<Decision> {
+        let ranking = self.classify(input)?;
+        Ok(self.decision(ranking))
+    }
+
+    /// Applies the reject rule of the net to `ranking`.
+    pub fn decision(&self, ranking: Ranking) -> Decision {
+        match self.net.reject {
+            Some(ref rule) if !rule.accepts(&ranking) => Decision::Unknown,
+            _ => ranking.into_iter().next().map(|(label, score)| Decision::Label(label, score)).unwrap_or(Decision::Unknown),
+        }
+    }
+
+    pub fn classify_batch<I>(&self, inputs: &[I]) -> Result<Vec<Ranking>> where I: Deref<Target = [f64]> {
+        inputs.iter().map(|input| self.classify(input)).collect()
+    }
+
+    /// Like `classify_batch`, but runs on all the available threads.
+    pub fn par_classify_batch<I>(&self, inputs: &[I]) -> Result<Vec<Ranking>> where I: Deref<Target = [f64]> + Sync {
+        let mut rankings = Vec::with_capacity(inputs.len());
+        inputs.par_iter().map(|input| self.classify(input)).collect_into(&mut rankings);
+        rankings.into_iter().collect()
+    }
+}
+
+/// Exponentiates and normalizes `xs` so that they sum up to 1.
+pub fn softmax(xs: &[f64]) -> Vec<f64> {
+    let max = xs.iter().cloned().fold(::std::f64::NEG_INFINITY, f64::max);
+    let exps: Vec<f64> = xs.iter().map(|&x| (x - max).exp()).collect();
+    let sum: f64 = exps.iter().sum();
+    exps.into_iter().map(|x| x / sum).collect()
+}
+
+#[test]
+fn test_softmax() {
+    let s = softmax(&[1.0, 2.0, 3.0]);
+    assert!((s.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    assert!(s[0] < s[1] && s[1] < s[2]);
+}
+
+#[test]
+fn test_classify_checks_input_length() {
+    use multilayer_perceptron::MultilayerPerceptron;
+    use activation_func::Tanh;
+    let perc = MultilayerPerceptron::new(0.1, 4, &[(3, Tanh(1.0).into()), (2, Tanh(1.0).into())]);
+    let labels = vec![(0, "a".to_string()), (1, "b".to_string())].into_iter().collect();
+    let classifier = Classifier::new(NetFile::new(perc, labels));
+
+    assert_eq!(classifier.classify(&[0.0; 4]).unwrap().len(), 2);
+    match classifier.classify(&[0.0; 5]) {
+        Err(Error::Shape { expected: 4, actual: 5, .. }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_decide() {
+    use multilayer_perceptron::MultilayerPerceptron;
+    use activation_func::Tanh;
+    let perc = MultilayerPerceptron::new(0.1, 4, &[(3, Tanh(1.0).into()), (2, Tanh(1.0).into())]);
+    let labels = vec![(0, "a".to_string()), (1, "b".to_string())].into_iter().collect();
+    let mut classifier = Classifier::new(NetFile::new(perc, labels));
+
+    match classifier.decide(&[0.0; 4]).unwrap() {
+        Decision::Label(..) => (),
+        Decision::Unknown => panic!("rejected without a reject rule"),
+    }
+    // with two labels the best score is always below 1
+    classifier.set_reject_rule(Some(RejectRule::Confidence(1.0)));
+    assert_eq!(classifier.decide(&[0.0; 4]).unwrap(), Decision::Unknown);
+}
+
+#[test]
+fn test_load_refuses_nets_without_labels() {
+    use std::{env, fs};
+    use std::collections::HashMap;
+    use multilayer_perceptron::MultilayerPerceptron;
+    use activation_func::Tanh;
+    let perc = MultilayerPerceptron::new(0.1, 4, &[(3, Tanh(1.0).into()), (4, Tanh(1.0).into())]);
+    let path = env::temp_dir().join("mulperc-test-unlabelled.net");
+
+    NetFile::new(perc.clone(), HashMap::new()).save(&path).unwrap();
+    match Classifier::load(&path) {
+        Err(Error::Net { .. }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    NetFile::autoencoder(perc).save(&path).unwrap();
+    assert_eq!(NetFile::load(&path).unwrap().task, Task::Autoencoder);
+    assert!(Classifier::load(&path).is_err());
+    let _ = fs::remove_file(&path);
+}
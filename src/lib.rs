@@ -0,0 +1,83 @@
+//! Multilayer perceptron based image classifier.
+//!
+//! The crate is split into:
+//!
+//! * the model: [`MultilayerPerceptron`](multilayer_perceptron/struct.MultilayerPerceptron.html) and its
+//!   [activation functions](activation_func/index.html),
+//! * the [trainer](trainer/index.html), which learns classifiers, multi-label classifiers, regressors and
+//!   autoencoders on optionally [augmented](augment/index.html) and [balanced](balance/index.html) examples, and
+//!   the [hyperparameter search](search/index.html) tuning them,
+//! * [datasets](dataset/index.html), read lazily: directories of labelled images, CSV and LibSVM files and the
+//!   MNIST digits in [IDX files](idx/index.html), optionally through binary caches,
+//! * IO: [`NetFile`](multilayer_perceptron/struct.NetFile.html) for saving and loading trained nets with the
+//!   [preprocessing](preprocess/index.html) of their inputs, and [`img`](img/index.html) for reading and writing
+//!   images,
+//! * [run directories](run/index.html) recording learning runs, optionally with
+//!   [TensorBoard event files](tensorboard/index.html),
+//! * [inference](inference/index.html) with trained nets, optionally [rejecting](rejection/index.html) uncertain
+//!   inputs, and its [evaluation](metrics/index.html), the [regressors](regression/index.html) predicting
+//!   real-valued targets and the [multi-label classifiers](multilabel/index.html) predicting sets of labels, with
+//!   their evaluations.
+//!
+//! ```no_run
+//! use mulperc::{dataset, NetFile, Trainer, TrainConfig};
+//!
+//! let (examples, _skipped) = dataset::load("res/Sieci Neuronowe", false).unwrap();
+//! let trainer = Trainer::new(TrainConfig::default());
+//! let mut net = trainer.new_net(&examples).unwrap();
+//! trainer.train(&mut net, &examples, |_, _| Ok(())).unwrap();
+//! net.save("digits.net").unwrap();
+//!
+//! let classifier = mulperc::Classifier::new(net);
+//! let (label, score) = classifier.best(&examples[0].0).unwrap();
+//! ```
+
+#![feature(iter_max_by)]
+#![feature(proc_macro)]
+#![feature(conservative_impl_trait)]
+
+extern crate rand;
+extern crate rayon;
+extern crate nalgebra as na;
+
+#[macro_use] extern crate serde_derive;
+extern crate serde;
+extern crate bincode;
+extern crate serde_json;
+
+extern crate image;
+extern crate regex;
+#[cfg(feature = "mmap")] extern crate memmap;
+
+#[macro_use] pub mod util;
+pub mod error;
+pub mod activation_func;
+pub mod multilayer_perceptron;
+pub mod img;
+pub mod preprocess;
+pub mod gzip;
+pub mod idx;
+pub mod mnist;
+pub mod dataset;
+pub mod split;
+pub mod augment;
+pub mod balance;
+pub mod trainer;
+pub mod autoencoder;
+pub mod search;
+pub mod run;
+pub mod tensorboard;
+pub mod inference;
+pub mod metrics;
+pub mod regression;
+pub mod multilabel;
+pub mod calibration;
+pub mod rejection;
+mod map_in_place;
+
+pub use error::{Error, Result};
+pub use multilayer_perceptron::{MultilayerPerceptron, NetFile};
+pub use trainer::{Trainer, TrainConfig};
+pub use inference::Classifier;
+pub use regression::Regressor;
+pub use multilabel::MultiLabelClassifier;
@@ -1,47 +1,67 @@
-#![feature(iter_max_by)]
 #![feature(proc_macro)]
-#![feature(conservative_impl_trait)]
 
-extern crate rand;
-extern crate rayon;
-extern crate nalgebra as na;
+extern crate mulperc;
 extern crate clap;
+extern crate rand;
 extern crate pbr;
-extern crate nfd;
-
 #[macro_use] extern crate serde_derive;
 extern crate serde;
-extern crate bincode;
-
-#[macro_use] extern crate conrod;
+extern crate serde_json;
 extern crate image;
-extern crate find_folder;
-
-#[macro_use] mod util;
-mod multilayer_perceptron;
-mod activation_func;
-mod img;
-mod validators;
-mod args;
-mod window;
-mod window_gui;
-mod mnist;
-mod gzip;
-mod autoencoder;
-mod classifier;
-mod map_in_place;
+extern crate rusttype;
 
-fn main() {
+#[cfg(feature = "gui")] #[macro_use] extern crate conrod;
+#[cfg(feature = "gui")] extern crate nfd;
+#[cfg(feature = "gui")] extern crate find_folder;
+
+mod cli;
+#[cfg(feature = "gui")]
+mod gui;
+
+use cli::{args, classifier, autoencoder, predict, crossval, search, plot, split};
+use mulperc::error;
+
+#[cfg(feature = "gui")]
+fn window_loop() -> error::Result<()> {
+    gui::window_loop()
+}
+
+#[cfg(not(feature = "gui"))]
+fn window_loop() -> error::Result<()> {
+    Err(error::Error::Unsupported { what: "the gui (enable the `gui` feature)".into() })
+}
+
+fn run() -> error::Result<()> {
     let matches = args::get();
     if let Some(matches) = matches.subcommand_matches("learn") {
-        classifier::learn(matches);
+        classifier::learn(matches)
     } else if let Some(matches) = matches.subcommand_matches("check") {
-        classifier::check(matches);
+        classifier::check(matches)
+    } else if let Some(matches) = matches.subcommand_matches("calibrate") {
+        classifier::calibrate(matches)
+    } else if let Some(matches) = matches.subcommand_matches("crossval") {
+        crossval::crossval(matches)
+    } else if let Some(matches) = matches.subcommand_matches("split") {
+        split::split(matches)
+    } else if let Some(matches) = matches.subcommand_matches("search") {
+        search::search(matches)
+    } else if let Some(matches) = matches.subcommand_matches("plot") {
+        plot::plot(matches)
+    } else if let Some(matches) = matches.subcommand_matches("predict") {
+        predict::predict(matches)
     } else if let Some(_) = matches.subcommand_matches("gui") {
-        window::window_loop();
+        window_loop()
     } else if let Some(matches) = matches.subcommand_matches("autoencoder") {
-        autoencoder::run(matches).unwrap();
+        autoencoder::run(matches)
     } else {
-        window::window_loop();
+        window_loop()
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        use std::io::Write;
+        let _ = writeln!(std::io::stderr(), "error: {}", e);
+        std::process::exit(1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,273 @@
+//! Evaluation of classifiers, see `regression` and `multilabel` for the ones of regressors and multi-label
+//! classifiers.
+
+use std::collections::{BTreeSet, HashMap};
+use inference::{argmax, Classifier, Ranking};
+use dataset::{self, Dataset};
+use multilayer_perceptron::NetFile;
+use calibration::Calibration;
+use rejection::{self, CoveragePoint, Measure, RejectRule};
+use error::{Error, Result};
+
+/// The true label of an example together with the classifier's ranking for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub truth: String,
+    pub ranking: Ranking,
+}
+
+impl Outcome {
+    /// The best label.
+    pub fn predicted(&self) -> &str {
+        &self.ranking[0].0
+    }
+
+    /// Score of the best label.
+    pub fn confidence(&self) -> f64 {
+        self.ranking[0].1
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.predicted() == self.truth
+    }
+
+    /// Whether the true label is among the `k` best ones.
+    pub fn in_top_k(&self, k: usize) -> bool {
+        self.ranking.iter().take(k).any(|&(ref label, _)| *label == self.truth)
+    }
+}
+
+/// Number of examples read into memory at once during the evaluation.
+const CHUNK_SIZE: usize = 1024;
+
+/// Indices of the examples of `data` in consecutive chunks of at most `CHUNK_SIZE`.
+pub fn chunks<D: Dataset + ?Sized>(data: &D) -> Vec<Vec<usize>> {
+    let indices: Vec<usize> = (0..data.len()).collect();
+    indices.chunks(CHUNK_SIZE).map(|chunk| chunk.to_vec()).collect()
+}
+
+/// Classifies all the examples of `data`, a chunk at a time.
+pub fn outcomes<D: Dataset + ?Sized>(classifier: &Classifier, data: &D) -> Result<Vec<Outcome>> {
+    let mut outcomes = Vec::with_capacity(data.len());
+    for chunk in chunks(data) {
+        let examples = dataset::load_batch(data, &chunk)?;
+        let inputs: Vec<&[f64]> = examples.iter().map(|&(ref input, _)| &input[..]).collect();
+        let rankings = classifier.par_classify_batch(&inputs)?;
+        outcomes.extend(rankings.into_iter().zip(examples).map(|(ranking, (_, truth))| Outcome {
+            truth: truth,
+            ranking: ranking,
+        }));
+    }
+    Ok(outcomes)
+}
+
+/// Fraction of the examples of `data` whose label has the highest output of `net`.
+pub fn accuracy<D: Dataset + ?Sized>(net: &NetFile, data: &D) -> Result<f64> {
+    if data.input_len() != net.num_inputs() {
+        return Err(Error::shape("evaluation set", net.num_inputs(), data.input_len()));
+    }
+    let mut correct = 0;
+    for chunk in chunks(data) {
+        let examples = dataset::load_batch(data, &chunk)?;
+        correct += examples.iter().filter(|&&(ref input, ref label)| {
+            argmax(&net.net.feed_forward(input).0.at).map(|i| net.labels[&i] == *label).unwrap_or(false)
+        }).count();
+    }
+    Ok(ratio(correct, data.len()))
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConfusionMatrix {
+    pub labels: Vec<String>,
+    /// `counts[t][p]` is the number of examples labelled `labels[t]` that were classified as `labels[p]`.
+    pub counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(outcomes: &[Outcome]) -> ConfusionMatrix {
+        let labels: BTreeSet<&str> = outcomes.iter()
+            .flat_map(|o| vec![o.truth.as_str(), o.predicted()])
+            .collect();
+        let labels: Vec<String> = labels.into_iter().map(|l| l.to_string()).collect();
+        let index: HashMap<&str, usize> = labels.iter().enumerate().map(|(i, l)| (l.as_str(), i)).collect();
+
+        let mut counts = vec![vec![0; labels.len()]; labels.len()];
+        for o in outcomes {
+            counts[index[o.truth.as_str()]][index[o.predicted()]] += 1;
+        }
+
+        ConfusionMatrix { labels: labels, counts: counts }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ClassMetrics {
+    pub label: String,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Number of examples with this label.
+    pub support: usize,
+}
+
+impl ClassMetrics {
+    /// Metrics of `label` from the number of examples correctly classified with it, classified with it and having it.
+    pub fn new(label: &str, correct: usize, predicted: usize, support: usize) -> ClassMetrics {
+        let precision = ratio(correct, predicted);
+        let recall = ratio(correct, support);
+        ClassMetrics {
+            label: label.into(),
+            precision: precision,
+            recall: recall,
+            f1: if precision + recall == 0.0 { 0.0 } else { 2.0 * precision * recall / (precision + recall) },
+            support: support,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Averages {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TopKAccuracy {
+    pub k: usize,
+    pub accuracy: f64,
+}
+
+/// Summary of the outcomes of a classifier on a labelled dataset.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    pub total: usize,
+    pub correct: usize,
+    pub accuracy: f64,
+    pub top_k: Vec<TopKAccuracy>,
+    pub per_class: Vec<ClassMetrics>,
+    /// Unweighted mean of the per-class metrics.
+    pub macro_avg: Averages,
+    /// Mean of the per-class metrics weighted by their support.
+    pub weighted_avg: Averages,
+    pub confusion: ConfusionMatrix,
+    pub calibration: Calibration,
+    /// Accuracy of the classifier if it rejected all but the most certain outcomes.
+    pub coverage_curve: Vec<CoveragePoint>,
+    /// Coverage and accuracy under the reject rule of the classifier, if it has one.
+    pub rejection: Option<CoveragePoint>,
+}
+
+/// Number of bins of the reliability diagram in evaluations.
+pub const RELIABILITY_BINS: usize = 10;
+
+/// Number of points of the accuracy vs coverage curve in evaluations.
+pub const COVERAGE_POINTS: usize = 10;
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 { 0.0 } else { a as f64 / b as f64 }
+}
+
+fn average<W: Fn(&ClassMetrics) -> f64>(per_class: &[ClassMetrics], weight: W) -> Averages {
+    let sum: f64 = per_class.iter().map(|c| weight(c)).sum();
+    if sum == 0.0 {
+        return Averages { precision: 0.0, recall: 0.0, f1: 0.0 };
+    }
+    Averages {
+        precision: per_class.iter().map(|c| weight(c) * c.precision).sum::<f64>() / sum,
+        recall: per_class.iter().map(|c| weight(c) * c.recall).sum::<f64>() / sum,
+        f1: per_class.iter().map(|c| weight(c) * c.f1).sum::<f64>() / sum,
+    }
+}
+
+impl Evaluation {
+    /// Evaluates `outcomes`, computing top-k accuracy for every k in `ks`.
+    ///
+    /// The coverage curve thresholds the measure of `reject`, or the confidence if there's no rule.
+    pub fn new(outcomes: &[Outcome], ks: &[usize], reject: Option<&RejectRule>) -> Evaluation {
+        let confusion = ConfusionMatrix::new(outcomes);
+        let n = confusion.labels.len();
+        let total = outcomes.len();
+        let correct = (0..n).map(|i| confusion.counts[i][i]).sum();
+
+        let per_class: Vec<ClassMetrics> = (0..n).map(|i| {
+            let support: usize = confusion.counts[i].iter().sum();
+            let predicted: usize = (0..n).map(|t| confusion.counts[t][i]).sum();
+            ClassMetrics::new(&confusion.labels[i], confusion.counts[i][i], predicted, support)
+        }).collect();
+
+        let macro_avg = average(&per_class, |_| 1.0);
+        let weighted_avg = average(&per_class, |c| c.support as f64);
+
+        let top_k = ks.iter().map(|&k| TopKAccuracy {
+            k: k,
+            accuracy: ratio(outcomes.iter().filter(|o| o.in_top_k(k)).count(), total),
+        }).collect();
+
+        Evaluation {
+            total: total,
+            correct: correct,
+            accuracy: ratio(correct, total),
+            top_k: top_k,
+            per_class: per_class,
+            macro_avg: macro_avg,
+            weighted_avg: weighted_avg,
+            confusion: confusion,
+            calibration: Calibration::new(outcomes, RELIABILITY_BINS),
+            coverage_curve: rejection::coverage_curve(outcomes, reject.map(|r| r.measure()).unwrap_or(Measure::Confidence),
+                                                      COVERAGE_POINTS),
+            rejection: reject.map(|r| rejection::apply(outcomes, r)),
+        }
+    }
+}
+
+/// Mean and standard deviation of `xs`, zeros if it's empty.
+pub fn mean_std(xs: &[f64]) -> (f64, f64) {
+    if xs.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+    let variance = xs.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / xs.len() as f64;
+    (mean, variance.sqrt())
+}
+
+#[test]
+fn test_accuracy_of_mismatched_dataset() {
+    use multilayer_perceptron::MultilayerPerceptron;
+    use activation_func::Tanh;
+    let perc = MultilayerPerceptron::new(0.1, 2, &[(3, Tanh(1.0).into()), (2, Tanh(1.0).into())]);
+    let net = NetFile::new(perc, vec![(0, "a".to_string()), (1, "b".to_string())].into_iter().collect());
+    let matching: Vec<(Vec<f64>, String)> = vec![(vec![0.0, 1.0], "a".into())];
+    let mismatched: Vec<(Vec<f64>, String)> = vec![(vec![0.0, 1.0, 2.0], "a".into())];
+    assert!(accuracy(&net, &matching).is_ok());
+    assert!(accuracy(&net, &mismatched).is_err());
+}
+
+#[cfg(test)]
+fn outcome(truth: &str, ranking: &[&str]) -> Outcome {
+    Outcome {
+        truth: truth.into(),
+        ranking: ranking.iter().enumerate().map(|(i, l)| (l.to_string(), 1.0 / (i + 1) as f64)).collect(),
+    }
+}
+
+#[test]
+fn test_evaluation() {
+    let outcomes = [
+        outcome("a", &["a", "b"]),
+        outcome("a", &["b", "a"]),
+        outcome("b", &["b", "a"]),
+        outcome("b", &["b", "a"]),
+    ];
+    let e = Evaluation::new(&outcomes, &[1, 2], None);
+
+    assert_eq!(e.correct, 3);
+    assert_eq!(e.confusion.labels, vec!["a", "b"]);
+    assert_eq!(e.confusion.counts, vec![vec![1, 1], vec![0, 2]]);
+    assert_eq!(e.per_class[0].precision, 1.0);
+    assert_eq!(e.per_class[0].recall, 0.5);
+    assert_eq!(e.per_class[1].precision, 2.0 / 3.0);
+    assert_eq!(e.per_class[1].support, 2);
+    assert_eq!(e.top_k[1], TopKAccuracy { k: 2, accuracy: 1.0 });
+    assert!((e.macro_avg.recall - 0.75).abs() < 1e-12);
+}
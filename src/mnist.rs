@@ -1,97 +1,75 @@
-use gzip::GzipData;
-use std::io::Read;
+use idx::IdxArray;
+use std::path::{Path, PathBuf};
+use error::{Error, Result};
 
+/// Width and height of every digit of the default sets.
+pub const SIZE: u32 = 28;
+
+/// Datasets in the format of MNIST: an IDX file of inputs and an IDX file of integer labels, like Fashion-MNIST or
+/// EMNIST.
 pub struct MnistDigits;
 
 impl MnistDigits {
 
-    fn from_high_endian(arr: &[u8]) -> u64 {
-        arr.iter().fold(0, |acc, val| acc * 256 + (*val as u64))
-    }
-
-    fn read_u32<T: Read>(src: &mut T) -> Result<u32, &'static str> {
-        let mut buf: [u8; 4] = [0; 4];
-
-        match src.read(&mut buf) {
-            Ok(4) => Ok(MnistDigits::from_high_endian(&buf) as u32),
-            _     => Err("Could not read data."),
-        }
-    }
-
-    fn read_labels(fname: &str) -> Result<Vec<u8>, &'static str> {
-
-        let mut data = GzipData::from_file(fname)?;
-
-        if MnistDigits::read_u32(&mut data)? != 8 * 256 + 1 {
-            return Err("Invalid magic number.");
+    /// Reads the integer labels in `fname`.
+    pub fn read_labels(fname: &Path) -> Result<Vec<String>> {
+        let labels = IdxArray::read(fname)?;
+        if labels.dims.len() != 1 {
+            return Err(Error::format(fname, format!("expected a vector of labels, got dimensions {:?}", labels.dims)));
         }
-
-        let n = MnistDigits::read_u32(&mut data)?;
-
-        let l: Vec<u8> = data.iter().cloned().collect();
-        if l.len() != n as usize {
-            return Err("Invalid number of items.");
+        if !labels.data_type.is_integer() {
+            return Err(Error::format(fname, format!("expected integer labels, got {:?}", labels.data_type)));
         }
 
-        if !l.iter().all(|&x| x <= 9) {
-            return Err("Found invalid values for labels.");
-        }
-
-        Ok(l)
+        Ok(labels.data.iter().map(|&label| (label as i64).to_string()).collect())
     }
 
-    fn read_examples(fname: &str) -> Result<Vec<Vec<f64>>, &'static str> {
-
-        let mut data = GzipData::from_file(fname)?;
-
-        if MnistDigits::read_u32(&mut data)? != 8 * 256 + 3 {
-            return Err("Invalid magic number.");
+    /// Reads the inputs in `fname`, every item being an input however many dimensions it has, scaled by
+    /// `DataType::scale`.
+    fn read_examples(fname: &Path) -> Result<Vec<Vec<f64>>> {
+        let examples = IdxArray::read(fname)?;
+        if examples.dims.len() < 2 {
+            return Err(Error::format(fname, format!("expected a list of inputs, got dimensions {:?}", examples.dims)));
         }
 
-        let n = MnistDigits::read_u32(&mut data)?;
+        let scale = examples.data_type.scale();
+        Ok(examples.items().into_iter().map(|item| item.iter().map(|&x| x / scale).collect()).collect())
+    }
 
-        let rows = MnistDigits::read_u32(&mut data)?;
-        let cols = MnistDigits::read_u32(&mut data)?;
-        if rows != 28 || cols != 28 {
-            return Err("Invalid number of rows or columns.");
-        }
+    pub fn from<P: AsRef<Path>, Q: AsRef<Path>>(vectors_fname: P, labels_fname: Q) -> Result<Vec<(Vec<f64>, String)>> {
+        let labels = MnistDigits::read_labels(labels_fname.as_ref())?;
+        let values = MnistDigits::read_examples(vectors_fname.as_ref())?;
 
-        let v = data.buf();
-        if v.len() != (n * 28 * 28) as usize {
-            return Err("Could not read data.");
+        if labels.len() != values.len() {
+            return Err(Error::format(labels_fname, format!("{} labels for {} images in {}",
+                                                           labels.len(), values.len(),
+                                                           vectors_fname.as_ref().display())));
         }
 
-        let res: Vec<Vec<f64>> = v.chunks(28 * 28)
-            .map(|chunk| chunk.iter().map(|&byte| (byte as f64 / u8::max_value() as f64)).collect())
-            .collect();
-
-        Ok(res)
+        Ok(values.into_iter().zip(labels).collect())
     }
 
-    pub fn from(vectors_fname: &str, labels_fname: &str) -> Result<Vec<(Vec<f64>, String)>, &'static str> {
-        let labels = MnistDigits::read_labels(labels_fname)?;
-        let values = MnistDigits::read_examples(vectors_fname)?;
-
-        Ok(values.into_iter().zip(labels.into_iter().map(|label| label.to_string())).collect())
-    }
-
-    fn path(fname: &str) -> Result<String, &'static str> {
-        use std::path::*;
+    fn path(fname: &str) -> PathBuf {
         let mut pbf = PathBuf::new();
         pbf.push("res/mnist");
         pbf.push(fname);
-        pbf.as_path().to_str().map(|x| x.to_string()).ok_or("Could not create path to mnist dataset")
+        pbf
+    }
+
+    /// Paths of the images and the labels of the MNIST training set, or of the test set if `test` is set.
+    pub fn default_paths(test: bool) -> (PathBuf, PathBuf) {
+        let set = if test { "t10k" } else { "train" };
+        (MnistDigits::path(&format!("{}-images-idx3-ubyte.gz", set)),
+         MnistDigits::path(&format!("{}-labels-idx1-ubyte.gz", set)))
     }
 
-    pub fn default_training_set() -> Result<Vec<(Vec<f64>, String)>, &'static str> {
-        let features = MnistDigits::path("train-images-idx3-ubyte.gz")?;
-        let labels = MnistDigits::path("train-labels-idx1-ubyte.gz")?;
+    pub fn default_training_set() -> Result<Vec<(Vec<f64>, String)>> {
+        let (features, labels) = MnistDigits::default_paths(false);
         MnistDigits::from(&features, &labels)
     }
 
-    pub fn default_test_set() -> Result<Vec<(Vec<f64>, String)>, &'static str> {
-        let features = MnistDigits::path("t10k-images-idx3-ubyte.gz")?;
-        let labels = MnistDigits::path("t10k-labels-idx1-ubyte.gz")?;
+    pub fn default_test_set() -> Result<Vec<(Vec<f64>, String)>> {
+        let (features, labels) = MnistDigits::default_paths(true);
         MnistDigits::from(&features, &labels)
     }
-}
\ No newline at end of file
+}
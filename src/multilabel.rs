@@ -0,0 +1,218 @@
+//! Multi-label classification: nets with an independent probability for every label, the labels of an input being
+//! all the ones whose probability reaches their threshold.
+
+use std::cmp::Ordering;
+use std::path::Path;
+use dataset::{self, Dataset};
+use metrics::{self, ClassMetrics};
+use multilayer_perceptron::{NetFile, Task};
+use error::{Error, Result};
+
+/// A trained net together with the labels of its output neurons and their thresholds.
+#[derive(Clone, Debug)]
+pub struct MultiLabelClassifier {
+    net: NetFile,
+}
+
+impl MultiLabelClassifier {
+    /// Wraps `net`, which fails for nets doing another task.
+    pub fn new(net: NetFile) -> Result<MultiLabelClassifier> {
+        if let Task::MultiLabel(..) = net.task {
+            return Ok(MultiLabelClassifier { net: net });
+        }
+        Err(Error::argument("net", format!("the net is a {}, not a multi-label classifier", net.task.name())))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<MultiLabelClassifier> {
+        let net = NetFile::load(&path)?;
+        if let Task::MultiLabel(..) = net.task {
+            return MultiLabelClassifier::new(net);
+        }
+        Err(Error::net(path, format!("the net is a {}, not a multi-label classifier", net.task.name())))
+    }
+
+    pub fn net(&self) -> &NetFile {
+        &self.net
+    }
+
+    pub fn into_net(self) -> NetFile {
+        self.net
+    }
+
+    /// Length of the inputs the classifier accepts.
+    pub fn num_inputs(&self) -> usize {
+        self.net.num_inputs()
+    }
+
+    /// Labels of the output neurons, in order.
+    pub fn labels(&self) -> Vec<&str> {
+        (0..self.net.labels.len()).map(|i| self.net.labels[&i].as_str()).collect()
+    }
+
+    /// Probability every label must reach to be predicted, in the order of the labels.
+    pub fn thresholds(&self) -> &[f64] {
+        match self.net.task {
+            Task::MultiLabel(ref thresholds) => thresholds,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets the thresholds of the labels, one for each of them in order.
+    pub fn set_thresholds(&mut self, thresholds: Vec<f64>) -> Result<()> {
+        if thresholds.len() != self.net.labels.len() {
+            return Err(Error::shape("thresholds", self.net.labels.len(), thresholds.len()));
+        }
+        self.net.task = Task::MultiLabel(thresholds);
+        Ok(())
+    }
+
+    /// Probability of every label for `input`, in order.
+    pub fn probabilities(&self, input: &[f64]) -> Result<Vec<f64>> {
+        if input.len() != self.num_inputs() {
+            return Err(Error::shape("classifier input", self.num_inputs(), input.len()));
+        }
+        Ok(self.net.net.feed_forward(input).0.at)
+    }
+
+    /// The labels of `input` with their probabilities, the most probable first.
+    pub fn predict(&self, input: &[f64]) -> Result<Vec<(String, f64)>> {
+        let mut labels: Vec<(String, f64)> = self.probabilities(input)?.into_iter().zip(self.thresholds())
+            .enumerate()
+            .filter(|&(_, (p, &threshold))| p >= threshold)
+            .map(|(i, (p, _))| (self.net.labels[&i].clone(), p))
+            .collect();
+        labels.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        Ok(labels)
+    }
+}
+
+/// Evaluation of a multi-label classifier on examples with label sets.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MultiLabelMetrics {
+    /// Number of examples.
+    pub total: usize,
+    /// Fraction of the labels of all the examples that were wrongly predicted or missed.
+    pub hamming_loss: f64,
+    /// Fraction of the examples whose label set was predicted exactly.
+    pub subset_accuracy: f64,
+    /// Precision, recall and F1 of every label, the support being the number of examples having it.
+    pub per_label: Vec<ClassMetrics>,
+    /// Unweighted mean of the F1 of the labels.
+    pub macro_f1: f64,
+    /// F1 of the counts of all the labels together.
+    pub micro_f1: f64,
+}
+
+impl MultiLabelMetrics {
+    /// Compares the `predictions` of the `labels` to the `truths`, both a vector per example telling whether it has
+    /// each label.
+    pub fn new(labels: &[String], predictions: &[Vec<bool>], truths: &[Vec<bool>]) -> MultiLabelMetrics {
+        let n = labels.len();
+        let correct: Vec<usize> = (0..n).map(|j| count(predictions, truths, j, |p, t| p && t)).collect();
+        let predicted: Vec<usize> = (0..n).map(|j| count(predictions, truths, j, |p, _| p)).collect();
+        let support: Vec<usize> = (0..n).map(|j| count(predictions, truths, j, |_, t| t)).collect();
+        let per_label: Vec<ClassMetrics> = labels.iter().enumerate()
+            .map(|(j, label)| ClassMetrics::new(label, correct[j], predicted[j], support[j]))
+            .collect();
+
+        let total = truths.len();
+        let wrong: usize = (0..n).map(|j| count(predictions, truths, j, |p, t| p != t)).sum();
+        let exact = predictions.iter().zip(truths).filter(|&(p, t)| p == t).count();
+        let micro = ClassMetrics::new("", correct.iter().sum(), predicted.iter().sum(), support.iter().sum());
+        MultiLabelMetrics {
+            total: total,
+            hamming_loss: wrong as f64 / (total * n) as f64,
+            subset_accuracy: exact as f64 / total as f64,
+            macro_f1: per_label.iter().map(|l| l.f1).sum::<f64>() / n as f64,
+            micro_f1: micro.f1,
+            per_label: per_label,
+        }
+    }
+}
+
+/// Number of the examples for which `f` holds for the prediction and the truth of the label `j`.
+fn count<F: Fn(bool, bool) -> bool>(predictions: &[Vec<bool>], truths: &[Vec<bool>], j: usize, f: F) -> usize {
+    predictions.iter().zip(truths).filter(|&(p, t)| f(p[j], t[j])).count()
+}
+
+/// Probabilities of the labels of `classifier` for the examples of `data`, and whether each example has each label.
+fn load_probabilities<D>(classifier: &MultiLabelClassifier, data: &D) -> Result<(Vec<Vec<f64>>, Vec<Vec<bool>>)>
+    where D: Dataset + ?Sized
+{
+    if data.is_empty() {
+        return Err(Error::EmptyDataset { source: "evaluation set".into() });
+    }
+    let labels = classifier.labels();
+    if let Some(label) = data.set_labels().into_iter().find(|label| !labels.contains(&label.as_str())) {
+        return Err(Error::argument("dataset", format!("the net has no output for label {:?}", label)));
+    }
+
+    let mut probabilities = Vec::with_capacity(data.len());
+    let mut truths = Vec::with_capacity(data.len());
+    for chunk in metrics::chunks(data) {
+        for (&i, (input, _)) in chunk.iter().zip(dataset::load_batch(data, &chunk)?) {
+            probabilities.push(classifier.probabilities(&input)?);
+            let label_set = data.label_set(i);
+            truths.push(labels.iter().map(|l| label_set.iter().any(|s| s.as_str() == *l)).collect());
+        }
+    }
+    Ok((probabilities, truths))
+}
+
+fn thresholded(probabilities: &[Vec<f64>], thresholds: &[f64]) -> Vec<Vec<bool>> {
+    probabilities.iter().map(|ps| ps.iter().zip(thresholds).map(|(p, t)| p >= t).collect()).collect()
+}
+
+/// Predicts the label sets of all the examples of `data`, a chunk at a time, and compares them to the truth.
+pub fn evaluate<D: Dataset + ?Sized>(classifier: &MultiLabelClassifier, data: &D) -> Result<MultiLabelMetrics> {
+    let (probabilities, truths) = load_probabilities(classifier, data)?;
+    let labels: Vec<String> = classifier.labels().into_iter().map(|l| l.to_string()).collect();
+    Ok(MultiLabelMetrics::new(&labels, &thresholded(&probabilities, classifier.thresholds()), &truths))
+}
+
+/// Fraction of the examples of `data` whose label set the multi-label `net` predicts exactly.
+pub fn subset_accuracy<D: Dataset + ?Sized>(net: &NetFile, data: &D) -> Result<f64> {
+    let classifier = MultiLabelClassifier::new(net.clone())?;
+    evaluate(&classifier, data).map(|metrics| metrics.subset_accuracy)
+}
+
+/// Number of thresholds tried for every label by `tune_thresholds`, evenly spaced in (0, 1).
+const THRESHOLD_STEPS: usize = 20;
+
+/// Sets the threshold of every label of `classifier` to the one maximizing its F1 on the held-out examples of `data`,
+/// the closest to 0.5 among equally good ones. Returns the new thresholds.
+pub fn tune_thresholds<D>(classifier: &mut MultiLabelClassifier, data: &D) -> Result<Vec<f64>>
+    where D: Dataset + ?Sized
+{
+    let (probabilities, truths) = load_probabilities(classifier, data)?;
+    let thresholds = (0..classifier.labels().len()).map(|j| {
+        let mut best: (f64, f64) = (-1.0, 0.5);
+        for step in 1..THRESHOLD_STEPS {
+            let threshold = step as f64 / THRESHOLD_STEPS as f64;
+            let predicted = probabilities.iter().filter(|ps| ps[j] >= threshold).count();
+            let correct = probabilities.iter().zip(&truths).filter(|&(ps, t)| ps[j] >= threshold && t[j]).count();
+            let support = truths.iter().filter(|t| t[j]).count();
+            let f1 = ClassMetrics::new("", correct, predicted, support).f1;
+            if f1 > best.0 || (f1 == best.0 && (threshold - 0.5).abs() < (best.1 - 0.5).abs()) {
+                best = (f1, threshold);
+            }
+        }
+        best.1
+    }).collect::<Vec<f64>>();
+    classifier.set_thresholds(thresholds.clone())?;
+    Ok(thresholds)
+}
+
+#[test]
+fn test_multilabel_metrics() {
+    let labels = vec!["cat".to_string(), "dog".to_string()];
+    let truths = vec![vec![true, true], vec![true, false], vec![false, false]];
+    let predictions = vec![vec![true, false], vec![true, false], vec![false, false]];
+    let metrics = MultiLabelMetrics::new(&labels, &predictions, &truths);
+    assert!((metrics.hamming_loss - 1.0 / 6.0).abs() < 1e-12);
+    assert!((metrics.subset_accuracy - 2.0 / 3.0).abs() < 1e-12);
+    assert_eq!((metrics.per_label[0].f1, metrics.per_label[1].f1), (1.0, 0.0));
+    assert_eq!(metrics.per_label[1].support, 1);
+    assert_eq!(metrics.macro_f1, 0.5);
+    assert!((metrics.micro_f1 - 0.8).abs() < 1e-12);
+}
@@ -1,4 +1,4 @@
-use rand;
+use rand::{self, Rng};
 use activation_func::{ActivationFunction, ActivationFunctionEnum};
 #[cfg(test)]
 use activation_func::Tanh;
@@ -10,8 +10,12 @@ use std::collections::HashMap;
 use rayon::prelude::*;
 use serde::ser::{Serialize, Serializer};
 use serde::de::{Deserialize, Deserializer};
-#[cfg(test)]
 use bincode;
+use std::fs::File;
+use std::path::Path;
+use error::{self, Error};
+use rejection::RejectRule;
+use preprocess::Pipeline;
 
 fn make_dvector_with_bias(x: &[f64]) -> DVector<f64> {
     let mut i = DVector::from_slice(x.len(), x);
@@ -85,9 +89,235 @@ pub struct MultilayerPerceptron {
     pub sparsity_params: Option<SparsityParams>,
 }
 
+/// What learning minimizes, as a function of the differences between the outputs and the targets.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Loss {
+    /// Squared distance between the outputs and the targets.
+    SquaredError,
+    /// The squared error of the outputs within `delta` of their targets, and `delta * (2 * |e| - delta)` beyond, so
+    /// that the outliers weigh less.
+    Huber(f64),
+    /// Binary cross-entropy of outputs in (0, 1) as probabilities of targets 0 or 1, for `Sigmoid(1.0)` outputs
+    /// only: the delta of an output is then just `output - target`, without the derivative of the activation
+    /// function.
+    CrossEntropy,
+}
+
+impl Default for Loss {
+    fn default() -> Loss {
+        Loss::SquaredError
+    }
+}
+
+impl Loss {
+    /// Loss of an `output` for its `target`.
+    pub fn value(&self, output: f64, target: f64) -> f64 {
+        let error = output - target;
+        match *self {
+            Loss::Huber(delta) if error.abs() > delta => delta * (2.0 * error.abs() - delta),
+            Loss::CrossEntropy => {
+                let p = output.max(1e-12).min(1.0 - 1e-12);
+                -(target * p.ln() + (1.0 - target) * (1.0 - p).ln())
+            }
+            _ => error * error,
+        }
+    }
 
+    /// Half the derivative of `value` for `output`, the error itself for the squared error. For the cross-entropy it
+    /// is the error too, but as the derivative for the input of a sigmoid output.
+    pub fn gradient(&self, output: f64, target: f64) -> f64 {
+        let error = output - target;
+        match *self {
+            Loss::Huber(delta) => error.max(-delta).min(delta),
+            Loss::SquaredError | Loss::CrossEntropy => error,
+        }
+    }
+}
+
+/// Statistics of a learnt batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchStats {
+    /// Mean loss of the outputs, before the weights were updated.
+    pub loss: f64,
+    /// Euclidean norm of the gradient of all the weights.
+    pub gradient_norm: f64,
+}
+
+/// Net files written before they had a header.
+#[derive(Deserialize)]
+struct LegacyNetFile(MultilayerPerceptron, HashMap<usize, String>);
+
+/// Version 1 net files, written before nets had reject rules.
+#[derive(Deserialize)]
+struct NetFileV1 {
+    net: MultilayerPerceptron,
+    labels: HashMap<usize, String>,
+    temperature: f64,
+}
+
+/// Version 2 net files, written before nets could be regressors.
+#[derive(Deserialize)]
+struct NetFileV2 {
+    net: MultilayerPerceptron,
+    labels: HashMap<usize, String>,
+    temperature: f64,
+    reject: Option<RejectRule>,
+}
+
+/// Version 3 net files, written before nets had their preprocessing.
+#[derive(Deserialize)]
+struct NetFileV3 {
+    net: MultilayerPerceptron,
+    labels: HashMap<usize, String>,
+    temperature: f64,
+    reject: Option<RejectRule>,
+    task: Task,
+}
+
+const NET_FILE_MAGIC: &'static [u8; 4] = b"MLPC";
+const NET_FILE_VERSION: u8 = 4;
+
+/// What the outputs of a net are.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Task {
+    /// Scores of the labels of the net.
+    Classification,
+    /// Values of the targets named in order.
+    Regression(Vec<String>),
+    /// Independent probabilities of the labels of the net, each one predicted above its threshold, in the order of
+    /// the outputs.
+    MultiLabel(Vec<f64>),
+    /// Reconstructions of the inputs, by nets without labels.
+    Autoencoder,
+}
+
+impl Task {
+    /// What a net doing the task is called.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Task::Classification => "classifier",
+            Task::Regression(..) => "regressor",
+            Task::MultiLabel(..) => "multi-label classifier",
+            Task::Autoencoder => "autoencoder",
+        }
+    }
+}
+
+/// A trained classifier, as saved on disk.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct NetFile(pub MultilayerPerceptron, pub HashMap<usize, String>);
+pub struct NetFile {
+    pub net: MultilayerPerceptron,
+    /// Label of every output neuron.
+    pub labels: HashMap<usize, String>,
+    /// Outputs are divided by it before the softmax, see `calibration::fit_temperature`.
+    pub temperature: f64,
+    /// When set, inputs the net isn't sure about are classified as unknown.
+    pub reject: Option<RejectRule>,
+    pub task: Task,
+    /// How the image files the net is given are made into inputs.
+    pub preprocessing: Pipeline,
+}
+
+impl NetFile {
+    /// A classifier whose output neurons are the scores of `labels`.
+    pub fn new(net: MultilayerPerceptron, labels: HashMap<usize, String>) -> NetFile {
+        NetFile {
+            net: net,
+            labels: labels,
+            temperature: 1.0,
+            reject: None,
+            task: Task::Classification,
+            preprocessing: Pipeline::default(),
+        }
+    }
+
+    /// A regressor whose output neurons are the values of `targets`.
+    pub fn regressor(net: MultilayerPerceptron, targets: Vec<String>) -> NetFile {
+        NetFile { task: Task::Regression(targets), ..NetFile::new(net, HashMap::new()) }
+    }
+
+    /// A multi-label classifier whose output neurons are the probabilities of `labels`, with thresholds of 0.5.
+    pub fn multi_label(net: MultilayerPerceptron, labels: HashMap<usize, String>) -> NetFile {
+        let thresholds = vec![0.5; labels.len()];
+        NetFile { task: Task::MultiLabel(thresholds), ..NetFile::new(net, labels) }
+    }
+
+    /// An autoencoder, whose output neurons reconstruct its inputs.
+    pub fn autoencoder(net: MultilayerPerceptron) -> NetFile {
+        NetFile { task: Task::Autoencoder, ..NetFile::new(net, HashMap::new()) }
+    }
+
+    /// Loads the net in `path`, which fails if it's a classifier without a label for every output neuron.
+    pub fn load<P: AsRef<Path>>(path: P) -> error::Result<NetFile> {
+        let path = path.as_ref();
+        let net = NetFile::load_unchecked(path)?;
+        match net.task {
+            Task::Classification | Task::MultiLabel(_) => {
+                if let Some(i) = (0..net.num_outputs()).find(|i| !net.labels.contains_key(i)) {
+                    let name = net.task.name();
+                    return Err(Error::net(path, format!("output neuron {} of the {} has no label", i, name)));
+                }
+            }
+            Task::Regression(_) | Task::Autoencoder => (),
+        }
+        Ok(net)
+    }
+
+    fn load_unchecked(path: &Path) -> error::Result<NetFile> {
+        use std::io::Read;
+        let mut bytes = Vec::new();
+        File::open(path).and_then(|mut f| f.read_to_end(&mut bytes)).map_err(|e| Error::io(path, e))?;
+
+        if !bytes.starts_with(NET_FILE_MAGIC) {
+            let LegacyNetFile(net, labels) = bincode::serde::deserialize(&bytes).map_err(|e| Error::net(path, e))?;
+            return Ok(NetFile::new(net, labels));
+        }
+
+        let version = bytes.get(NET_FILE_MAGIC.len()).cloned().unwrap_or(0);
+        let body = &bytes[::std::cmp::min(NET_FILE_MAGIC.len() + 1, bytes.len())..];
+        match version {
+            1 => {
+                let v1: NetFileV1 = bincode::serde::deserialize(body).map_err(|e| Error::net(path, e))?;
+                Ok(NetFile { temperature: v1.temperature, ..NetFile::new(v1.net, v1.labels) })
+            }
+            2 => {
+                let v2: NetFileV2 = bincode::serde::deserialize(body).map_err(|e| Error::net(path, e))?;
+                Ok(NetFile { temperature: v2.temperature, reject: v2.reject, ..NetFile::new(v2.net, v2.labels) })
+            }
+            3 => {
+                let v3: NetFileV3 = bincode::serde::deserialize(body).map_err(|e| Error::net(path, e))?;
+                Ok(NetFile {
+                    temperature: v3.temperature,
+                    reject: v3.reject,
+                    task: v3.task,
+                    ..NetFile::new(v3.net, v3.labels)
+                })
+            }
+            NET_FILE_VERSION => bincode::serde::deserialize(body).map_err(|e| Error::net(path, e)),
+            _ => Err(Error::net(path, format!("unsupported version {}, expected {}", version, NET_FILE_VERSION))),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> error::Result<()> {
+        use std::io::Write;
+        let path = path.as_ref();
+        let mut file = File::create(path).map_err(|e| Error::io(path, e))?;
+        file.write_all(NET_FILE_MAGIC).and_then(|_| file.write_all(&[NET_FILE_VERSION]))
+            .map_err(|e| Error::io(path, e))?;
+        bincode::serde::serialize_into(&mut file, self, bincode::SizeLimit::Infinite)
+            .map_err(|e| Error::net(path, e))
+    }
+
+    /// Number of inputs the net expects, without the bias.
+    pub fn num_inputs(&self) -> usize {
+        self.net.layers[0].num_inputs() - 1
+    }
+
+    /// Number of output neurons of the net.
+    pub fn num_outputs(&self) -> usize {
+        self.net.layers.last().map(|layer| layer.weights.ncols()).unwrap_or(0)
+    }
+}
 
 #[test]
 fn test_serialization() {
@@ -113,6 +343,16 @@ impl MultilayerPerceptron {
         learning_rate: f64,
         inputs: usize,
         layers: &[(usize, ActivationFunctionEnum)]
+    ) -> MultilayerPerceptron {
+        MultilayerPerceptron::with_rng(learning_rate, inputs, layers, &mut rand::thread_rng())
+    }
+
+    /// Like `new`, but draws the initial weights from `rng`.
+    pub fn with_rng<R: Rng>(
+        learning_rate: f64,
+        inputs: usize,
+        layers: &[(usize, ActivationFunctionEnum)],
+        rng: &mut R
     ) -> MultilayerPerceptron {
         let mut l = Vec::with_capacity(layers.len());
         let mut prev_layer_size = inputs + 1;
@@ -122,10 +362,11 @@ impl MultilayerPerceptron {
         let normal = Normal::new(0.0, 0.1);
 
         for i in 0..layers.len() {
+            let (rows, cols) = (prev_layer_size, layers[i].0);
+            let values: Vec<f64> = (0..rows * cols).map(|_| normal.ind_sample(rng)).collect();
             l.push(Layer::new(
                 layers[i].1,
-                DMatrix::from_fn(prev_layer_size, layers[i].0,
-                                 |_, _| normal.ind_sample(&mut rand::thread_rng())))
+                DMatrix::from_fn(rows, cols, |r, c| values[r * cols + c]))
             );
             prev_layer_size = layers[i].0;
         }
@@ -157,6 +398,19 @@ impl MultilayerPerceptron {
         target: &[f64],
         average_activations_of_hidden_layers: Option<&Vec<DVector<f64>>>
     ) -> Vec<DMatrix<f64>> {
+        self.backpropagate_with_loss(input, target, average_activations_of_hidden_layers, Loss::SquaredError, 1.0).0
+    }
+
+    /// Like `backpropagate`, but minimizing `loss`, whose value for the output is also returned, both scaled by
+    /// the `weight` of the example.
+    fn backpropagate_with_loss(
+        &self,
+        input: &[f64],
+        target: &[f64],
+        average_activations_of_hidden_layers: Option<&Vec<DVector<f64>>>,
+        loss: Loss,
+        weight: f64
+    ) -> (Vec<DMatrix<f64>>, f64) {
         let expected_output = DVector::from_slice(target.len(), target);
         let (final_out, steps) = self.feed_forward(input);
         let num_steps = steps.len();
@@ -170,10 +424,18 @@ impl MultilayerPerceptron {
 
         let mut activation_derivative = last_layer.net(steps.last().unwrap());
         for x in activation_derivative.iter_mut() {
-            *x = last_layer.activation_function.derivative(*x);
+            *x = match loss {
+                // the derivative of Sigmoid(1.0) cancels out with the one of the cross-entropy
+                Loss::CrossEntropy => 1.0,
+                _ => last_layer.activation_function.derivative(*x),
+            };
         }
 
-        let error = final_out - expected_output;
+        let loss_value: f64 = final_out.at.iter().zip(target).map(|(&o, &t)| loss.value(o, t)).sum();
+        let mut error = final_out;
+        for (e, &t) in error.iter_mut().zip(target) {
+            *e = loss.gradient(*e, t);
+        }
         let output_layer_delta = error * activation_derivative;
 
         let mut deltas = Vec::with_capacity(self.layers.len());
@@ -206,13 +468,78 @@ impl MultilayerPerceptron {
 
         assert!(deltas.len() == steps.len());
 
-        deltas.into_iter().rev().zip(steps.iter())
-            .map(|(d, s)| self.learning_rate * s.outer(&d)).collect()
+        let weight_deltas = deltas.into_iter().rev().zip(steps.iter())
+            .map(|(d, s)| (weight * self.learning_rate) * s.outer(&d)).collect();
+        (weight_deltas, weight * loss_value)
+    }
+
+    /// Subtracts the mean of the summed `batch_delta` over examples of the total weight `batch_weight` from the
+    /// weights, which are left as they are if the examples don't weigh anything.
+    fn apply_batch_delta(&mut self, mut batch_delta: Vec<DMatrix<f64>>, loss_sum: f64, batch_weight: f64)
+                         -> BatchStats {
+        if batch_weight == 0.0 {
+            return BatchStats { loss: 0.0, gradient_norm: 0.0 };
+        }
+        let mut squared_norm = 0.0;
+        for x in &mut batch_delta {
+            for el in x.as_mut_vector() {
+                *el /= batch_weight;
+                squared_norm += *el * *el;
+            }
+        }
+
+        for (l, d) in self.layers.iter_mut().zip(batch_delta.iter()) {
+            l.weights -= d
+        }
+
+        BatchStats {
+            loss: loss_sum / batch_weight,
+            // the deltas are the gradient scaled by the learning rate
+            gradient_norm: if self.learning_rate == 0.0 { 0.0 } else { squared_norm.sqrt() / self.learning_rate },
+        }
+    }
+
+    pub fn learn_batch<I, T>(&mut self, batch: &[(I, T)]) -> BatchStats
+        where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync
+    {
+        self.learn_batch_with_loss(batch, Loss::SquaredError, true)
+    }
+
+    pub fn learn_batch_no_parallel<I, T>(&mut self, batch: &[(I, T)]) -> BatchStats
+        where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync
+    {
+        self.learn_batch_with_loss(batch, Loss::SquaredError, false)
+    }
+
+    /// Learns `batch` minimizing `loss`, on all the available threads if `parallel` is set.
+    pub fn learn_batch_with_loss<I, T>(&mut self, batch: &[(I, T)], loss: Loss, parallel: bool) -> BatchStats
+        where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync
+    {
+        self.learn_examples(batch, None, loss, parallel)
+    }
+
+    /// Like `learn_batch_with_loss`, but every example counts as much as its weight in `weights`, the weights being
+    /// in the order of the examples. The reported loss is the weighted mean.
+    pub fn learn_weighted_batch<I, T>(&mut self, batch: &[(I, T)], weights: &[f64], loss: Loss, parallel: bool)
+                                      -> BatchStats
+        where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync
+    {
+        if weights.len() != batch.len() {
+            panic!("weights have wrong length: expected: {}, given: {}", batch.len(), weights.len())
+        }
+        self.learn_examples(batch, Some(weights), loss, parallel)
     }
 
-    pub fn learn_batch<I, T>(&mut self, batch: &[(I, T)])
+    fn learn_examples<I, T>(&mut self, batch: &[(I, T)], weights: Option<&[f64]>, loss: Loss, parallel: bool)
+                            -> BatchStats
         where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync
     {
+        let batch_weight = weights.map(|w| w.iter().sum::<f64>()).unwrap_or(batch.len() as f64);
+        let weight = |k: usize| weights.map(|w| w[k]).unwrap_or(1.0);
+        if !parallel {
+            return self.learn_batch_sequentially(batch, &weight, batch_weight, loss);
+        }
+
         let average_activations_of_hidden_layers = if let Some(..) = self.sparsity_params {
             batch.par_iter()
                 .map(|&(ref i, _)| Some(self.feed_forward(i.deref()).1))
@@ -237,51 +564,82 @@ impl MultilayerPerceptron {
                 })
         } else { None };
 
-        let mut batch_delta = batch.par_iter()
-            .map(|&(ref i, ref t)| Some(self.backpropagate(i.deref(), t.deref(), average_activations_of_hidden_layers.as_ref())))
+        let (batch_delta, loss_sum) = batch.par_iter().enumerate()
+            .map(|(k, &(ref i, ref t))| Some(self.backpropagate_with_loss(i.deref(), t.deref(), average_activations_of_hidden_layers.as_ref(), loss, weight(k))))
             .weight_max()
             .reduce(|| None, |acc, v_opt| {
-                acc.and_then(|mut old_v: Vec<DMatrix<f64>>| {
-                    v_opt.as_ref().map(|v| {
+                acc.and_then(|(mut old_v, old_loss): (Vec<DMatrix<f64>>, f64)| {
+                    v_opt.as_ref().map(|&(ref v, loss)| {
                         for (i, x) in old_v.iter_mut().enumerate() { *x += &v[i] }
-                        old_v
+                        (old_v, old_loss + loss)
                     })
                 }).or(v_opt)
             }).unwrap();
 
-        for x in &mut batch_delta {
-            for el in x.as_mut_vector() {
-                *el /= batch.len() as f64
-            }
-        }
-
-        for (l, d) in self.layers.iter_mut().zip(batch_delta.iter()) {
-            l.weights -= d
-        }
+        self.apply_batch_delta(batch_delta, loss_sum, batch_weight)
     }
 
-    pub fn learn_batch_no_parallel<I, T>(&mut self, batch: &[(I, T)])
-        where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync {
-        let mut batch_delta = batch.iter()
-            .map(|&(ref i, ref t)| Some(self.backpropagate(i.deref(), t.deref(), None)))
+    fn learn_batch_sequentially<I, T, W>(&mut self, batch: &[(I, T)], weight: &W, batch_weight: f64, loss: Loss)
+                                         -> BatchStats
+        where I: Deref<Target = [f64]> + Sync, T: Deref<Target = [f64]> + Sync, W: Fn(usize) -> f64 {
+        let (batch_delta, loss_sum) = batch.iter().enumerate()
+            .map(|(k, &(ref i, ref t))| Some(self.backpropagate_with_loss(i.deref(), t.deref(), None, loss, weight(k))))
             .fold(None, |acc, v_opt| {
-                acc.and_then(|mut old_v: Vec<DMatrix<f64>>| {
-                    v_opt.as_ref().map(|v| {
+                acc.and_then(|(mut old_v, old_loss): (Vec<DMatrix<f64>>, f64)| {
+                    v_opt.as_ref().map(|&(ref v, loss)| {
                         for (i, x) in old_v.iter_mut().enumerate() { *x += &v[i] }
-                        old_v
+                        (old_v, old_loss + loss)
                     })
                 }).or(v_opt)
             }).unwrap();
 
-        for x in &mut batch_delta {
-            for el in x.as_mut_vector() {
-                *el /= batch.len() as f64
-            }
-        }
+        self.apply_batch_delta(batch_delta, loss_sum, batch_weight)
+    }
+}
 
-        for (l, d) in self.layers.iter_mut().zip(batch_delta.iter()) {
-            l.weights -= d
-        }
+#[test]
+fn test_huber_loss() {
+    let huber = Loss::Huber(1.0);
+    assert_eq!((huber.value(0.5, 0.0), huber.gradient(0.5, 0.0)), (0.25, 0.5));
+    assert_eq!((huber.value(-3.0, 0.0), huber.gradient(-3.0, 0.0)), (5.0, -1.0));
+    assert_eq!(Loss::SquaredError.value(-3.0, 0.0), 9.0);
+}
+
+#[test]
+fn test_cross_entropy_loss() {
+    let loss = Loss::CrossEntropy;
+    assert!((loss.value(0.5, 1.0) - 2.0f64.ln()).abs() < 1e-12);
+    assert_eq!(loss.value(1.0, 1.0), loss.value(0.0, 0.0));
+    assert_eq!(loss.gradient(0.25, 1.0), -0.75);
+}
+
+#[test]
+fn test_learn_weighted_batch() {
+    let perc = MultilayerPerceptron::new(0.5, 2, &[(3, Tanh(1.0).into()), (1, Tanh(1.0).into())]);
+    let (a, b) = ((vec![1.0, 0.0], vec![1.0]), (vec![0.0, 1.0], vec![-1.0]));
+
+    // an example of weight 2 counts as much as two copies of it
+    let mut weighted = perc.clone();
+    let stats = weighted.learn_weighted_batch(&[a.clone(), b.clone()], &[2.0, 1.0], Loss::SquaredError, false);
+    let mut repeated = perc.clone();
+    let expected = repeated.learn_batch_no_parallel(&[a.clone(), a.clone(), b.clone()]);
+    assert!((stats.loss - expected.loss).abs() < 1e-12);
+    for input in &[&a.0, &b.0] {
+        assert!((weighted.feed_forward(input).0[0] - repeated.feed_forward(input).0[0]).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_learn_weightless_batch() {
+    let perc = MultilayerPerceptron::new(0.5, 2, &[(3, Tanh(1.0).into()), (1, Tanh(1.0).into())]);
+    let batch = [(vec![1.0, 0.0], vec![1.0]), (vec![0.0, 1.0], vec![-1.0])];
+
+    // examples that don't weigh anything leave the weights as they are
+    let mut learnt = perc.clone();
+    let stats = learnt.learn_weighted_batch(&batch, &[0.0, 0.0], Loss::SquaredError, false);
+    assert_eq!((stats.loss, stats.gradient_norm), (0.0, 0.0));
+    for (learnt, layer) in learnt.layers.iter().zip(&perc.layers) {
+        assert_eq!(learnt.weights, layer.weights);
     }
 }
 
@@ -325,16 +683,16 @@ use img;
 #[ignore]
 #[test]
 fn test_learn_batch() {
-    let zero = (img::get_pixels("res/Sieci Neuronowe/0_158975_1.png"), &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
-    let one = (img::get_pixels("res/Sieci Neuronowe/1_158975_1.png"), &[0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
-    let two = (img::get_pixels("res/Sieci Neuronowe/2_203255_0.png"), &[0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
-    let three = (img::get_pixels("res/Sieci Neuronowe/3_203119_1.png"), &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
-    let four = (img::get_pixels("res/Sieci Neuronowe/4_203277_0.png"), &[0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
-    let five = (img::get_pixels("res/Sieci Neuronowe/5_203277_1.png"), &[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0][..]);
-    let six = (img::get_pixels("res/Sieci Neuronowe/6_203255_2.png"), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0][..]);
-    let seven = (img::get_pixels("res/Sieci Neuronowe/7_203255_2.png"), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0][..]);
-    let eight = (img::get_pixels("res/Sieci Neuronowe/8_158975_1.png"), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0][..]);
-    let nine = (img::get_pixels("res/Sieci Neuronowe/9_203303_2.png"), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0][..]);
+    let zero = (img::get_pixels("res/Sieci Neuronowe/0_158975_1.png").unwrap(), &[1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
+    let one = (img::get_pixels("res/Sieci Neuronowe/1_158975_1.png").unwrap(), &[0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
+    let two = (img::get_pixels("res/Sieci Neuronowe/2_203255_0.png").unwrap(), &[0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
+    let three = (img::get_pixels("res/Sieci Neuronowe/3_203119_1.png").unwrap(), &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
+    let four = (img::get_pixels("res/Sieci Neuronowe/4_203277_0.png").unwrap(), &[0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0][..]);
+    let five = (img::get_pixels("res/Sieci Neuronowe/5_203277_1.png").unwrap(), &[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0][..]);
+    let six = (img::get_pixels("res/Sieci Neuronowe/6_203255_2.png").unwrap(), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0][..]);
+    let seven = (img::get_pixels("res/Sieci Neuronowe/7_203255_2.png").unwrap(), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0][..]);
+    let eight = (img::get_pixels("res/Sieci Neuronowe/8_158975_1.png").unwrap(), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0][..]);
+    let nine = (img::get_pixels("res/Sieci Neuronowe/9_203303_2.png").unwrap(), &[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0][..]);
 
     let num_pixels = one.0.len();
     println!("#pixels: {}", num_pixels);
@@ -0,0 +1,355 @@
+//! Preprocessing of image files into the inputs of nets.
+//!
+//! The pipeline a net learnt with is saved in its net file, so that the images it later predicts are prepared the
+//! same way. It is only applied to image files: the other datasets are numbers already.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use image;
+use error::{Error, Result};
+
+/// The channels the images are read with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    /// The luma.
+    Gray,
+    /// Red, green and blue values, interleaved.
+    Rgb,
+}
+
+impl Color {
+    pub fn channels(&self) -> usize {
+        match *self {
+            Color::Gray => 1,
+            Color::Rgb => 3,
+        }
+    }
+}
+
+/// A step of a preprocessing pipeline. The steps that look for the ink take it to be the pixels the most unlike the
+/// corners of the image.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// Scales the image to a width and a height, interpolating bilinearly.
+    Resize(u32, u32),
+    /// Replaces every value `x` by `1 - x`, making dark ink on white paper high values on low ones.
+    Invert,
+    /// Crops the image to the bounding box of its ink and scales it back up to the size of the image, keeping its
+    /// aspect ratio, in the middle.
+    Crop,
+    /// Moves the image so that the center of mass of its ink is in the middle.
+    Center,
+    /// Shears the image horizontally so that its ink stands upright, as measured by its second moments.
+    Deskew,
+    /// Stretches the values of the image to `[0, 1]`.
+    MinMax,
+    /// Shifts and scales the values of the image to a mean of 0 and a standard deviation of 1.
+    ZScore,
+}
+
+/// An image with values in `[0, 1]` for the files, white being 1, row by row and with interleaved channels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub channels: usize,
+    pub pixels: Vec<f64>,
+}
+
+/// Fraction of the largest difference from the background above which a pixel is ink.
+const INK_THRESHOLD: f64 = 0.2;
+
+impl Image {
+    /// Decodes the image file at `path` with the channels of `color`.
+    pub fn open<P: AsRef<Path>>(path: P, color: Color) -> Result<Image> {
+        use image::GenericImage;
+        let path = path.as_ref();
+        let img = image::open(path).map_err(|e| Error::image(path, e))?;
+        let (width, height) = img.dimensions();
+        let bytes = match color {
+            Color::Gray => img.to_luma().into_raw(),
+            Color::Rgb => img.to_rgb().into_raw(),
+        };
+        Ok(Image {
+            width: width,
+            height: height,
+            channels: color.channels(),
+            pixels: bytes.into_iter().map(|byte| byte as f64 / u8::max_value() as f64).collect(),
+        })
+    }
+
+    /// A grayscale image of `pixels`, which must be `width * height` of them.
+    pub fn gray(width: u32, height: u32, pixels: Vec<f64>) -> Result<Image> {
+        if pixels.len() != (width * height) as usize {
+            return Err(Error::shape(format!("{}x{} image", width, height), (width * height) as usize, pixels.len()));
+        }
+        Ok(Image { width: width, height: height, channels: 1, pixels: pixels })
+    }
+
+    fn at(&self, x: usize, y: usize, c: usize) -> f64 {
+        self.pixels[(y * self.width as usize + x) * self.channels + c]
+    }
+
+    /// Converts the image to the channels of `color`.
+    fn with_color(self, color: Color) -> Image {
+        let pixels = match (self.channels, color) {
+            (1, Color::Rgb) => self.pixels.iter().flat_map(|&x| vec![x; 3]).collect(),
+            (3, Color::Gray) => self.pixels.chunks(3).map(|p| 0.299 * p[0] + 0.587 * p[1] + 0.114 * p[2]).collect(),
+            _ => return self,
+        };
+        Image { channels: color.channels(), pixels: pixels, ..self }
+    }
+
+    /// Mean value of the corners in every channel.
+    fn background(&self) -> Vec<f64> {
+        let (w, h) = (self.width as usize - 1, self.height as usize - 1);
+        (0..self.channels)
+            .map(|c| (self.at(0, 0, c) + self.at(w, 0, c) + self.at(0, h, c) + self.at(w, h, c)) / 4.0)
+            .collect()
+    }
+
+    /// How much every pixel differs from the background, 0 below the ink threshold.
+    fn ink(&self) -> Vec<f64> {
+        let background = self.background();
+        let diffs: Vec<f64> = self.pixels.chunks(self.channels)
+            .map(|p| p.iter().zip(&background).map(|(x, b)| (x - b).abs()).sum::<f64>() / self.channels as f64)
+            .collect();
+        let max = diffs.iter().cloned().fold(0.0, f64::max);
+        diffs.into_iter().map(|d| if d > INK_THRESHOLD * max { d } else { 0.0 }).collect()
+    }
+
+    /// Center of mass of `ink`, `None` if there's none.
+    fn center_of_mass(&self, ink: &[f64]) -> Option<(f64, f64)> {
+        let mass: f64 = ink.iter().sum();
+        if mass == 0.0 {
+            return None;
+        }
+        let w = self.width as usize;
+        let x = ink.iter().enumerate().map(|(i, m)| (i % w) as f64 * m).sum::<f64>() / mass;
+        let y = ink.iter().enumerate().map(|(i, m)| (i / w) as f64 * m).sum::<f64>() / mass;
+        Some((x, y))
+    }
+
+    /// Bilinear interpolation of the channel `c` at `(x, y)` in pixels, `fill` outside of the image.
+    fn sample(&self, x: f64, y: f64, c: usize, fill: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let value = |x: f64, y: f64| {
+            if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+                fill
+            } else {
+                self.at(x as usize, y as usize, c)
+            }
+        };
+        (1.0 - fy) * ((1.0 - fx) * value(x0, y0) + fx * value(x0 + 1.0, y0)) +
+            fy * ((1.0 - fx) * value(x0, y0 + 1.0) + fx * value(x0 + 1.0, y0 + 1.0))
+    }
+
+    /// A `width`x`height` image whose pixel `(x, y)` is sampled at `source(x, y)` of this one, the background beyond.
+    pub fn warp<F: Fn(f64, f64) -> (f64, f64)>(&self, width: u32, height: u32, source: F) -> Image {
+        let background = self.background();
+        let mut pixels = Vec::with_capacity((width * height) as usize * self.channels);
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = source(x as f64, y as f64);
+                for c in 0..self.channels {
+                    pixels.push(self.sample(sx, sy, c, background[c]));
+                }
+            }
+        }
+        Image { width: width, height: height, channels: self.channels, pixels: pixels }
+    }
+
+    fn resize(&self, width: u32, height: u32) -> Image {
+        let (sx, sy) = (self.width as f64 / width as f64, self.height as f64 / height as f64);
+        self.warp(width, height, |x, y| ((x + 0.5) * sx - 0.5, (y + 0.5) * sy - 0.5))
+    }
+
+    fn crop(&self) -> Image {
+        let ink = self.ink();
+        let w = self.width as usize;
+        let inked: Vec<(usize, usize)> = ink.iter().enumerate().filter(|&(_, &m)| m > 0.0).map(|(i, _)| (i % w, i / w))
+            .collect();
+        if inked.is_empty() {
+            return self.clone();
+        }
+        let (left, right) = (inked.iter().map(|p| p.0).min().unwrap(), inked.iter().map(|p| p.0).max().unwrap());
+        let (top, bottom) = (inked.iter().map(|p| p.1).min().unwrap(), inked.iter().map(|p| p.1).max().unwrap());
+        let (box_w, box_h) = ((right - left + 1) as f64, (bottom - top + 1) as f64);
+        let (width, height) = (self.width as f64, self.height as f64);
+        let scale = (width / box_w).min(height / box_h);
+        self.warp(self.width, self.height, |x, y| {
+            (left as f64 + (x + 0.5 - width / 2.0) / scale + box_w / 2.0 - 0.5,
+             top as f64 + (y + 0.5 - height / 2.0) / scale + box_h / 2.0 - 0.5)
+        })
+    }
+
+    fn center(&self) -> Image {
+        let (cx, cy) = match self.center_of_mass(&self.ink()) {
+            Some(center) => center,
+            None => return self.clone(),
+        };
+        let (dx, dy) = (cx - (self.width as f64 - 1.0) / 2.0, cy - (self.height as f64 - 1.0) / 2.0);
+        self.warp(self.width, self.height, |x, y| (x + dx, y + dy))
+    }
+
+    fn deskew(&self) -> Image {
+        let ink = self.ink();
+        let (cx, cy) = match self.center_of_mass(&ink) {
+            Some(center) => center,
+            None => return self.clone(),
+        };
+        let w = self.width as usize;
+        let mu11: f64 = ink.iter().enumerate().map(|(i, m)| m * ((i % w) as f64 - cx) * ((i / w) as f64 - cy)).sum();
+        let mu02: f64 = ink.iter().enumerate().map(|(i, m)| m * ((i / w) as f64 - cy).powi(2)).sum();
+        if mu02 == 0.0 {
+            return self.clone();
+        }
+        let skew = mu11 / mu02;
+        self.warp(self.width, self.height, |x, y| (x + skew * (y - cy), y))
+    }
+
+    fn map<F: Fn(f64) -> f64>(self, f: F) -> Image {
+        Image { pixels: self.pixels.into_iter().map(f).collect(), ..self }
+    }
+
+    fn min_max(self) -> Image {
+        let min = self.pixels.iter().cloned().fold(1.0 / 0.0, f64::min);
+        let max = self.pixels.iter().cloned().fold(-1.0 / 0.0, f64::max);
+        let delta = max - min;
+        self.map(|x| if delta > 0.0 { (x - min) / delta } else { 0.0 })
+    }
+
+    fn z_score(self) -> Image {
+        let n = self.pixels.len() as f64;
+        let mean = self.pixels.iter().sum::<f64>() / n;
+        let std = (self.pixels.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / n).sqrt();
+        self.map(|x| if std > 0.0 { (x - mean) / std } else { 0.0 })
+    }
+
+    fn apply(self, step: Step) -> Image {
+        match step {
+            Step::Resize(width, height) => self.resize(width, height),
+            Step::Invert => self.map(|x| 1.0 - x),
+            Step::Crop => self.crop(),
+            Step::Center => self.center(),
+            Step::Deskew => self.deskew(),
+            Step::MinMax => self.min_max(),
+            Step::ZScore => self.z_score(),
+        }
+    }
+}
+
+/// How image files become inputs: the channels they are read with, then steps applied in order.
+///
+/// Written like `gray,resize=28x28,crop,invert`, see `FromStr`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pipeline {
+    pub color: Color,
+    pub steps: Vec<Step>,
+}
+
+/// The pipeline of the nets that were saved without one: grayscale, dark ink being high values.
+impl Default for Pipeline {
+    fn default() -> Pipeline {
+        Pipeline { color: Color::Gray, steps: vec![Step::Invert] }
+    }
+}
+
+impl Pipeline {
+    /// Reads the image file at `path` and preprocesses it into an input.
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<Vec<f64>> {
+        Image::open(path, self.color).map(|image| self.process(image).pixels)
+    }
+
+    /// Preprocesses `image`, converting it to the channels of the pipeline first.
+    pub fn process(&self, image: Image) -> Image {
+        self.steps.iter().fold(image.with_color(self.color), |image, &step| image.apply(step))
+    }
+
+    /// Width and height of the inputs made of `width`x`height` images.
+    pub fn output_dims(&self, width: u32, height: u32) -> (u32, u32) {
+        self.steps.iter().fold((width, height), |dims, step| match *step {
+            Step::Resize(w, h) => (w, h),
+            _ => dims,
+        })
+    }
+
+    /// Shape of the inputs made of `width`x`height` images: rows and columns, and channels if there are several.
+    pub fn shape(&self, width: u32, height: u32) -> Vec<usize> {
+        let (w, h) = self.output_dims(width, height);
+        match self.color {
+            Color::Gray => vec![h as usize, w as usize],
+            Color::Rgb => vec![h as usize, w as usize, 3],
+        }
+    }
+}
+
+/// Parses comma-separated steps: `gray` or `rgb` for the channels (gray if neither is given), `resize=WxH`, `invert`,
+/// `crop`, `center`, `deskew`, `minmax` and `zscore`. `none` is no steps at all.
+impl FromStr for Pipeline {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Pipeline> {
+        let mut pipeline = Pipeline { color: Color::Gray, steps: Vec::new() };
+        for step in s.split(',').map(|s| s.trim()).filter(|s| !s.is_empty() && *s != "none") {
+            let step = match step {
+                "gray" => { pipeline.color = Color::Gray; continue }
+                "rgb" => { pipeline.color = Color::Rgb; continue }
+                "invert" => Step::Invert,
+                "crop" => Step::Crop,
+                "center" => Step::Center,
+                "deskew" => Step::Deskew,
+                "minmax" => Step::MinMax,
+                "zscore" => Step::ZScore,
+                s if s.starts_with("resize=") => {
+                    let dims: Vec<u32> = s["resize=".len()..].split('x').filter_map(|d| d.parse().ok()).collect();
+                    if dims.len() != 2 || dims.contains(&0) {
+                        return Err(Error::argument("preprocessing", format!("{:?} is not resize=WIDTHxHEIGHT", s)));
+                    }
+                    Step::Resize(dims[0], dims[1])
+                }
+                s => return Err(Error::argument("preprocessing", format!("unknown step {:?}", s))),
+            };
+            pipeline.steps.push(step);
+        }
+        Ok(pipeline)
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self.color { Color::Gray => "gray", Color::Rgb => "rgb" })?;
+        for step in &self.steps {
+            match *step {
+                Step::Resize(w, h) => write!(f, ",resize={}x{}", w, h)?,
+                Step::Invert => write!(f, ",invert")?,
+                Step::Crop => write!(f, ",crop")?,
+                Step::Center => write!(f, ",center")?,
+                Step::Deskew => write!(f, ",deskew")?,
+                Step::MinMax => write!(f, ",minmax")?,
+                Step::ZScore => write!(f, ",zscore")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let pipeline: Pipeline = "gray, resize=4x2, crop, invert".parse().unwrap();
+    assert_eq!(pipeline.steps, vec![Step::Resize(4, 2), Step::Crop, Step::Invert]);
+    assert_eq!(pipeline.to_string().parse::<Pipeline>().unwrap(), pipeline);
+    assert_eq!(pipeline.shape(28, 28), vec![2, 4]);
+    assert!("resize=4".parse::<Pipeline>().is_err());
+
+    // a dark dot in the top left corner of a white 4x4 image
+    let mut pixels = vec![1.0; 16];
+    pixels[5] = 0.0;
+    let image = Image::gray(4, 4, pixels).unwrap();
+    let centered = Pipeline { color: Color::Gray, steps: vec![Step::Center, Step::Invert] }.process(image.clone());
+    assert_eq!(centered.center_of_mass(&centered.pixels), Some((1.5, 1.5)));
+    let resized = Pipeline { color: Color::Gray, steps: vec![Step::Resize(2, 2)] }.process(image);
+    assert_eq!(resized.pixels.len(), 4);
+}
@@ -0,0 +1,173 @@
+//! Regression: nets whose outputs are the real values of targets rather than scores of labels.
+
+use std::path::Path;
+use dataset::{self, Dataset};
+use metrics;
+use multilayer_perceptron::{NetFile, Task};
+use error::{Error, Result};
+
+/// A trained net together with the names of the targets it outputs.
+#[derive(Clone, Debug)]
+pub struct Regressor {
+    net: NetFile,
+    targets: Vec<String>,
+}
+
+impl Regressor {
+    /// Wraps `net`, which fails for nets doing another task.
+    pub fn new(net: NetFile) -> Result<Regressor> {
+        let targets = match net.task {
+            Task::Regression(ref targets) => targets.clone(),
+            ref task => return Err(Error::argument("net", format!("the net is a {}, not a regressor", task.name()))),
+        };
+        Ok(Regressor { net: net, targets: targets })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Regressor> {
+        let net = NetFile::load(&path)?;
+        if let Task::Regression(..) = net.task {
+            return Regressor::new(net);
+        }
+        Err(Error::net(path, format!("the net is a {}, not a regressor", net.task.name())))
+    }
+
+    pub fn net(&self) -> &NetFile {
+        &self.net
+    }
+
+    /// Names of the outputs, in order.
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Length of the inputs the regressor accepts.
+    pub fn num_inputs(&self) -> usize {
+        self.net.num_inputs()
+    }
+
+    /// The predicted value of every target for `input`.
+    pub fn predict(&self, input: &[f64]) -> Result<Vec<f64>> {
+        if input.len() != self.num_inputs() {
+            return Err(Error::shape("regressor input", self.num_inputs(), input.len()));
+        }
+        Ok(self.net.net.feed_forward(input).0.at)
+    }
+}
+
+/// How far the predictions of a target are from the truth.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TargetMetrics {
+    pub name: String,
+    /// Mean absolute error.
+    pub mae: f64,
+    /// Root of the mean squared error.
+    pub rmse: f64,
+    /// Coefficient of determination: 1 for perfect predictions, 0 for always predicting the mean.
+    pub r2: f64,
+}
+
+impl TargetMetrics {
+    fn new(name: &str, predicted: &[f64], truth: &[f64]) -> TargetMetrics {
+        let n = truth.len() as f64;
+        let mean = truth.iter().sum::<f64>() / n;
+        let absolute: f64 = predicted.iter().zip(truth).map(|(p, t)| (p - t).abs()).sum();
+        let squared: f64 = predicted.iter().zip(truth).map(|(p, t)| (p - t) * (p - t)).sum();
+        let total: f64 = truth.iter().map(|t| (t - mean) * (t - mean)).sum();
+        TargetMetrics {
+            name: name.into(),
+            mae: absolute / n,
+            rmse: (squared / n).sqrt(),
+            // constant targets are explained perfectly or not at all
+            r2: if total > 0.0 { 1.0 - squared / total } else if squared == 0.0 { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+/// Evaluation of a regressor on labelled examples.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RegressionMetrics {
+    /// Number of examples.
+    pub total: usize,
+    /// Mean absolute error over all the targets.
+    pub mae: f64,
+    /// Root of the mean squared error over all the targets.
+    pub rmse: f64,
+    /// Mean of the coefficients of determination of the targets.
+    pub r2: f64,
+    pub per_target: Vec<TargetMetrics>,
+}
+
+impl RegressionMetrics {
+    /// Compares the `predictions` of the targets `names` to the `truths`, both one vector per example.
+    pub fn new(names: &[String], predictions: &[Vec<f64>], truths: &[Vec<f64>]) -> RegressionMetrics {
+        let per_target: Vec<TargetMetrics> = names.iter().enumerate().map(|(j, name)| {
+            let predicted: Vec<f64> = predictions.iter().map(|p| p[j]).collect();
+            let truth: Vec<f64> = truths.iter().map(|t| t[j]).collect();
+            TargetMetrics::new(name, &predicted, &truth)
+        }).collect();
+
+        let targets = per_target.len() as f64;
+        RegressionMetrics {
+            total: truths.len(),
+            mae: per_target.iter().map(|t| t.mae).sum::<f64>() / targets,
+            rmse: (per_target.iter().map(|t| t.rmse * t.rmse).sum::<f64>() / targets).sqrt(),
+            r2: per_target.iter().map(|t| t.r2).sum::<f64>() / targets,
+            per_target: per_target,
+        }
+    }
+}
+
+/// Predicts the targets of all the examples of `data`, a chunk at a time, and compares them to the truth.
+pub fn evaluate<D: Dataset + ?Sized>(regressor: &Regressor, data: &D) -> Result<RegressionMetrics> {
+    if data.is_empty() {
+        return Err(Error::EmptyDataset { source: "evaluation set".into() });
+    }
+    if data.target_names() != regressor.targets() {
+        return Err(Error::argument("dataset", format!("the net outputs the targets {:?}, not {:?}",
+                                                      regressor.targets(), data.target_names())));
+    }
+
+    let mut predictions = Vec::with_capacity(data.len());
+    let mut truths = Vec::with_capacity(data.len());
+    for chunk in metrics::chunks(data) {
+        for (input, _) in dataset::load_batch(data, &chunk)? {
+            predictions.push(regressor.predict(&input)?);
+        }
+        truths.extend(dataset::load_targets(data, &chunk)?);
+    }
+    Ok(RegressionMetrics::new(regressor.targets(), &predictions, &truths))
+}
+
+/// Root of the mean squared error of the regressor `net` over all the targets of `data`.
+pub fn rmse<D: Dataset + ?Sized>(net: &NetFile, data: &D) -> Result<f64> {
+    if data.input_len() != net.num_inputs() {
+        return Err(Error::shape("evaluation set", net.num_inputs(), data.input_len()));
+    }
+    let mut squared = 0.0;
+    let mut count = 0;
+    for chunk in metrics::chunks(data) {
+        let examples = dataset::load_batch(data, &chunk)?;
+        for ((input, _), target) in examples.into_iter().zip(dataset::load_targets(data, &chunk)?) {
+            let output = net.net.feed_forward(&input).0.at;
+            if output.len() != target.len() {
+                return Err(Error::shape("regression targets", output.len(), target.len()));
+            }
+            squared += output.iter().zip(&target).map(|(o, t)| (o - t) * (o - t)).sum::<f64>();
+            count += target.len();
+        }
+    }
+    Ok((squared / count as f64).sqrt())
+}
+
+#[test]
+fn test_regression_metrics() {
+    let names = vec!["y".to_string()];
+    let truths = vec![vec![1.0], vec![2.0], vec![3.0]];
+    let perfect = RegressionMetrics::new(&names, &truths, &truths);
+    assert_eq!((perfect.mae, perfect.rmse, perfect.r2), (0.0, 0.0, 1.0));
+
+    let mean = RegressionMetrics::new(&names, &vec![vec![2.0]; 3], &truths);
+    assert!((mean.mae - 2.0 / 3.0).abs() < 1e-12);
+    assert!((mean.rmse - (2.0f64 / 3.0).sqrt()).abs() < 1e-12);
+    assert_eq!(mean.r2, 0.0);
+}
@@ -0,0 +1,149 @@
+//! Open-set rejection: answering "unknown" instead of a label the classifier isn't sure about.
+
+use std::cmp::{max, Ordering};
+use std::str::FromStr;
+use inference::Ranking;
+use metrics::Outcome;
+use error::{Error, Result};
+
+/// What a classification has to exceed to be accepted.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum RejectRule {
+    /// Minimal score of the best label.
+    Confidence(f64),
+    /// Minimal difference between the scores of the two best labels.
+    Margin(f64),
+}
+
+/// The measure a `RejectRule` thresholds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Measure {
+    Confidence,
+    Margin,
+}
+
+impl FromStr for Measure {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Measure> {
+        match s {
+            "confidence" => Ok(Measure::Confidence),
+            "margin" => Ok(Measure::Margin),
+            _ => Err(Error::argument("reject measure", format!("unknown measure {:?}", s))),
+        }
+    }
+}
+
+impl Measure {
+    pub fn of(&self, ranking: &Ranking) -> f64 {
+        let best = ranking.get(0).map(|r| r.1).unwrap_or(0.0);
+        match *self {
+            Measure::Confidence => best,
+            Measure::Margin => best - ranking.get(1).map(|r| r.1).unwrap_or(0.0),
+        }
+    }
+
+    pub fn rule(&self, threshold: f64) -> RejectRule {
+        match *self {
+            Measure::Confidence => RejectRule::Confidence(threshold),
+            Measure::Margin => RejectRule::Margin(threshold),
+        }
+    }
+}
+
+impl RejectRule {
+    pub fn measure(&self) -> Measure {
+        match *self {
+            RejectRule::Confidence(_) => Measure::Confidence,
+            RejectRule::Margin(_) => Measure::Margin,
+        }
+    }
+
+    pub fn threshold(&self) -> f64 {
+        match *self {
+            RejectRule::Confidence(t) | RejectRule::Margin(t) => t,
+        }
+    }
+
+    pub fn accepts(&self, ranking: &Ranking) -> bool {
+        self.measure().of(ranking) >= self.threshold()
+    }
+}
+
+/// Measures of `outcomes` paired with their correctness, the most certain first.
+fn sorted_measures(outcomes: &[Outcome], measure: Measure) -> Vec<(f64, bool)> {
+    let mut measures: Vec<(f64, bool)> = outcomes.iter()
+        .map(|o| (measure.of(&o.ranking), o.is_correct()))
+        .collect();
+    measures.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    measures
+}
+
+/// The strictest rule that still accepts at least `coverage` of `outcomes`.
+pub fn tune(outcomes: &[Outcome], measure: Measure, coverage: f64) -> Result<RejectRule> {
+    if coverage <= 0.0 || coverage > 1.0 {
+        return Err(Error::argument("coverage", format!("{} is not in (0, 1]", coverage)));
+    }
+    let measures = sorted_measures(outcomes, measure);
+    if measures.is_empty() {
+        return Err(Error::EmptyDataset { source: "rejection tuning set".into() });
+    }
+    let accepted = max((coverage * measures.len() as f64).ceil() as usize, 1);
+    Ok(measure.rule(measures[accepted - 1].0))
+}
+
+/// Accuracy of the accepted outcomes when only the `coverage` most certain ones are accepted.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CoveragePoint {
+    pub coverage: f64,
+    /// The threshold that accepts `coverage` of the outcomes.
+    pub threshold: f64,
+    pub accuracy: f64,
+}
+
+/// Accuracy vs coverage at `points` evenly spaced coverages.
+pub fn coverage_curve(outcomes: &[Outcome], measure: Measure, points: usize) -> Vec<CoveragePoint> {
+    let measures = sorted_measures(outcomes, measure);
+    if measures.is_empty() {
+        return Vec::new();
+    }
+
+    (1..points + 1).map(|p| {
+        let coverage = p as f64 / points as f64;
+        let accepted = max((coverage * measures.len() as f64).ceil() as usize, 1);
+        let correct = measures[..accepted].iter().filter(|m| m.1).count();
+        CoveragePoint {
+            coverage: accepted as f64 / measures.len() as f64,
+            threshold: measures[accepted - 1].0,
+            accuracy: correct as f64 / accepted as f64,
+        }
+    }).collect()
+}
+
+/// Coverage and accuracy of the outcomes accepted by `rule`.
+pub fn apply(outcomes: &[Outcome], rule: &RejectRule) -> CoveragePoint {
+    let accepted: Vec<&Outcome> = outcomes.iter().filter(|o| rule.accepts(&o.ranking)).collect();
+    let correct = accepted.iter().filter(|o| o.is_correct()).count();
+    CoveragePoint {
+        coverage: if outcomes.is_empty() { 0.0 } else { accepted.len() as f64 / outcomes.len() as f64 },
+        threshold: rule.threshold(),
+        accuracy: if accepted.is_empty() { 0.0 } else { correct as f64 / accepted.len() as f64 },
+    }
+}
+
+#[test]
+fn test_tune() {
+    let outcome = |confidence: f64, correct: bool| Outcome {
+        truth: "a".into(),
+        ranking: vec![((if correct { "a" } else { "b" }).into(), confidence), ("c".into(), 1.0 - confidence)],
+    };
+    let outcomes = [outcome(0.9, true), outcome(0.8, true), outcome(0.6, false), outcome(0.5, true)];
+
+    let rule = tune(&outcomes, Measure::Confidence, 0.5).unwrap();
+    assert_eq!(rule, RejectRule::Confidence(0.8));
+    assert_eq!(apply(&outcomes, &rule).accuracy, 1.0);
+
+    let curve = coverage_curve(&outcomes, Measure::Confidence, 4);
+    assert_eq!(curve[2].accuracy, 2.0 / 3.0);
+    assert_eq!(curve[3].coverage, 1.0);
+}
@@ -0,0 +1,203 @@
+//! Run directories, which keep everything needed to compare learning runs after the fact:
+//!
+//! * `manifest.json`: what was learnt, with which settings and seed, on which datasets, when and by which version,
+//! * `metrics.csv` and `metrics.jsonl`: statistics of every epoch,
+//! * `final.net` and `best.net`: the net after the last epoch and the best one according to the run's score.
+
+use serde::Serialize;
+use serde_json::{self, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use multilayer_perceptron::NetFile;
+use trainer::EpochStats;
+use error::{Error, Result};
+
+pub const MANIFEST: &'static str = "manifest.json";
+pub const METRICS_CSV: &'static str = "metrics.csv";
+pub const METRICS_JSONL: &'static str = "metrics.jsonl";
+pub const FINAL_NET: &'static str = "final.net";
+pub const BEST_NET: &'static str = "best.net";
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `runs/KIND-STARTED_AT`, where runs go if not told otherwise.
+pub fn default_path(manifest: &Manifest) -> PathBuf {
+    Path::new("runs").join(format!("{}-{}", manifest.kind, manifest.started_at))
+}
+
+/// A dataset used by a run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DatasetInfo {
+    /// What the dataset was used for, like `learning` or `validation`.
+    pub role: String,
+    pub source: String,
+    pub size: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// What was learnt: `classifier` or `autoencoder`.
+    pub kind: String,
+    pub config: Value,
+    pub seed: usize,
+    pub datasets: Vec<DatasetInfo>,
+    /// Version of mulperc that did the run.
+    pub version: String,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    /// Seconds since the Unix epoch, `None` while the run goes on or if it failed.
+    pub finished_at: Option<u64>,
+}
+
+impl Manifest {
+    /// Manifest of a run starting now, without datasets.
+    pub fn new<C: Serialize>(kind: &str, config: &C, seed: usize) -> Manifest {
+        Manifest {
+            kind: kind.into(),
+            config: serde_json::to_value(config),
+            seed: seed,
+            datasets: Vec::new(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            started_at: now(),
+            finished_at: None,
+        }
+    }
+
+    pub fn add_dataset<S: Into<String>>(&mut self, role: &str, source: S, size: usize) {
+        self.datasets.push(DatasetInfo { role: role.into(), source: source.into(), size: size });
+    }
+}
+
+/// A line of the metrics log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EpochRecord {
+    pub epoch: u64,
+    /// Loss of the epoch's batch.
+    pub loss: f64,
+    pub train_accuracy: Option<f64>,
+    pub validation_accuracy: Option<f64>,
+    pub learning_rate: f64,
+    pub gradient_norm: f64,
+}
+
+impl EpochRecord {
+    /// Record of the epoch described by `stats`, without accuracies.
+    pub fn new(stats: &EpochStats) -> EpochRecord {
+        EpochRecord {
+            epoch: stats.epoch,
+            loss: stats.batch.loss,
+            train_accuracy: None,
+            validation_accuracy: None,
+            learning_rate: stats.learning_rate,
+            gradient_norm: stats.batch.gradient_norm,
+        }
+    }
+
+    fn csv_header() -> &'static str {
+        "epoch,loss,train_accuracy,validation_accuracy,learning_rate,gradient_norm"
+    }
+
+    fn to_csv(&self) -> String {
+        let optional = |x: Option<f64>| x.map(|x| x.to_string()).unwrap_or(String::new());
+        format!("{},{},{},{},{},{}", self.epoch, self.loss, optional(self.train_accuracy),
+                optional(self.validation_accuracy), self.learning_rate, self.gradient_norm)
+    }
+}
+
+/// Reads the metrics log of the run in `dir`.
+pub fn read_metrics<P: AsRef<Path>>(dir: P) -> Result<Vec<EpochRecord>> {
+    use std::io::{BufRead, BufReader};
+    let path = dir.as_ref().join(METRICS_JSONL);
+    let file = File::open(&path).map_err(|e| Error::io(&path, e))?;
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| Error::io(&path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line).map_err(|e| Error::record(&path, i, e.to_string()))?);
+    }
+    Ok(records)
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    File::create(path).and_then(|mut f| f.write_all(contents)).map_err(|e| Error::io(path, e))
+}
+
+pub struct RunDir {
+    path: PathBuf,
+    manifest: Manifest,
+    csv: File,
+    jsonl: File,
+    best_score: Option<f64>,
+}
+
+impl RunDir {
+    /// Creates the directory at `path` with the manifest and empty metrics logs in it.
+    pub fn create<P: AsRef<Path>>(path: P, manifest: Manifest) -> Result<RunDir> {
+        let path = path.as_ref().to_path_buf();
+        fs::create_dir_all(&path).map_err(|e| Error::io(&path, e))?;
+
+        let open = |name: &str| {
+            let file = path.join(name);
+            File::create(&file).map_err(|e| Error::io(&file, e))
+        };
+        let mut csv = open(METRICS_CSV)?;
+        writeln!(csv, "{}", EpochRecord::csv_header()).map_err(|e| Error::io(path.join(METRICS_CSV), e))?;
+        let jsonl = open(METRICS_JSONL)?;
+
+        let run = RunDir {
+            path: path.clone(),
+            manifest: manifest,
+            csv: csv,
+            jsonl: jsonl,
+            best_score: None,
+        };
+        run.write_manifest()?;
+        Ok(run)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    fn write_manifest(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.manifest).unwrap();
+        write_file(&self.path.join(MANIFEST), json.as_bytes())
+    }
+
+    /// Appends `record` to the metrics logs.
+    pub fn log(&mut self, record: &EpochRecord) -> Result<()> {
+        writeln!(self.csv, "{}", record.to_csv()).map_err(|e| Error::io(self.path.join(METRICS_CSV), e))?;
+        writeln!(self.jsonl, "{}", serde_json::to_string(record).unwrap())
+            .map_err(|e| Error::io(self.path.join(METRICS_JSONL), e))
+    }
+
+    /// Whether `score` is higher than the score of the best net so far.
+    pub fn improves(&self, score: f64) -> bool {
+        self.best_score.map(|best| score > best).unwrap_or(true)
+    }
+
+    /// Saves `net`, which scored `score`, as the best one.
+    pub fn save_best(&mut self, score: f64, net: &NetFile) -> Result<()> {
+        net.save(self.path.join(BEST_NET))?;
+        self.best_score = Some(score);
+        Ok(())
+    }
+
+    /// Saves the final `net` and the end time of the run.
+    pub fn finish(mut self, net: &NetFile) -> Result<()> {
+        net.save(self.path.join(FINAL_NET))?;
+        self.manifest.finished_at = Some(now());
+        self.write_manifest()
+    }
+}
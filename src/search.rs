@@ -0,0 +1,314 @@
+//! Hyperparameter search: grid and random search, optionally with successive halving.
+
+use std::cmp::{max, Ordering};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use rand::Rng;
+use rayon::prelude::*;
+use autoencoder::{self, AutoencoderConfig};
+use dataset::Dataset;
+use metrics;
+use multilayer_perceptron::{MultilayerPerceptron, NetFile};
+use trainer::{Trainer, TrainConfig};
+use util;
+use error::{Error, Result};
+
+/// Stream of the seed of a search the random candidates are drawn from, the trainer drawing from the ones before it.
+pub const CANDIDATES_STREAM: usize = 3;
+
+/// Values of the searched parameters, by name.
+pub type Params = BTreeMap<String, f64>;
+
+/// The values a parameter can take.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Values {
+    /// Exactly these values.
+    List(Vec<f64>),
+    /// Uniformly distributed in `[min, max]`, or log-uniformly if `log` is set.
+    Range { min: f64, max: f64, log: bool },
+}
+
+impl FromStr for Values {
+    type Err = Error;
+
+    /// Parses `a,b,c` as a list, `min..max` as a range and `min..max:log` as a logarithmic one.
+    fn from_str(s: &str) -> Result<Values> {
+        let parse = |x: &str| x.trim().parse::<f64>()
+            .map_err(|_| Error::argument("search space", format!("{:?} is not a number", x)));
+
+        if let Some(i) = s.find("..") {
+            let (range, log) = if s.ends_with(":log") { (&s[..s.len() - 4], true) } else { (s, false) };
+            let (min, max) = (parse(&range[..i])?, parse(&range[i + 2..])?);
+            if min > max || (log && min <= 0.0) {
+                return Err(Error::argument("search space", format!("{:?} is not a valid range", s)));
+            }
+            return Ok(Values::Range { min: min, max: max, log: log });
+        }
+
+        let list = s.split(',').map(parse).collect::<Result<Vec<f64>>>()?;
+        Ok(Values::List(list))
+    }
+}
+
+impl Values {
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        match *self {
+            Values::List(ref values) => values[rng.gen_range(0, values.len())],
+            Values::Range { min, max, log: false } => min + rng.gen::<f64>() * (max - min),
+            Values::Range { min, max, log: true } => (min.ln() + rng.gen::<f64>() * (max.ln() - min.ln())).exp(),
+        }
+    }
+}
+
+/// The searched parameters and their values.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Space {
+    pub params: Vec<(String, Values)>,
+}
+
+impl Space {
+    pub fn new() -> Space {
+        Space { params: Vec::new() }
+    }
+
+    pub fn add<S: Into<String>>(&mut self, name: S, values: Values) {
+        self.params.push((name.into(), values));
+    }
+
+    /// Every combination of the values of the parameters, which all have to be lists.
+    pub fn grid(&self) -> Result<Vec<Params>> {
+        let mut grid = vec![Params::new()];
+        for &(ref name, ref values) in &self.params {
+            let values = match *values {
+                Values::List(ref values) => values,
+                Values::Range { .. } => return Err(Error::argument(
+                    "search space", format!("grid search needs a list of values of {}, not a range", name))),
+            };
+            grid = grid.into_iter()
+                .flat_map(|params| values.iter().map(move |&v| {
+                    let mut params = params.clone();
+                    params.insert(name.clone(), v);
+                    params
+                }))
+                .collect();
+        }
+        Ok(grid)
+    }
+
+    /// `n` independently sampled combinations of the values of the parameters.
+    pub fn random<R: Rng>(&self, n: usize, rng: &mut R) -> Vec<Params> {
+        (0..n).map(|_| self.params.iter().map(|&(ref name, ref values)| (name.clone(), values.sample(rng))).collect())
+            .collect()
+    }
+}
+
+/// What is being tuned: how to create and learn models with the given parameters, and how good they are.
+pub trait Objective: Sync {
+    type Model: Send;
+
+    fn create(&self, params: &Params) -> Result<Self::Model>;
+
+    /// Learns `model` for `epochs` more epochs during the rung `rung` of the search and scores it, the higher the
+    /// better.
+    fn train(&self, params: &Params, model: &mut Self::Model, epochs: u64, rung: usize) -> Result<f64>;
+}
+
+/// Parameters of a model and its score after being learnt for `epochs` epochs.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Trial {
+    pub id: usize,
+    pub params: Params,
+    pub epochs: u64,
+    pub score: f64,
+}
+
+/// How long the trials learn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// Epochs of the trials that learn the longest.
+    pub epochs: u64,
+    /// If set, all the trials learn for a fraction of `epochs`, then only the best `1 / halving` of them go on for
+    /// `halving` times longer, and so on, until the survivors have learnt for `epochs` epochs.
+    pub halving: Option<usize>,
+}
+
+impl Schedule {
+    /// The epochs after which `trials` trials are scored and the worst of them dropped.
+    fn rungs(&self, trials: usize) -> Vec<u64> {
+        let eta = match self.halving {
+            Some(eta) if eta > 1 => eta,
+            _ => return vec![self.epochs],
+        };
+        let mut count = 0;
+        let mut left = trials;
+        while left >= eta {
+            left /= eta;
+            count += 1;
+        }
+        (0..count + 1).rev().map(|r| max(self.epochs / (eta as u64).pow(r), 1)).collect()
+    }
+}
+
+/// The seed of the learning during the rung `rung` of a search seeded with `seed`, so that every rung draws other
+/// samples than the ones before it.
+pub fn rung_seed(seed: Option<usize>, rung: usize) -> Option<usize> {
+    seed.map(|seed| util::rng(Some(seed), CANDIDATES_STREAM + 1 + rung).gen())
+}
+
+/// Learns and scores a model for every set of `candidates` in parallel, according to `schedule`.
+///
+/// Returns the leaderboard, the best trial first, and the best model.
+pub fn search<O: Objective>(objective: &O, candidates: Vec<Params>, schedule: &Schedule) -> Result<(Vec<Trial>, O::Model)> {
+    if candidates.is_empty() {
+        return Err(Error::argument("search space", "no candidates to try"));
+    }
+
+    let rungs = schedule.rungs(candidates.len());
+    let mut alive = Vec::with_capacity(candidates.len());
+    for (id, params) in candidates.into_iter().enumerate() {
+        let model = objective.create(&params)?;
+        alive.push((Trial { id: id, params: params, epochs: 0, score: ::std::f64::NEG_INFINITY }, model));
+    }
+
+    let mut dropped = Vec::new();
+    for (r, &epochs) in rungs.iter().enumerate() {
+        let mut results = Vec::with_capacity(alive.len());
+        alive.par_iter_mut().map(|&mut (ref mut trial, ref mut model)| -> Result<()> {
+            trial.score = objective.train(&trial.params, model, epochs - trial.epochs, r)?;
+            trial.epochs = epochs;
+            Ok(())
+        }).collect_into(&mut results);
+        results.into_iter().collect::<Result<Vec<()>>>()?;
+
+        alive.sort_by(|a, b| b.0.score.partial_cmp(&a.0.score).unwrap_or(Ordering::Equal));
+        if r + 1 < rungs.len() {
+            let keep = max(alive.len() / schedule.halving.unwrap_or(1), 1);
+            // the trials dropped later learnt longer, so they rank higher
+            let mut worse: Vec<Trial> = alive.drain(keep..).map(|(trial, _)| trial).collect();
+            worse.extend(dropped);
+            dropped = worse;
+        }
+    }
+
+    let mut survivors = alive.into_iter();
+    let (best, model) = survivors.next().unwrap();
+    let mut leaderboard = vec![best];
+    leaderboard.extend(survivors.map(|(trial, _)| trial));
+    leaderboard.extend(dropped);
+    Ok((leaderboard, model))
+}
+
+fn unknown_param(model: &str, name: &str) -> Error {
+    Error::argument("search space", format!("{} have no parameter {:?}", model, name))
+}
+
+/// Tunes the settings of classifiers by their accuracy on the validation examples.
+pub struct ClassifierSearch<'a> {
+    /// Settings of the parameters that aren't searched.
+    pub config: TrainConfig,
+    pub train: &'a Dataset,
+    pub validation: &'a Dataset,
+}
+
+impl<'a> ClassifierSearch<'a> {
+    /// `config` with the searched parameters set: `learning-rate`, `hidden-neurons` and `sample-ratio`.
+    pub fn config(&self, params: &Params) -> Result<TrainConfig> {
+        let mut config = self.config.clone();
+        for (name, &value) in params {
+            match name.as_str() {
+                "learning-rate" => config.learning_rate = value,
+                "hidden-neurons" => config.hidden_neurons = value.round() as usize,
+                "sample-ratio" => config.sample_ratio = value,
+                _ => return Err(unknown_param("classifiers", name)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl<'a> Objective for ClassifierSearch<'a> {
+    type Model = NetFile;
+
+    fn create(&self, params: &Params) -> Result<NetFile> {
+        Trainer::new(self.config(params)?).new_net(self.train)
+    }
+
+    fn train(&self, params: &Params, net: &mut NetFile, epochs: u64, rung: usize) -> Result<f64> {
+        let seed = rung_seed(self.config.seed, rung);
+        let config = TrainConfig { max_epochs: epochs, seed: seed, ..self.config(params)? };
+        Trainer::new(config).train(net, self.train, |_, _| Ok(()))?;
+        metrics::accuracy(net, self.validation)
+    }
+}
+
+/// Tunes the settings of autoencoders by their negated reconstruction error on the validation images.
+pub struct AutoencoderSearch<'a> {
+    /// Settings of the parameters that aren't searched.
+    pub config: AutoencoderConfig,
+    pub train: &'a Dataset,
+    pub validation: &'a Dataset,
+}
+
+impl<'a> AutoencoderSearch<'a> {
+    /// `config` with the searched parameters set: `learning-rate`, `hidden-neurons`, `sample-ratio`, `sparsity`
+    /// and `penalty-factor`.
+    pub fn config(&self, params: &Params) -> Result<AutoencoderConfig> {
+        let mut config = self.config.clone();
+        for (name, &value) in params {
+            match name.as_str() {
+                "learning-rate" => config.learning_rate = value,
+                "hidden-neurons" => config.hidden_neurons = value.round() as usize,
+                "sample-ratio" => config.sample_ratio = value,
+                "sparsity" => config.sparsity = value,
+                "penalty-factor" => config.penalty_factor = value,
+                _ => return Err(unknown_param("autoencoders", name)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl<'a> Objective for AutoencoderSearch<'a> {
+    type Model = MultilayerPerceptron;
+
+    fn create(&self, params: &Params) -> Result<MultilayerPerceptron> {
+        if self.train.is_empty() {
+            return Err(Error::EmptyDataset { source: "training set".into() });
+        }
+        Ok(autoencoder::new_net(&self.config(params)?, self.train.input_len()))
+    }
+
+    fn train(&self, params: &Params, net: &mut MultilayerPerceptron, epochs: u64, rung: usize) -> Result<f64> {
+        let seed = rung_seed(self.config.seed, rung);
+        let config = AutoencoderConfig { epochs: epochs, seed: seed, ..self.config(params)? };
+        autoencoder::train(&config, net, self.train, |_, _| Ok(()))?;
+        Ok(-autoencoder::reconstruction_error(net, self.validation)?)
+    }
+}
+
+#[test]
+fn test_space() {
+    let mut space = Space::new();
+    space.add("a", "1,2,3".parse().unwrap());
+    space.add("b", "0.5,1".parse().unwrap());
+    assert_eq!(space.grid().unwrap().len(), 6);
+
+    space.add("c", "0.001..1:log".parse().unwrap());
+    assert!(space.grid().is_err());
+    let candidates = space.random(10, &mut util::rng(Some(1), CANDIDATES_STREAM));
+    for params in &candidates {
+        assert!(params["c"] >= 0.001 && params["c"] <= 1.0);
+    }
+    // a seeded search tries the same candidates again
+    assert_eq!(space.random(10, &mut util::rng(Some(1), CANDIDATES_STREAM)), candidates);
+}
+
+#[test]
+fn test_rungs() {
+    let schedule = Schedule { epochs: 81, halving: Some(3) };
+    assert_eq!(schedule.rungs(27), vec![3, 9, 27, 81]);
+    assert_eq!(schedule.rungs(2), vec![81]);
+    assert_eq!(Schedule { epochs: 81, halving: None }.rungs(27), vec![81]);
+    assert!(rung_seed(Some(1), 0) != rung_seed(Some(1), 1));
+    assert_eq!(rung_seed(None, 0), None);
+}
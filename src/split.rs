@@ -0,0 +1,182 @@
+//! Splitting datasets for validation, into folds or into train, validation and test portions, which can be written
+//! to index files that datasets are opened with.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use rand::Rng;
+use dataset::Dataset;
+use util;
+use error::{Error, Result};
+
+/// Names of the portions `stratified_group_split` divides datasets into, in order.
+pub const PORTIONS: &'static [&'static str] = &["train", "validation", "test"];
+
+/// Assigns every example to one of `k` folds, so that all the examples of a group land in the same fold and
+/// every label is spread across the folds as evenly as the groups allow.
+///
+/// `labels[i]` and `groups[i]` are the label and the group of the i-th example. Returns the fold of every example.
+pub fn stratified_group_folds<S: AsRef<str>, G: AsRef<str>>(labels: &[S], groups: &[G], k: usize) -> Result<Vec<usize>> {
+    if labels.len() != groups.len() {
+        return Err(Error::shape("groups of the examples", labels.len(), groups.len()));
+    }
+    if k < 2 {
+        return Err(Error::argument("folds", format!("{} is less than 2 folds", k)));
+    }
+
+    let (mut ordered, totals) = count_groups(labels, groups);
+    if ordered.len() < k {
+        return Err(Error::argument("folds", format!("{} groups can't fill {} folds", ordered.len(), k)));
+    }
+
+    // the biggest groups first, while there's still room to balance the small ones around them
+    ordered.sort_by(|a, b| match b.1.iter().sum::<usize>().cmp(&a.1.iter().sum::<usize>()) {
+        Ordering::Equal => a.0.cmp(b.0),
+        other => other,
+    });
+
+    let mut fold_counts = vec![vec![0; totals.len()]; k];
+    let mut fold_of_group = BTreeMap::new();
+    for (group, counts) in ordered {
+        // the fold whose label shares grow the least, the smallest one on ties
+        let cost = |fold: &[usize]| -> f64 {
+            fold.iter().zip(&counts).zip(&totals)
+                .map(|((&f, &c), &t)| { let share = (f + c) as f64 / t as f64; share * share })
+                .sum()
+        };
+        let key = |fold: &[usize]| (cost(fold), fold.iter().sum::<usize>());
+        let best = (1..k).fold(0, |best, f| if key(&fold_counts[f]) < key(&fold_counts[best]) { f } else { best });
+        for (f, c) in fold_counts[best].iter_mut().zip(&counts) {
+            *f += *c;
+        }
+        fold_of_group.insert(group, best);
+    }
+
+    Ok(groups.iter().map(|g| fold_of_group[g.as_ref()]).collect())
+}
+
+/// Label counts of every group, the groups in order of their names, and the total counts of the labels.
+fn count_groups<'a, S: AsRef<str>, G: AsRef<str>>(labels: &[S], groups: &'a [G])
+                                                  -> (Vec<(&'a str, Vec<usize>)>, Vec<usize>) {
+    let label_set: BTreeSet<&str> = labels.iter().map(|l| l.as_ref()).collect();
+    let label_index: BTreeMap<&str, usize> = label_set.iter().enumerate().map(|(i, &l)| (l, i)).collect();
+    let mut totals = vec![0; label_index.len()];
+    let mut group_counts: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (label, group) in labels.iter().zip(groups) {
+        let i = label_index[label.as_ref()];
+        totals[i] += 1;
+        group_counts.entry(group.as_ref()).or_insert_with(|| vec![0; label_index.len()])[i] += 1;
+    }
+    (group_counts.into_iter().collect(), totals)
+}
+
+/// Assigns every example to a portion whose share of the examples is given by `ratios`, like `[0.7, 0.15, 0.15]`
+/// for the train, validation and test `PORTIONS`, so that all the examples of a group land in the same portion and
+/// every label is divided by the ratios as closely as the groups allow.
+///
+/// `labels[i]` and `groups[i]` are the label and the group of the i-th example, every example being a group of its
+/// own if it shares its group with no other. `seed` picks which of the equally big groups go where, randomly if not
+/// set. Returns the portion of every example.
+pub fn stratified_group_split<S, G>(labels: &[S], groups: &[G], ratios: &[f64], seed: Option<usize>)
+                                    -> Result<Vec<usize>>
+    where S: AsRef<str>, G: AsRef<str>
+{
+    if labels.len() != groups.len() {
+        return Err(Error::shape("groups of the examples", labels.len(), groups.len()));
+    }
+    let sum: f64 = ratios.iter().sum();
+    if ratios.iter().any(|&r| !(r >= 0.0)) || !(sum > 0.0) {
+        return Err(Error::argument("ratios", format!("{:?} are not non-negative ratios with a positive sum", ratios)));
+    }
+    let ratios: Vec<f64> = ratios.iter().map(|r| r / sum).collect();
+
+    let (mut ordered, totals) = count_groups(labels, groups);
+    // the biggest groups first, the equally big ones in random order
+    util::rng(seed, 0).shuffle(&mut ordered);
+    ordered.sort_by(|a, b| b.1.iter().sum::<usize>().cmp(&a.1.iter().sum::<usize>()));
+
+    let mut portion_counts = vec![vec![0; totals.len()]; ratios.len()];
+    let mut portion_of_group = BTreeMap::new();
+    for (group, counts) in ordered {
+        // the portion furthest below its share of the labels of the group, the first one on ties
+        let mut best: Option<(usize, f64)> = None;
+        for p in (0..ratios.len()).filter(|&p| ratios[p] > 0.0) {
+            let deficit: f64 = counts.iter().zip(&portion_counts[p]).zip(&totals)
+                .map(|((&c, &n), &t)| c as f64 * (ratios[p] * t as f64 - n as f64) / t as f64)
+                .sum();
+            if best.map(|(_, most)| deficit > most).unwrap_or(true) {
+                best = Some((p, deficit));
+            }
+        }
+        let best = best.unwrap().0;
+        for (n, c) in portion_counts[best].iter_mut().zip(&counts) {
+            *n += *c;
+        }
+        portion_of_group.insert(group, best);
+    }
+
+    Ok(groups.iter().map(|g| portion_of_group[g.as_ref()]).collect())
+}
+
+/// Writes the examples of `data` at `indices` to the index file `path`, as `index,label` lines under an
+/// `index,label` header. Opening the same dataset with `index=FILE` (see `dataset::Source`) gives those examples.
+pub fn write_index<P: AsRef<Path>>(path: P, data: &Dataset, indices: &[usize]) -> Result<()> {
+    let path = path.as_ref();
+    let file = File::create(path).map_err(|e| Error::io(path, e))?;
+    let mut out = BufWriter::new(file);
+    writeln!(out, "index,label").map_err(|e| Error::io(path, e))?;
+    for &i in indices {
+        writeln!(out, "{},{}", i, data.label(i)).map_err(|e| Error::io(path, e))?;
+    }
+    out.flush().map_err(|e| Error::io(path, e))
+}
+
+/// The indices and labels of the examples in the index file at `path`, written by `write_index`.
+pub fn read_index<P: AsRef<Path>>(path: P) -> Result<Vec<(usize, String)>> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| Error::io(path, e))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| Error::io(path, e))?;
+        let line = line.trim();
+        if line.is_empty() || (i == 0 && line == "index,label") {
+            continue;
+        }
+        let comma = line.find(',').ok_or_else(|| Error::record(path, i, "expected an index and a label"))?;
+        let index = line[..comma].trim().parse::<usize>()
+            .map_err(|_| Error::record(path, i, format!("{:?} is not an index", &line[..comma])))?;
+        entries.push((index, line[comma + 1..].to_string()));
+    }
+    Ok(entries)
+}
+
+#[test]
+fn test_stratified_group_folds() {
+    let labels = ["a", "a", "a", "a", "b", "b", "b", "b"];
+    let groups = ["x", "x", "y", "y", "z", "z", "w", "w"];
+    let folds = stratified_group_folds(&labels, &groups, 2).unwrap();
+
+    assert_eq!(folds[0], folds[1]);
+    assert!(folds[0] != folds[2]);
+    assert!(folds[4] != folds[6]);
+    assert!(stratified_group_folds(&labels, &groups, 5).is_err());
+}
+
+#[test]
+fn test_stratified_group_split() {
+    let labels: Vec<&str> = vec!["a"; 10].into_iter().chain(vec!["b"; 10]).collect();
+    let groups: Vec<String> = (0..20).map(|i| format!("{}", i)).collect();
+    let portions = stratified_group_split(&labels, &groups, &[0.8, 0.0, 0.2], Some(1)).unwrap();
+    for label in &["a", "b"] {
+        let count = |p| (0..20).filter(|&i| labels[i] == *label && portions[i] == p).count();
+        assert_eq!((count(0), count(1), count(2)), (8, 0, 2));
+    }
+    assert_eq!(stratified_group_split(&labels, &groups, &[0.8, 0.0, 0.2], Some(1)).unwrap(), portions);
+
+    let authors: Vec<String> = (0..20).map(|i| format!("{}", i / 2)).collect();
+    let portions = stratified_group_split(&labels, &authors, &[0.5, 0.5], None).unwrap();
+    assert!((0..10).all(|j| portions[2 * j] == portions[2 * j + 1]));
+    assert!(stratified_group_split(&labels, &authors, &[-1.0, 2.0], None).is_err());
+}
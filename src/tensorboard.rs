@@ -0,0 +1,231 @@
+//! Event files that TensorBoard can read, with scalars, histograms and images of a learning run.
+//!
+//! An event file is a sequence of TFRecords, each holding an `Event` protocol buffer. Both are simple enough to be
+//! written by hand, so TensorBoard itself isn't needed until the run is looked at.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use multilayer_perceptron::MultilayerPerceptron;
+use img;
+use error::{Error, Result};
+
+/// Number of equally wide buckets of the histograms.
+const HISTOGRAM_BUCKETS: usize = 30;
+
+/// CRC-32C (Castagnoli) of `data`.
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f6_3b78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The checksum stored in TFRecords, masked so that checksums of data containing checksums stay meaningful.
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    ((crc >> 15) | (crc << 17)).wrapping_add(0xa282_ead8)
+}
+
+fn write_u32(buf: &mut Vec<u8>, x: u32) {
+    for i in 0..4 {
+        buf.push((x >> (8 * i)) as u8);
+    }
+}
+
+fn write_u64(buf: &mut Vec<u8>, x: u64) {
+    for i in 0..8 {
+        buf.push((x >> (8 * i)) as u8);
+    }
+}
+
+/// A protocol buffer message being encoded.
+struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    fn new() -> Message {
+        Message { buf: Vec::new() }
+    }
+
+    fn varint(&mut self, mut x: u64) {
+        while x >= 0x80 {
+            self.buf.push(x as u8 | 0x80);
+            x >>= 7;
+        }
+        self.buf.push(x as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u32) {
+        self.varint((field << 3 | wire_type) as u64);
+    }
+
+    fn int(&mut self, field: u32, x: i64) {
+        self.key(field, 0);
+        self.varint(x as u64);
+    }
+
+    fn double(&mut self, field: u32, x: f64) {
+        self.key(field, 1);
+        write_u64(&mut self.buf, unsafe { mem::transmute::<f64, u64>(x) });
+    }
+
+    fn float(&mut self, field: u32, x: f32) {
+        self.key(field, 5);
+        write_u32(&mut self.buf, unsafe { mem::transmute::<f32, u32>(x) });
+    }
+
+    fn bytes(&mut self, field: u32, bytes: &[u8]) {
+        self.key(field, 2);
+        self.varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn message(&mut self, field: u32, message: Message) {
+        self.bytes(field, &message.buf);
+    }
+
+    fn packed_doubles(&mut self, field: u32, xs: &[f64]) {
+        let mut packed = Vec::with_capacity(8 * xs.len());
+        for &x in xs {
+            write_u64(&mut packed, unsafe { mem::transmute::<f64, u64>(x) });
+        }
+        self.bytes(field, &packed);
+    }
+}
+
+/// A `HistogramProto` of `values`.
+fn histogram(values: &[f64]) -> Message {
+    let min = values.iter().cloned().fold(1.0 / 0.0, f64::min);
+    let max = values.iter().cloned().fold(-1.0 / 0.0, f64::max);
+    let width = (max - min) / HISTOGRAM_BUCKETS as f64;
+
+    let mut buckets = vec![0.0; HISTOGRAM_BUCKETS];
+    for &x in values {
+        let i = if width > 0.0 { ((x - min) / width) as usize } else { 0 };
+        buckets[if i < HISTOGRAM_BUCKETS { i } else { HISTOGRAM_BUCKETS - 1 }] += 1.0;
+    }
+    let limits: Vec<f64> = (1..HISTOGRAM_BUCKETS + 1).map(|i| min + width * i as f64).collect();
+
+    let mut m = Message::new();
+    m.double(1, min);
+    m.double(2, max);
+    m.double(3, values.len() as f64);
+    m.double(4, values.iter().sum());
+    m.double(5, values.iter().map(|x| x * x).sum());
+    m.packed_doubles(6, &limits);
+    m.packed_doubles(7, &buckets);
+    m
+}
+
+/// A `Summary.Value` tagged `tag`, without its contents.
+fn tagged(tag: &str) -> Message {
+    let mut value = Message::new();
+    value.bytes(1, tag.as_bytes());
+    value
+}
+
+/// Writes an event file in a directory, which TensorBoard can then be pointed at.
+pub struct EventWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl EventWriter {
+    /// Creates a new event file in `dir`, which has to exist.
+    pub fn create<P: AsRef<Path>>(dir: P) -> Result<EventWriter> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let path = dir.as_ref().join(format!("events.out.tfevents.{}.mulperc", now));
+        let file = File::create(&path).map_err(|e| Error::io(&path, e))?;
+        let mut writer = EventWriter { path: path, file: BufWriter::new(file) };
+
+        let mut event = writer.event(0);
+        event.bytes(3, b"brain.Event:2");
+        writer.write_record(&event.buf)?;
+        Ok(writer)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// An `Event` at `step`, with the current time.
+    fn event(&self, step: u64) -> Message {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as f64 + d.subsec_nanos() as f64 * 1e-9).unwrap_or(0.0);
+        let mut event = Message::new();
+        event.double(1, now);
+        event.int(2, step as i64);
+        event
+    }
+
+    fn write_record(&mut self, data: &[u8]) -> Result<()> {
+        let mut record = Vec::with_capacity(data.len() + 16);
+        write_u64(&mut record, data.len() as u64);
+        let header_crc = masked_crc32c(&record);
+        write_u32(&mut record, header_crc);
+        record.extend_from_slice(data);
+        write_u32(&mut record, masked_crc32c(data));
+        self.file.write_all(&record).map_err(|e| Error::io(&self.path, e))
+    }
+
+    /// Writes an event at `step` with a summary of the single `value`.
+    fn write_summary(&mut self, step: u64, value: Message) -> Result<()> {
+        let mut summary = Message::new();
+        summary.message(1, value);
+        let mut event = self.event(step);
+        event.message(5, summary);
+        self.write_record(&event.buf)
+    }
+
+    pub fn scalar(&mut self, tag: &str, step: u64, x: f64) -> Result<()> {
+        let mut value = tagged(tag);
+        value.float(2, x as f32);
+        self.write_summary(step, value)
+    }
+
+    /// Writes the distribution of `values`, which must not be empty.
+    pub fn histogram(&mut self, tag: &str, step: u64, values: &[f64]) -> Result<()> {
+        let mut value = tagged(tag);
+        value.message(5, histogram(values));
+        self.write_summary(step, value)
+    }
+
+    /// Writes `v` rendered as a `w`x`h` grayscale image, like `img::save` does.
+    pub fn image(&mut self, tag: &str, step: u64, v: &[f64], w: u32, h: u32) -> Result<()> {
+        let mut image = Message::new();
+        image.int(1, h as i64);
+        image.int(2, w as i64);
+        image.int(3, 1);
+        image.bytes(4, &img::png(v, w, h)?);
+        let mut value = tagged(tag);
+        value.message(4, image);
+        self.write_summary(step, value)
+    }
+
+    /// Writes the histograms of the weights of every layer of `net`, tagged `weights/layer_N`.
+    pub fn weights(&mut self, step: u64, net: &MultilayerPerceptron) -> Result<()> {
+        for (i, layer) in net.layers.iter().enumerate() {
+            self.histogram(&format!("weights/layer_{}", i), step, layer.weights.as_vector())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered events to the file.
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush().map_err(|e| Error::io(&self.path, e))
+    }
+}
+
+#[test]
+fn test_crc32c() {
+    assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    assert_eq!(crc32c(b""), 0);
+}
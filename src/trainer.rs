@@ -0,0 +1,256 @@
+//! Supervised learning of classifiers, multi-label classifiers and regressors.
+
+use rand;
+use std::collections::HashMap;
+use augment::Augmentation;
+use balance::{BalancedSampler, ClassWeighting};
+use activation_func::{ActivationFunctionEnum, Linear, Sigmoid, Tanh};
+use dataset::{self, Dataset};
+use multilayer_perceptron::{BatchStats, Loss, MultilayerPerceptron, NetFile, Task};
+use preprocess::Pipeline;
+use util;
+use error::{Error, Result};
+
+/// What newly created nets learn.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// The labels of the examples, with an output neuron for each of them.
+    Classification,
+    /// The targets of the examples, with a linear output neuron for each of them.
+    Regression,
+    /// The label sets of the examples, with a sigmoid output neuron for each label found in them.
+    MultiLabel,
+}
+
+/// How the examples learnt during an epoch are drawn.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Distinct examples, every one as likely as the others.
+    Uniform,
+    /// Examples of every label equally often, see `BalancedSampler`.
+    Balanced,
+}
+
+/// Settings of a supervised learning run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrainConfig {
+    /// Learning rate of newly created nets. Nets that are trained further keep their own.
+    pub learning_rate: f64,
+    /// Number of neurons in the hidden layer of newly created nets.
+    pub hidden_neurons: usize,
+    /// Fraction of the dataset that is used during each epoch.
+    pub sample_ratio: f64,
+    pub sampling: Sampling,
+    /// How much the examples of every label count, by the labels of the whole dataset.
+    pub class_weighting: ClassWeighting,
+    pub max_epochs: u64,
+    /// Whether to learn each batch on all the available threads.
+    pub parallel: bool,
+    /// Seed of the initial weights, the samples and their augmentation, random if not set.
+    pub seed: Option<usize>,
+    pub mode: Mode,
+    /// Loss of classifiers and regressors. Multi-label classifiers always minimize the cross-entropy.
+    pub loss: Loss,
+    /// Preprocessing of the image files, saved in newly created nets. The datasets must be opened with it.
+    pub preprocessing: Pipeline,
+    /// Random transformations of the sampled examples, none by default.
+    pub augmentation: Augmentation,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        TrainConfig {
+            learning_rate: 0.1,
+            hidden_neurons: 200,
+            sample_ratio: 0.2,
+            sampling: Sampling::Uniform,
+            class_weighting: ClassWeighting::Unweighted,
+            max_epochs: 50,
+            parallel: true,
+            seed: None,
+            mode: Mode::Classification,
+            loss: Loss::SquaredError,
+            preprocessing: Pipeline::default(),
+            augmentation: Augmentation::default(),
+        }
+    }
+}
+
+/// What happened during an epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochStats {
+    /// Number of the epoch, counted from 0.
+    pub epoch: u64,
+    pub learning_rate: f64,
+    pub batch: BatchStats,
+}
+
+pub struct Trainer {
+    pub config: TrainConfig,
+}
+
+impl Trainer {
+    pub fn new(config: TrainConfig) -> Trainer {
+        Trainer { config: config }
+    }
+
+    /// Creates an untrained net with one output neuron for every label in `data`, for every label of its label sets
+    /// in multi-label mode, or for every target in regression mode.
+    pub fn new_net<D: Dataset + ?Sized>(&self, data: &D) -> Result<NetFile> {
+        if data.is_empty() {
+            return Err(Error::EmptyDataset { source: "training set".into() });
+        }
+        let inputs = data.input_len();
+        let new_perc = |outputs: (usize, ActivationFunctionEnum)| MultilayerPerceptron::with_rng(
+            self.config.learning_rate,
+            inputs,
+            &[
+                (self.config.hidden_neurons, Tanh(1.0).into()),
+                outputs
+            ],
+            &mut util::rng(self.config.seed, 0)
+        );
+
+        let mut net = match self.config.mode {
+            Mode::Classification => {
+                let labels = data.labels();
+                let perc = new_perc((labels.len(), Tanh(1.0).into()));
+                NetFile::new(perc, labels.into_iter().enumerate().collect())
+            }
+            Mode::Regression => {
+                let targets = data.target_names();
+                if targets.is_empty() {
+                    return Err(Error::argument("training set", "the examples have no targets to regress"));
+                }
+                let perc = new_perc((targets.len(), Linear(1.0).into()));
+                NetFile::regressor(perc, targets)
+            }
+            Mode::MultiLabel => {
+                let labels = data.set_labels();
+                let perc = new_perc((labels.len(), Sigmoid(1.0).into()));
+                NetFile::multi_label(perc, labels.into_iter().enumerate().collect())
+            }
+        };
+        net.preprocessing = self.config.preprocessing.clone();
+        Ok(net)
+    }
+
+    /// Learns `net` on `data`, calling `on_epoch` after every epoch and stopping at its first error. Only the examples
+    /// sampled for an epoch are read, and they are augmented before being learned.
+    pub fn train<D, F>(&self, net: &mut NetFile, data: &D, mut on_epoch: F) -> Result<()>
+        where D: Dataset + ?Sized, F: FnMut(&EpochStats, &NetFile) -> Result<()>
+    {
+        let one_hot = one_hot_targets(&net.labels);
+
+        if data.input_len() != net.num_inputs() {
+            return Err(Error::shape("training set", net.num_inputs(), data.input_len()));
+        }
+        match net.task {
+            Task::Classification => {
+                if let Some(label) = data.labels().into_iter().find(|label| !one_hot.contains_key(label.as_str())) {
+                    return Err(Error::argument("net", format!("net has no output for label {:?}", label)));
+                }
+            }
+            Task::Regression(ref targets) => {
+                if data.target_names() != *targets {
+                    return Err(Error::argument("training set", format!("the net outputs the targets {:?}, not {:?}",
+                                                                       targets, data.target_names())));
+                }
+            }
+            Task::MultiLabel(_) => {
+                if let Some(label) = data.set_labels().into_iter().find(|label| !one_hot.contains_key(label.as_str())) {
+                    return Err(Error::argument("net", format!("net has no output for label {:?}", label)));
+                }
+            }
+            Task::Autoencoder => {
+                return Err(Error::argument("net", "autoencoders are learnt by the autoencoder subcommand"));
+            }
+        }
+        let loss = match net.task {
+            Task::MultiLabel(_) => Loss::CrossEntropy,
+            _ => self.config.loss,
+        };
+        // the delta of the cross-entropy leaves out the derivative of the output activation, that of Sigmoid(1.0)
+        let output: ActivationFunctionEnum = Sigmoid(1.0).into();
+        if loss == Loss::CrossEntropy && net.net.layers.last().map(|l| l.activation_function) != Some(output) {
+            return Err(Error::argument("net", "the cross-entropy needs an output layer of Sigmoid(1.0)"));
+        }
+
+        let sample_amt = (self.config.sample_ratio * data.len() as f64) as usize;
+        if sample_amt == 0 {
+            return Err(Error::argument("sample ratio", format!("{} of {} examples is an empty sample",
+                                                               self.config.sample_ratio, data.len())));
+        }
+
+        let augmentation = &self.config.augmentation;
+        let shape = data.shape();
+        augmentation.check(&shape)?;
+
+        let class_weights = self.config.class_weighting.weights(data);
+        let sampler = match self.config.sampling {
+            Sampling::Uniform => None,
+            Sampling::Balanced => Some(BalancedSampler::new(data)),
+        };
+
+        let mut rng = util::rng(self.config.seed, 1);
+        let mut augmentation_rng = util::rng(self.config.seed, 2);
+        for epoch in 0..self.config.max_epochs {
+            let indices = match sampler {
+                Some(ref sampler) => sampler.sample(&mut rng, sample_amt),
+                None => rand::sample(&mut rng, 0..data.len(), sample_amt),
+            };
+            let examples = dataset::load_batch(data, &indices)?;
+            for (&i, &(ref input, ref label)) in indices.iter().zip(&examples) {
+                if input.len() != net.num_inputs() {
+                    return Err(Error::shape(format!("training example {} ({})", i, label), net.num_inputs(), input.len()));
+                }
+            }
+
+            let mut targets: Vec<Vec<f64>> = match net.task {
+                Task::Classification => {
+                    examples.iter().map(|&(_, ref label)| one_hot[label.as_str()].clone()).collect()
+                }
+                Task::Regression(_) => dataset::load_targets(data, &indices)?,
+                Task::MultiLabel(_) => indices.iter().map(|&i| multi_hot(&one_hot, &data.label_set(i))).collect(),
+                Task::Autoencoder => unreachable!(),
+            };
+            let mut inputs: Vec<Vec<f64>> = examples.into_iter().map(|(input, _)| input).collect();
+            if !augmentation.is_none() {
+                augmentation.apply(&shape, &mut inputs, &mut targets, &mut augmentation_rng);
+            }
+            let sample: Vec<(&[f64], &[f64])> = inputs.iter().zip(&targets)
+                .map(|(input, target)| (&input[..], &target[..]))
+                .collect();
+            let batch = match class_weights {
+                Some(ref class_weights) => {
+                    let weights: Vec<f64> = indices.iter().map(|&i| class_weights[data.label(i)]).collect();
+                    net.net.learn_weighted_batch(&sample, &weights, loss, self.config.parallel)
+                }
+                None => net.net.learn_batch_with_loss(&sample, loss, self.config.parallel),
+            };
+            on_epoch(&EpochStats { epoch: epoch, learning_rate: net.net.learning_rate, batch: batch }, net)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps every label to the desired output of the net: 1.0 on the label's neuron, 0.0 elsewhere.
+pub fn one_hot_targets(neuron_to_label: &HashMap<usize, String>) -> HashMap<String, Vec<f64>> {
+    neuron_to_label.iter().map(|(&i, label)| {
+        let target = (0..neuron_to_label.len())
+            .map(|j| if j == i { 1.0 } else { 0.0 }).collect();
+        (label.clone(), target)
+    }).collect()
+}
+
+/// The desired output of a multi-label net for the label set `labels`: 1.0 on the neurons of all of them.
+pub fn multi_hot(one_hot: &HashMap<String, Vec<f64>>, labels: &[String]) -> Vec<f64> {
+    let mut target = vec![0.0; one_hot.len()];
+    for label in labels {
+        for (t, &x) in target.iter_mut().zip(&one_hot[label.as_str()]) {
+            *t = t.max(x);
+        }
+    }
+    target
+}
@@ -2,6 +2,7 @@ use std::error::Error;
 use std::fmt::Display;
 use std::fmt;
 use std::convert::From;
+use rand::{self, Rng, SeedableRng, StdRng};
 
 #[derive(Debug, Copy, Clone)]
 pub struct DontCare;
@@ -20,4 +21,12 @@ macro_rules! catch {
 #[macro_export]
 macro_rules! ignore_err {
     ($($tts:tt)*) => { {let x: ::std::result::Result<_, $crate::util::DontCare> = (|| {let x = {$($tts)*}?; ::std::result::Result::Ok(x)})(); x.ok()} };
-}
\ No newline at end of file
+}
+/// A generator seeded with `seed` and `stream`, so that the different uses of a seed draw different numbers,
+/// or a randomly seeded one if there's no seed.
+pub fn rng(seed: Option<usize>, stream: usize) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::from_seed(&[seed, stream][..]),
+        None => rand::thread_rng().gen(),
+    }
+}